@@ -0,0 +1,11 @@
+-- 貸出期限（返却予定日時）を追加する
+-- 既存の貸出は貸出日時から 14 日後を期限とみなす
+ALTER TABLE checkouts ADD COLUMN due_at TIMESTAMP(3) WITH TIME ZONE;
+UPDATE checkouts SET due_at = checked_out_at + INTERVAL '14 days';
+ALTER TABLE checkouts ALTER COLUMN due_at SET NOT NULL;
+
+CREATE INDEX IF NOT EXISTS checkouts_due_at_idx ON checkouts(due_at);
+
+ALTER TABLE returned_checkouts ADD COLUMN due_at TIMESTAMP(3) WITH TIME ZONE;
+UPDATE returned_checkouts SET due_at = checked_out_at + INTERVAL '14 days';
+ALTER TABLE returned_checkouts ALTER COLUMN due_at SET NOT NULL;
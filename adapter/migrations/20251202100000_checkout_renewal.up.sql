@@ -0,0 +1,3 @@
+-- 貸出の延長回数を記録する
+ALTER TABLE checkouts ADD COLUMN renewal_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE returned_checkouts ADD COLUMN renewal_count INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,17 @@
+-- 貸出中の蔵書に対する予約（取り置き待ち）の列
+-- 返却されると先頭の予約者に取り置き期限 hold_expires_at が設定され、
+-- 期限までの間はその予約者だけが蔵書を借りられる
+CREATE TABLE IF NOT EXISTS reservations (
+    reservation_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    book_id UUID NOT NULL,
+    user_id UUID NOT NULL,
+    reserved_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3),
+    hold_expires_at TIMESTAMP(3) WITH TIME ZONE NULL,
+
+    UNIQUE (book_id, user_id),
+    FOREIGN KEY (book_id) REFERENCES books(book_id) ON UPDATE CASCADE ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(user_id) ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS reservations_book_id_reserved_at_idx
+    ON reservations(book_id, reserved_at);
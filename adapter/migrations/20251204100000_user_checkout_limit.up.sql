@@ -0,0 +1,2 @@
+-- ユーザーごとに同時に借りられる冊数の上限を上書きする（NULL の場合はロールごとの上限を使う）
+ALTER TABLE users ADD COLUMN checkout_limit INTEGER NULL CHECK (checkout_limit >= 0);
@@ -0,0 +1,3 @@
+ALTER TABLE returned_checkouts DROP COLUMN IF EXISTS return_operator_id;
+ALTER TABLE returned_checkouts DROP COLUMN IF EXISTS checkout_operator_id;
+ALTER TABLE checkouts DROP COLUMN IF EXISTS checkout_operator_id;
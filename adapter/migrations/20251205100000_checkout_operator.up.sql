@@ -0,0 +1,10 @@
+-- 貸出・返却の操作を実際に行ったユーザーを記録する
+-- 管理者が代理で貸出・返却した場合は借りたユーザー（user_id）と異なる
+ALTER TABLE checkouts ADD COLUMN checkout_operator_id UUID NULL
+    REFERENCES users(user_id) ON UPDATE CASCADE ON DELETE SET NULL;
+UPDATE checkouts SET checkout_operator_id = user_id;
+
+ALTER TABLE returned_checkouts ADD COLUMN checkout_operator_id UUID NULL;
+ALTER TABLE returned_checkouts ADD COLUMN return_operator_id UUID NULL;
+UPDATE returned_checkouts
+    SET checkout_operator_id = user_id, return_operator_id = user_id;
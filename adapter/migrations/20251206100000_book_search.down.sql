@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS books_author_trgm_idx;
+DROP INDEX IF EXISTS books_search_document_trgm_idx;
+DROP INDEX IF EXISTS books_search_document_fts_idx;
+
+ALTER TABLE books DROP COLUMN IF EXISTS search_document;
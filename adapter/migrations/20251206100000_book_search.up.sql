@@ -0,0 +1,18 @@
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-- 全文検索・部分一致検索の対象とする文字列（タイトル・著者名・ISBN・説明）
+ALTER TABLE books
+    ADD COLUMN search_document TEXT GENERATED ALWAYS AS (
+        title || ' ' || author || ' ' || isbn || ' ' || description
+    ) STORED;
+
+-- 空白区切りの単語による全文検索用
+CREATE INDEX IF NOT EXISTS books_search_document_fts_idx
+    ON books USING GIN (to_tsvector('simple', search_document));
+
+-- 日本語のタイトルなど空白で区切られない文字列の部分一致・あいまい検索用
+CREATE INDEX IF NOT EXISTS books_search_document_trgm_idx
+    ON books USING GIN (search_document gin_trgm_ops);
+
+CREATE INDEX IF NOT EXISTS books_author_trgm_idx
+    ON books USING GIN (author gin_trgm_ops);
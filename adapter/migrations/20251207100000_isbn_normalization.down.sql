@@ -0,0 +1,2 @@
+-- 正規化前の表記には戻せないため、インデックスのみ削除する
+DROP INDEX IF EXISTS books_isbn_idx;
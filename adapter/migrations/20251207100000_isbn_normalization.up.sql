@@ -0,0 +1,59 @@
+-- 登録済みの ISBN を、ハイフンなしの ISBN-13 に正規化する
+-- ISBN として解釈できない値が残っていると、アプリケーションが蔵書を読み込めなくなるため、
+-- そのような蔵書がある場合はマイグレーションを中止し、修正が必要な蔵書を一覧で示す
+DO $$
+DECLARE
+    r RECORD;
+    digits TEXT;
+    total INTEGER;
+    i INTEGER;
+    invalid TEXT[] := '{}';
+BEGIN
+    FOR r IN SELECT book_id, isbn FROM books LOOP
+        digits := upper(regexp_replace(r.isbn, '[- ]', '', 'g'));
+
+        IF digits ~ '^[0-9]{9}[0-9X]$' THEN
+            total := 0;
+            FOR i IN 1..10 LOOP
+                total := total + (11 - i) * CASE
+                    WHEN substr(digits, i, 1) = 'X' THEN 10
+                    ELSE substr(digits, i, 1)::INTEGER
+                END;
+            END LOOP;
+            IF total % 11 <> 0 THEN
+                invalid := invalid || format('%s (%s)', r.book_id, r.isbn);
+                CONTINUE;
+            END IF;
+            digits := '978' || substr(digits, 1, 9);
+            total := 0;
+            FOR i IN 1..12 LOOP
+                total := total + substr(digits, i, 1)::INTEGER * CASE WHEN i % 2 = 1 THEN 1 ELSE 3 END;
+            END LOOP;
+            digits := digits || ((10 - total % 10) % 10)::TEXT;
+        ELSIF digits ~ '^97[89][0-9]{10}$' THEN
+            total := 0;
+            FOR i IN 1..12 LOOP
+                total := total + substr(digits, i, 1)::INTEGER * CASE WHEN i % 2 = 1 THEN 1 ELSE 3 END;
+            END LOOP;
+            IF (10 - total % 10) % 10 <> substr(digits, 13, 1)::INTEGER THEN
+                invalid := invalid || format('%s (%s)', r.book_id, r.isbn);
+                CONTINUE;
+            END IF;
+        ELSE
+            invalid := invalid || format('%s (%s)', r.book_id, r.isbn);
+            CONTINUE;
+        END IF;
+
+        IF digits <> r.isbn THEN
+            UPDATE books SET isbn = digits WHERE book_id = r.book_id;
+        END IF;
+    END LOOP;
+
+    IF cardinality(invalid) > 0 THEN
+        RAISE EXCEPTION 'ISBN として解釈できない蔵書があります。ISBN を修正してから再実行してください: %',
+            array_to_string(invalid, ', ');
+    END IF;
+END
+$$;
+
+CREATE INDEX IF NOT EXISTS books_isbn_idx ON books (isbn);
@@ -0,0 +1,37 @@
+-- 蔵書ごとに 1 冊のコピーだけを残す（貸出中のコピーを優先し、同じ条件なら最も古いコピーを残す）
+-- 削除するコピーの貸出は、戻す時点で返却されたものとして貸出履歴に移す
+CREATE TEMPORARY TABLE kept_book_copies ON COMMIT DROP AS
+SELECT DISTINCT ON (bc.book_id) bc.copy_id
+FROM book_copies AS bc
+ORDER BY
+    bc.book_id,
+    EXISTS(SELECT 1 FROM checkouts AS c WHERE c.copy_id = bc.copy_id) DESC,
+    bc.created_at ASC,
+    bc.copy_id ASC;
+
+INSERT INTO returned_checkouts
+(
+    checkout_id, book_id, copy_id, user_id, checkout_operator_id,
+    checked_out_at, due_at, renewal_count, returned_at
+)
+SELECT
+    checkout_id, book_id, copy_id, user_id, checkout_operator_id,
+    checked_out_at, due_at, renewal_count, CURRENT_TIMESTAMP(3)
+FROM checkouts
+WHERE copy_id NOT IN (SELECT copy_id FROM kept_book_copies);
+
+DELETE FROM book_copies
+WHERE copy_id NOT IN (SELECT copy_id FROM kept_book_copies);
+
+ALTER TABLE returned_checkouts DROP COLUMN IF EXISTS copy_id;
+
+DROP INDEX IF EXISTS checkouts_book_id_idx;
+ALTER TABLE checkouts
+    DROP CONSTRAINT IF EXISTS checkouts_copy_id_fkey,
+    DROP CONSTRAINT IF EXISTS checkouts_copy_id_key,
+    DROP COLUMN IF EXISTS copy_id,
+    ADD CONSTRAINT checkouts_book_id_key UNIQUE (book_id);
+
+DROP TABLE IF EXISTS book_copies;
+DROP FUNCTION IF EXISTS next_book_copy_barcode();
+DROP SEQUENCE IF EXISTS book_copy_barcode_seq;
@@ -0,0 +1,45 @@
+-- 蔵書（書誌情報）ごとに複数の現物（コピー）を持てるようにする
+CREATE SEQUENCE IF NOT EXISTS book_copy_barcode_seq;
+
+-- バーコードを指定せずにコピーを登録した場合に採番するバーコード
+CREATE OR REPLACE FUNCTION next_book_copy_barcode() RETURNS TEXT AS '
+    SELECT ''BC'' || LPAD(nextval(''book_copy_barcode_seq'')::TEXT, 8, ''0'');
+' LANGUAGE SQL;
+
+CREATE TABLE IF NOT EXISTS book_copies (
+    copy_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    book_id UUID NOT NULL,
+    barcode VARCHAR(255) NOT NULL UNIQUE DEFAULT next_book_copy_barcode(),
+    user_id UUID NOT NULL,
+    created_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3),
+    updated_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3),
+
+    FOREIGN KEY (book_id) REFERENCES books(book_id) ON UPDATE CASCADE ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(user_id) ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS book_copies_book_id_idx ON book_copies (book_id);
+
+CREATE TRIGGER book_copies_updated_at_trigger
+    BEFORE UPDATE ON book_copies FOR EACH ROW
+    EXECUTE FUNCTION set_updated_at();
+
+-- 登録済みの蔵書には、蔵書の所有者が所有するコピーを 1 冊ずつ作る
+INSERT INTO book_copies (book_id, user_id, created_at)
+SELECT book_id, user_id, created_at FROM books ORDER BY created_at;
+
+-- 貸出は蔵書ではなくコピーに対して行う
+ALTER TABLE checkouts ADD COLUMN copy_id UUID NULL;
+UPDATE checkouts AS c SET copy_id = bc.copy_id
+FROM book_copies AS bc WHERE bc.book_id = c.book_id;
+ALTER TABLE checkouts
+    ALTER COLUMN copy_id SET NOT NULL,
+    ADD CONSTRAINT checkouts_copy_id_key UNIQUE (copy_id),
+    ADD CONSTRAINT checkouts_copy_id_fkey FOREIGN KEY (copy_id)
+        REFERENCES book_copies(copy_id) ON UPDATE CASCADE ON DELETE CASCADE,
+    DROP CONSTRAINT checkouts_book_id_key;
+CREATE INDEX IF NOT EXISTS checkouts_book_id_idx ON checkouts (book_id);
+
+ALTER TABLE returned_checkouts ADD COLUMN copy_id UUID NULL;
+UPDATE returned_checkouts AS rc SET copy_id = bc.copy_id
+FROM book_copies AS bc WHERE bc.book_id = rc.book_id;
@@ -0,0 +1,3 @@
+ALTER TABLE books
+    DROP COLUMN IF EXISTS archive_reason,
+    DROP COLUMN IF EXISTS deleted_at;
@@ -0,0 +1,5 @@
+-- 蔵書は物理削除せずにアーカイブする（貸出履歴を残すため）
+-- deleted_at が NULL でない蔵書はアーカイブ済みとして一覧などから除外する
+ALTER TABLE books
+    ADD COLUMN deleted_at TIMESTAMP(3) WITH TIME ZONE NULL,
+    ADD COLUMN archive_reason VARCHAR(255) NULL;
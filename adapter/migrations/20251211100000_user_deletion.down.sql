@@ -0,0 +1,16 @@
+ALTER TABLE checkouts
+    DROP CONSTRAINT checkouts_user_id_fkey,
+    ADD CONSTRAINT checkouts_user_id_fkey FOREIGN KEY (user_id)
+        REFERENCES users(user_id) ON UPDATE CASCADE ON DELETE CASCADE;
+ALTER TABLE book_copies
+    DROP CONSTRAINT book_copies_user_id_fkey,
+    ADD CONSTRAINT book_copies_user_id_fkey FOREIGN KEY (user_id)
+        REFERENCES users(user_id) ON UPDATE CASCADE ON DELETE CASCADE;
+ALTER TABLE books
+    DROP CONSTRAINT books_user_id_fkey,
+    ADD CONSTRAINT books_user_id_fkey FOREIGN KEY (user_id)
+        REFERENCES users(user_id) ON UPDATE CASCADE ON DELETE CASCADE;
+
+-- 匿名化した貸出履歴は元に戻せないため削除する
+DELETE FROM returned_checkouts WHERE user_id IS NULL;
+ALTER TABLE returned_checkouts ALTER COLUMN user_id SET NOT NULL;
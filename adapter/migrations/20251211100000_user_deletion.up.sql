@@ -0,0 +1,17 @@
+-- ユーザーを削除しても、返却済みの貸出履歴は借りたユーザーを匿名化して残す
+ALTER TABLE returned_checkouts ALTER COLUMN user_id DROP NOT NULL;
+
+-- ユーザーの削除で蔵書・コピー・貸出が一緒に削除されないようにする
+-- （削除する前に、蔵書とコピーの引き継ぎと、貸出中の蔵書の返却が必要）
+ALTER TABLE books
+    DROP CONSTRAINT books_user_id_fkey,
+    ADD CONSTRAINT books_user_id_fkey FOREIGN KEY (user_id)
+        REFERENCES users(user_id) ON UPDATE CASCADE ON DELETE RESTRICT;
+ALTER TABLE book_copies
+    DROP CONSTRAINT book_copies_user_id_fkey,
+    ADD CONSTRAINT book_copies_user_id_fkey FOREIGN KEY (user_id)
+        REFERENCES users(user_id) ON UPDATE CASCADE ON DELETE RESTRICT;
+ALTER TABLE checkouts
+    DROP CONSTRAINT checkouts_user_id_fkey,
+    ADD CONSTRAINT checkouts_user_id_fkey FOREIGN KEY (user_id)
+        REFERENCES users(user_id) ON UPDATE CASCADE ON DELETE RESTRICT;
@@ -0,0 +1,14 @@
+-- 蔵書の更新の競合を検出するためのバージョン（ETag として公開する）
+-- 蔵書の行を更新するたびに 1 ずつ増える
+ALTER TABLE books ADD COLUMN version BIGINT NOT NULL DEFAULT 1;
+
+CREATE OR REPLACE FUNCTION increment_version() RETURNS trigger AS '
+    BEGIN
+        new.version := old.version + 1;
+        return new;
+    END;
+' LANGUAGE 'plpgsql';
+
+CREATE TRIGGER books_version_trigger
+    BEFORE UPDATE ON books FOR EACH ROW
+    EXECUTE FUNCTION increment_version();
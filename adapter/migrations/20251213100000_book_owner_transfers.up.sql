@@ -0,0 +1,18 @@
+-- 蔵書の所有者の変更履歴
+-- ユーザーを削除しても履歴は残し、削除されたユーザーは NULL（匿名）とする
+CREATE TABLE IF NOT EXISTS book_owner_transfers (
+    book_owner_transfer_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    book_id UUID NOT NULL,
+    from_user_id UUID NULL,
+    to_user_id UUID NULL,
+    transferred_by UUID NULL,
+    transferred_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3),
+
+    FOREIGN KEY (book_id) REFERENCES books(book_id) ON UPDATE CASCADE ON DELETE CASCADE,
+    FOREIGN KEY (from_user_id) REFERENCES users(user_id) ON UPDATE CASCADE ON DELETE SET NULL,
+    FOREIGN KEY (to_user_id) REFERENCES users(user_id) ON UPDATE CASCADE ON DELETE SET NULL,
+    FOREIGN KEY (transferred_by) REFERENCES users(user_id) ON UPDATE CASCADE ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS book_owner_transfers_book_id_idx
+    ON book_owner_transfers (book_id, transferred_at);
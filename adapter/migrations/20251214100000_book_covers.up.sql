@@ -0,0 +1,5 @@
+-- 蔵書の書影
+-- 画像はファイルの保存先に cover_id ごとのキーで保存し、ここには ID と形式だけを持つ
+ALTER TABLE books
+    ADD COLUMN cover_id UUID NULL,
+    ADD COLUMN cover_content_type VARCHAR(255) NULL;
@@ -3,9 +3,21 @@ use shared::{
     error::{AppError, AppResult},
 };
 use sqlx::{PgPool, postgres::PgConnectOptions};
+use std::{future::Future, pin::Pin, time::Duration};
 
 pub mod model;
 
+pub type PgTransaction = sqlx::Transaction<'static, sqlx::Postgres>;
+
+// transaction_serializable に渡すクロージャが返す Future
+pub type TransactionFuture<'t, T> = Pin<Box<dyn Future<Output = AppResult<T>> + Send + 't>>;
+
+// 直列化の失敗時にトランザクションを試行する最大回数
+const MAX_TRANSACTION_ATTEMPTS: u32 = 8;
+// リトライまでの待ち時間の初期値と上限（試行ごとに倍にする）
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(5);
+const RETRY_MAX_DELAY: Duration = Duration::from_millis(200);
+
 fn make_pg_connect_options(cfg: &DatabaseConfig) -> PgConnectOptions {
     PgConnectOptions::new()
         .host(&cfg.host)
@@ -30,6 +42,57 @@ impl ConnectionPool {
     pub async fn begin(&self) -> AppResult<sqlx::Transaction<'_, sqlx::Postgres>> {
         self.0.begin().await.map_err(AppError::TransactionError)
     }
+
+    // SERIALIZABLE なトランザクション内で `f` を実行してコミットする。
+    // 直列化の失敗やデッドロックでトランザクションが中断された場合は、
+    // 待ち時間を空けながら上限回数まで `f` ごとやり直す。
+    pub async fn transaction_serializable<T, F>(&self, mut f: F) -> AppResult<T>
+    where
+        F: for<'t> FnMut(&'t mut PgTransaction) -> TransactionFuture<'t, T>,
+    {
+        let mut attempt = 1;
+        loop {
+            let res = async {
+                let mut tx = self.0.begin().await.map_err(AppError::TransactionError)?;
+                sqlx::query!("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE")
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(AppError::SpecificOperationError)?;
+                let value = f(&mut tx).await?;
+                tx.commit().await.map_err(AppError::TransactionError)?;
+                Ok(value)
+            }
+            .await;
+
+            match res {
+                Err(e) if attempt < MAX_TRANSACTION_ATTEMPTS && is_retryable(&e) => {
+                    tokio::time::sleep(retry_delay(attempt)).await;
+                    attempt += 1;
+                }
+                res => return res,
+            }
+        }
+    }
+}
+
+// 直列化の失敗（40001）とデッドロックの検出（40P01）はやり直せば成功しうるエラー
+fn is_retryable(e: &AppError) -> bool {
+    match e {
+        AppError::TransactionError(sqlx::Error::Database(db))
+        | AppError::SpecificOperationError(sqlx::Error::Database(db)) => {
+            matches!(db.code().as_deref(), Some("40001" | "40P01"))
+        }
+        _ => false,
+    }
+}
+
+// 同時にやり直したトランザクション同士が再び衝突しないよう、待ち時間にばらつきを持たせる
+fn retry_delay(attempt: u32) -> Duration {
+    let delay = RETRY_BASE_DELAY
+        .saturating_mul(1 << (attempt - 1).min(16))
+        .min(RETRY_MAX_DELAY);
+    let jitter = uuid::Uuid::new_v4().as_u128() % (delay.as_millis() + 1);
+    delay / 2 + Duration::from_millis(jitter as u64 / 2)
 }
 
 pub fn connect_database_with(cfg: &DatabaseConfig) -> ConnectionPool {
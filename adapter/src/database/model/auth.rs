@@ -1,9 +1,11 @@
-use std::str::FromStr;
-
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
 use kernel::model::{
-    auth::{AccessToken, event::CreateToken},
-    id::UserId,
+    auth::{AccessToken, RefreshToken, Session, event::CreateToken},
+    id::{SessionId, UserId},
 };
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
 use shared::error::{AppError, AppResult};
 
 use crate::redis::model::{RedisKey, RedisValue};
@@ -13,31 +15,135 @@ pub struct UserItem {
     pub password_hash: String,
 }
 
+// トークンを Redis のキーにするときのハッシュ関数
+// Redis を読める人がトークンをそのまま使えないよう、設定した秘密鍵で HMAC-SHA256 を計算する
+#[derive(Clone)]
+pub struct TokenHasher(Hmac<Sha256>);
+
+impl TokenHasher {
+    pub fn new(secret: &str) -> Self {
+        Self(Hmac::new_from_slice(secret.as_bytes()).expect("HMAC can take key of any size"))
+    }
+
+    fn hash(&self, token: &str) -> String {
+        let mut mac = self.0.clone();
+        mac.update(token.as_bytes());
+        hex::encode(mac.finalize().into_bytes())
+    }
+}
+
+// アクセストークンのハッシュ値をキーにする
 pub struct AuthorizationKey(String);
-pub struct AuthorizedUserId(UserId);
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthorizedUserId {
+    user_id: UserId,
+    session_id: SessionId,
+}
 
-pub fn from(event: CreateToken) -> (AuthorizationKey, AuthorizedUserId) {
-    (
-        AuthorizationKey(event.access_token),
-        AuthorizedUserId(event.user_id),
-    )
+// リフレッシュトークンのハッシュ値をキーにする
+pub struct RefreshTokenKey(String);
+pub struct AuthorizedSessionId(SessionId);
+
+// セッションと、その時点で有効なトークンのハッシュ値
+pub struct SessionKey(SessionId);
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionItem {
+    pub user_id: UserId,
+    pub access_token_hash: String,
+    pub refresh_token_hash: String,
+    // ローテーションで使用済みになったリフレッシュトークンのハッシュ値（古い順）
+    // 使用済みのトークンが再び使われたことを、推測したトークンと区別して検出するために使う
+    #[serde(default)]
+    pub consumed_refresh_tokens: Vec<ConsumedRefreshToken>,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
 }
 
-impl From<AuthorizationKey> for AccessToken {
-    fn from(key: AuthorizationKey) -> Self {
-        Self(key.0)
-    }
+// セッションごとに記録しておく使用済みのリフレッシュトークンの上限
+// 上限を超えた場合は、古いものから記録を消す
+pub const MAX_CONSUMED_REFRESH_TOKENS: usize = 32;
+
+// ローテーションで使用済みになったリフレッシュトークン
+// 使用済みになってからリフレッシュトークンの有効期間が過ぎたものは、再使用を検出しなくてよいため記録を消す
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsumedRefreshToken {
+    hash: String,
+    consumed_at: DateTime<Utc>,
 }
 
-impl From<AccessToken> for AuthorizationKey {
-    fn from(value: AccessToken) -> Self {
-        Self(value.0)
-    }
+// セッションが最後に使われた日時
+// リクエストのたびに更新するため、セッションとは別のキーに保存する
+pub struct SessionLastUsedKey(SessionId);
+pub struct SessionLastUsedAt(DateTime<Utc>);
+
+// ユーザーごとのセッション ID の集合
+pub struct UserSessionsKey(UserId);
+
+// ユーザーのセッションをまとめて終了した記録
+// 終了する前に読み込まれたセッションが、リフレッシュで作り直されないようにするために使う
+pub struct SessionsRevokedKey(UserId);
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionsRevoked {
+    revoked_at: DateTime<Utc>,
+    // 終了せずに残したセッション
+    kept_session_id: Option<SessionId>,
+}
+
+pub fn from(
+    event: CreateToken,
+    created_at: DateTime<Utc>,
+    consumed_refresh_tokens: Vec<ConsumedRefreshToken>,
+    hasher: &TokenHasher,
+) -> (
+    (AuthorizationKey, AuthorizedUserId),
+    (RefreshTokenKey, AuthorizedSessionId),
+    (SessionKey, SessionItem),
+) {
+    let CreateToken {
+        user_id,
+        session_id,
+        access_token,
+        refresh_token,
+        user_agent,
+        ip_address,
+    } = event;
+    let access_token_hash = hasher.hash(&access_token);
+    let refresh_token_hash = hasher.hash(&refresh_token);
+    (
+        (
+            AuthorizationKey(access_token_hash.clone()),
+            AuthorizedUserId {
+                user_id,
+                session_id,
+            },
+        ),
+        (
+            RefreshTokenKey(refresh_token_hash.clone()),
+            AuthorizedSessionId(session_id),
+        ),
+        (
+            SessionKey(session_id),
+            SessionItem {
+                user_id,
+                access_token_hash,
+                refresh_token_hash,
+                consumed_refresh_tokens,
+                user_agent,
+                ip_address,
+                created_at,
+            },
+        ),
+    )
 }
 
-impl From<&AccessToken> for AuthorizationKey {
-    fn from(value: &AccessToken) -> Self {
-        Self(value.0.to_string())
+impl AuthorizationKey {
+    pub fn new(access_token: &AccessToken, hasher: &TokenHasher) -> Self {
+        Self(hasher.hash(&access_token.0))
     }
 }
 
@@ -45,13 +151,13 @@ impl RedisKey for AuthorizationKey {
     type Value = AuthorizedUserId;
 
     fn inner(&self) -> String {
-        self.0.clone()
+        format!("access_token:{}", self.0)
     }
 }
 
 impl RedisValue for AuthorizedUserId {
     fn inner(&self) -> String {
-        self.0.to_string()
+        serde_json::to_string(self).unwrap_or_default()
     }
 }
 
@@ -59,14 +165,297 @@ impl TryFrom<String> for AuthorizedUserId {
     type Error = AppError;
 
     fn try_from(value: String) -> AppResult<Self> {
-        Ok(Self(UserId::from_str(&value).map_err(|e| {
-            AppError::ConversionEntityError(e.to_string())
-        })?))
+        serde_json::from_str(&value).map_err(|e| AppError::ConversionEntityError(e.to_string()))
     }
 }
 
 impl AuthorizedUserId {
-    pub fn into_inner(self) -> UserId {
+    pub fn user_id(&self) -> UserId {
+        self.user_id
+    }
+
+    pub fn session_id(&self) -> SessionId {
+        self.session_id
+    }
+}
+
+impl RefreshTokenKey {
+    pub fn new(refresh_token: &RefreshToken, hasher: &TokenHasher) -> Self {
+        Self(hasher.hash(&refresh_token.0))
+    }
+}
+
+impl RedisKey for RefreshTokenKey {
+    type Value = AuthorizedSessionId;
+
+    fn inner(&self) -> String {
+        format!("refresh_token:{}", self.0)
+    }
+}
+
+impl RedisValue for AuthorizedSessionId {
+    fn inner(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+impl TryFrom<String> for AuthorizedSessionId {
+    type Error = AppError;
+
+    fn try_from(value: String) -> AppResult<Self> {
+        Ok(Self(value.parse()?))
+    }
+}
+
+impl AuthorizedSessionId {
+    pub fn session_id(&self) -> SessionId {
+        self.0
+    }
+}
+
+impl From<SessionId> for SessionKey {
+    fn from(value: SessionId) -> Self {
+        Self(value)
+    }
+}
+
+impl RedisKey for SessionKey {
+    type Value = SessionItem;
+
+    fn inner(&self) -> String {
+        format!("session:{}", self.0)
+    }
+}
+
+impl RedisValue for SessionItem {
+    fn inner(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+impl TryFrom<String> for SessionItem {
+    type Error = AppError;
+
+    fn try_from(value: String) -> AppResult<Self> {
+        serde_json::from_str(&value).map_err(|e| AppError::ConversionEntityError(e.to_string()))
+    }
+}
+
+impl SessionItem {
+    pub fn access_token_key(&self) -> AuthorizationKey {
+        AuthorizationKey(self.access_token_hash.clone())
+    }
+
+    pub fn refresh_token_key(&self) -> RefreshTokenKey {
+        RefreshTokenKey(self.refresh_token_hash.clone())
+    }
+
+    // 指定したリフレッシュトークンが、このセッションでローテーションにより使用済みになったものかどうか
+    pub fn is_consumed_refresh_token(&self, key: &RefreshTokenKey) -> bool {
+        self.consumed_refresh_tokens.iter().any(|x| x.hash == key.0)
+    }
+
+    // 現在のリフレッシュトークンを使用済みとして加えた、ローテーション後のセッションに記録する一覧
+    // リフレッシュトークンの有効期間（秒）より前に使用済みになったものと、上限を超えた古いものは除く
+    pub fn consume_refresh_token(
+        &self,
+        consumed_at: DateTime<Utc>,
+        refresh_token_ttl: u64,
+    ) -> Vec<ConsumedRefreshToken> {
+        let expired_before = consumed_at
+            - chrono::Duration::seconds(refresh_token_ttl.try_into().unwrap_or(i64::MAX));
+        let mut consumed: Vec<_> = self
+            .consumed_refresh_tokens
+            .iter()
+            .filter(|x| x.consumed_at > expired_before)
+            .cloned()
+            .chain(std::iter::once(ConsumedRefreshToken {
+                hash: self.refresh_token_hash.clone(),
+                consumed_at,
+            }))
+            .collect();
+        let overflow = consumed.len().saturating_sub(MAX_CONSUMED_REFRESH_TOKENS);
+        consumed.drain(..overflow);
+        consumed
+    }
+
+    pub fn into_session(self, id: SessionId, last_used_at: Option<DateTime<Utc>>) -> Session {
+        let SessionItem {
+            user_agent,
+            ip_address,
+            created_at,
+            ..
+        } = self;
+        Session {
+            id,
+            user_agent,
+            ip_address,
+            created_at,
+            last_used_at: last_used_at.unwrap_or(created_at),
+        }
+    }
+}
+
+impl From<SessionId> for SessionLastUsedKey {
+    fn from(value: SessionId) -> Self {
+        Self(value)
+    }
+}
+
+impl RedisKey for SessionLastUsedKey {
+    type Value = SessionLastUsedAt;
+
+    fn inner(&self) -> String {
+        format!("session_last_used:{}", self.0)
+    }
+}
+
+impl RedisValue for SessionLastUsedAt {
+    fn inner(&self) -> String {
+        self.0.to_rfc3339()
+    }
+}
+
+impl TryFrom<String> for SessionLastUsedAt {
+    type Error = AppError;
+
+    fn try_from(value: String) -> AppResult<Self> {
+        DateTime::parse_from_rfc3339(&value)
+            .map(|x| Self(x.with_timezone(&Utc)))
+            .map_err(|e| AppError::ConversionEntityError(e.to_string()))
+    }
+}
+
+impl SessionLastUsedAt {
+    pub fn now() -> Self {
+        Self(Utc::now())
+    }
+
+    pub fn into_inner(self) -> DateTime<Utc> {
         self.0
     }
 }
+
+impl From<UserId> for UserSessionsKey {
+    fn from(value: UserId) -> Self {
+        Self(value)
+    }
+}
+
+impl RedisKey for UserSessionsKey {
+    type Value = AuthorizedSessionId;
+
+    fn inner(&self) -> String {
+        format!("user_sessions:{}", self.0)
+    }
+}
+
+impl From<SessionId> for AuthorizedSessionId {
+    fn from(value: SessionId) -> Self {
+        Self(value)
+    }
+}
+
+impl From<UserId> for SessionsRevokedKey {
+    fn from(value: UserId) -> Self {
+        Self(value)
+    }
+}
+
+impl RedisKey for SessionsRevokedKey {
+    type Value = SessionsRevoked;
+
+    fn inner(&self) -> String {
+        format!("sessions_revoked:{}", self.0)
+    }
+}
+
+impl RedisValue for SessionsRevoked {
+    fn inner(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+impl TryFrom<String> for SessionsRevoked {
+    type Error = AppError;
+
+    fn try_from(value: String) -> AppResult<Self> {
+        serde_json::from_str(&value).map_err(|e| AppError::ConversionEntityError(e.to_string()))
+    }
+}
+
+impl SessionsRevoked {
+    pub fn now(kept_session_id: Option<SessionId>) -> Self {
+        Self {
+            revoked_at: Utc::now(),
+            kept_session_id,
+        }
+    }
+
+    // 指定したセッションが、この記録の時点で終了したセッションかどうか
+    pub fn revokes(&self, session_id: SessionId, created_at: DateTime<Utc>) -> bool {
+        created_at <= self.revoked_at && self.kept_session_id != Some(session_id)
+    }
+}
+
+// ログインに失敗した回数
+// アカウントはメールアドレスのハッシュ値で、IP アドレスはそのままの値で区別する
+pub struct LoginFailureKey(String);
+pub struct LoginFailureCount(u64);
+
+// ログインの失敗が続いたことによるロック
+// ロックしている間だけ存在し、値はロックしたときの失敗回数
+pub struct LoginLockKey(String);
+
+impl LoginFailureKey {
+    pub fn account(email: &str, hasher: &TokenHasher) -> Self {
+        Self(format!("account:{}", hasher.hash(&email.to_lowercase())))
+    }
+
+    pub fn ip_address(ip_address: &str) -> Self {
+        Self(format!("ip:{}", ip_address))
+    }
+
+    pub fn lock_key(&self) -> LoginLockKey {
+        LoginLockKey(self.0.clone())
+    }
+}
+
+impl RedisKey for LoginFailureKey {
+    type Value = LoginFailureCount;
+
+    fn inner(&self) -> String {
+        format!("login_failure:{}", self.0)
+    }
+}
+
+impl RedisKey for LoginLockKey {
+    type Value = LoginFailureCount;
+
+    fn inner(&self) -> String {
+        format!("login_lock:{}", self.0)
+    }
+}
+
+impl RedisValue for LoginFailureCount {
+    fn inner(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+impl TryFrom<String> for LoginFailureCount {
+    type Error = AppError;
+
+    fn try_from(value: String) -> AppResult<Self> {
+        value
+            .parse()
+            .map(Self)
+            .map_err(|e: std::num::ParseIntError| AppError::ConversionEntityError(e.to_string()))
+    }
+}
+
+impl From<u64> for LoginFailureCount {
+    fn from(value: u64) -> Self {
+        Self(value)
+    }
+}
@@ -1,7 +1,9 @@
 use chrono::{DateTime, Utc};
 use kernel::model::{
-    book::{Book, Checkout},
-    id::{BookId, CheckoutId, UserId},
+    book::{Book, BookArchive, BookCopy, BookCover, BookOwnerTransfer, Checkout},
+    id::{BookCopyId, BookId, CheckoutId, CoverId, UserId},
+    isbn::Isbn,
+    tag::Tag,
     user::{BookOwner, CheckoutUser},
 };
 use uuid::Uuid;
@@ -10,14 +12,19 @@ pub struct BookRow {
     pub book_id: Uuid,
     pub title: String,
     pub author: String,
-    pub isbn: String,
+    pub isbn: Isbn,
     pub description: String,
     pub owned_by: UserId,
     pub owner_name: String,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub archive_reason: Option<String>,
+    pub version: i64,
+    pub cover_id: Option<CoverId>,
+    pub cover_content_type: Option<String>,
 }
 
 impl BookRow {
-    pub fn into_book(self, checkout: Option<Checkout>) -> Book {
+    pub fn into_book(self, copies: Vec<BookCopy>, tags: Vec<Tag>) -> Book {
         let BookRow {
             book_id,
             title,
@@ -26,6 +33,11 @@ impl BookRow {
             description,
             owned_by,
             owner_name,
+            deleted_at,
+            archive_reason,
+            version,
+            cover_id,
+            cover_content_type,
         } = self;
 
         Book {
@@ -38,7 +50,54 @@ impl BookRow {
                 id: owned_by,
                 name: owner_name,
             },
-            checkout,
+            copies,
+            tags,
+            archive: deleted_at.map(|archived_at| BookArchive {
+                archived_at,
+                reason: archive_reason,
+            }),
+            version,
+            cover: cover_id
+                .zip(cover_content_type)
+                .map(|(id, content_type)| BookCover { id, content_type }),
+        }
+    }
+}
+
+// 削除されたユーザーは ID と名前が None になる
+pub struct BookOwnerTransferRow {
+    pub from_user_id: Option<UserId>,
+    pub from_user_name: Option<String>,
+    pub to_user_id: Option<UserId>,
+    pub to_user_name: Option<String>,
+    pub transferred_by: Option<UserId>,
+    pub transferred_by_name: Option<String>,
+    pub transferred_at: DateTime<Utc>,
+}
+
+impl From<BookOwnerTransferRow> for BookOwnerTransfer {
+    fn from(value: BookOwnerTransferRow) -> Self {
+        fn owner(id: Option<UserId>, name: Option<String>) -> BookOwner {
+            match (id, name) {
+                (Some(id), Some(name)) => BookOwner { id, name },
+                _ => BookOwner::anonymized(),
+            }
+        }
+
+        let BookOwnerTransferRow {
+            from_user_id,
+            from_user_name,
+            to_user_id,
+            to_user_name,
+            transferred_by,
+            transferred_by_name,
+            transferred_at,
+        } = value;
+        BookOwnerTransfer {
+            from: owner(from_user_id, from_user_name),
+            to: owner(to_user_id, to_user_name),
+            transferred_by: owner(transferred_by, transferred_by_name),
+            transferred_at,
         }
     }
 }
@@ -46,33 +105,65 @@ impl BookRow {
 pub struct PaginatedBookRow {
     pub total: i64,
     pub id: BookId,
+    pub created_at: DateTime<Utc>,
 }
 
-pub struct BookCheckoutRow {
-    pub checkout_id: CheckoutId,
+// 蔵書のコピーと、その貸出状況を取得する際に使う型
+// コピーが貸出中でない場合は checkout_id 以降のフィールドが None になる
+pub struct BookCopyRow {
+    pub copy_id: BookCopyId,
     pub book_id: BookId,
-    pub user_id: UserId,
-    pub user_name: String,
-    pub checked_out_at: DateTime<Utc>,
+    pub barcode: String,
+    pub owned_by: UserId,
+    pub owner_name: String,
+    pub checkout_id: Option<CheckoutId>,
+    pub checked_out_by: Option<UserId>,
+    pub checked_out_by_name: Option<String>,
+    pub checked_out_at: Option<DateTime<Utc>>,
+    pub due_at: Option<DateTime<Utc>>,
 }
 
-impl From<BookCheckoutRow> for Checkout {
-    fn from(value: BookCheckoutRow) -> Self {
-        let BookCheckoutRow {
-            checkout_id,
+impl From<BookCopyRow> for BookCopy {
+    fn from(value: BookCopyRow) -> Self {
+        let BookCopyRow {
+            copy_id,
             book_id: _,
-            user_id,
-            user_name,
+            barcode,
+            owned_by,
+            owner_name,
+            checkout_id,
+            checked_out_by,
+            checked_out_by_name,
             checked_out_at,
+            due_at,
         } = value;
 
-        Checkout {
+        let checkout = match (
             checkout_id,
-            checked_out_by: CheckoutUser {
-                id: user_id,
-                name: user_name,
-            },
+            checked_out_by,
+            checked_out_by_name,
             checked_out_at,
+            due_at,
+        ) {
+            (Some(checkout_id), Some(id), Some(name), Some(checked_out_at), Some(due_at)) => {
+                Some(Checkout {
+                    checkout_id,
+                    checked_out_by: CheckoutUser { id, name },
+                    checked_out_at,
+                    due_at,
+                })
+            }
+            _ => None,
+        };
+
+        BookCopy {
+            id: copy_id,
+            barcode,
+            owner: BookOwner {
+                id: owned_by,
+                name: owner_name,
+            },
+            checkout,
         }
     }
 }
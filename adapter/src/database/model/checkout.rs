@@ -1,27 +1,39 @@
 use kernel::model::{
-    checkout::{Checkout, CheckoutBook},
-    id::{BookId, CheckoutId, UserId},
+    checkout::{Checkout, CheckoutBook, OverdueCheckout},
+    id::{BookCopyId, BookId, CheckoutId, UserId},
     user::CheckoutUser,
 };
 use sqlx::types::chrono::{DateTime, Utc};
 
 // 貸し出し状態を確認するための型
 // 蔵書が存在する場合はこの型にはまるレコードが存在し、
-// その蔵書が貸出中の場合は checkout_id および user_id が None ではない値になる
-// 蔵書が貸出中でない場合は checkout_id も user_id も None
+// 指定の貸出がその蔵書のコピーの貸出中のものである場合は checkout_id および user_id が None ではない値になる
+// そうでない場合は checkout_id も user_id も None
 pub struct CheckoutStateRow {
     pub book_id: BookId,
     pub checkout_id: Option<CheckoutId>,
     pub user_id: Option<UserId>,
 }
 
+// 貸出数の上限を確認するための型
+// checkout_limit はユーザーごとの上限で、None の場合はロールごとの上限を使う
+pub struct CheckoutLimitRow {
+    pub role_name: String,
+    pub checkout_limit: Option<i32>,
+    pub checkout_count: i64,
+}
+
 // 貸出中の一覧を取得する際に使う型
 pub struct CheckoutRow {
     pub checkout_id: CheckoutId,
     pub book_id: BookId,
+    pub copy_id: BookCopyId,
     pub user_id: UserId,
     pub user_name: String,
     pub checked_out_at: DateTime<Utc>,
+    pub due_at: DateTime<Utc>,
+    pub renewal_count: i32,
+    pub is_overdue: bool,
     pub title: String,
     pub author: String,
     pub isbn: String,
@@ -32,9 +44,13 @@ impl From<CheckoutRow> for Checkout {
         let CheckoutRow {
             checkout_id,
             book_id,
+            copy_id,
             user_id,
             user_name,
             checked_out_at,
+            due_at,
+            renewal_count,
+            is_overdue,
             title,
             author,
             isbn,
@@ -46,10 +62,14 @@ impl From<CheckoutRow> for Checkout {
                 name: user_name,
             },
             checked_out_at,
+            due_at,
             // 未返却なので、returned_at は None を入れる
             returned_at: None,
+            renewal_count,
+            is_overdue,
             book: CheckoutBook {
                 book_id,
+                copy_id,
                 title,
                 author,
                 isbn,
@@ -62,9 +82,13 @@ impl From<CheckoutRow> for Checkout {
 pub struct ReturnedCheckoutRow {
     pub checkout_id: CheckoutId,
     pub book_id: BookId,
-    pub user_id: UserId,
-    pub user_name: String,
+    pub copy_id: BookCopyId,
+    // 借りたユーザーが削除されている場合は None
+    pub user_id: Option<UserId>,
+    pub user_name: Option<String>,
     pub checked_out_at: DateTime<Utc>,
+    pub due_at: DateTime<Utc>,
+    pub renewal_count: i32,
     pub returned_at: DateTime<Utc>,
     pub title: String,
     pub author: String,
@@ -76,9 +100,12 @@ impl From<ReturnedCheckoutRow> for Checkout {
         let ReturnedCheckoutRow {
             checkout_id,
             book_id,
+            copy_id,
             user_id,
             user_name,
             checked_out_at,
+            due_at,
+            renewal_count,
             returned_at,
             title,
             author,
@@ -86,15 +113,20 @@ impl From<ReturnedCheckoutRow> for Checkout {
         } = value;
         Checkout {
             id: checkout_id,
-            checked_out_by: CheckoutUser {
-                id: user_id,
-                name: user_name,
+            checked_out_by: match (user_id, user_name) {
+                (Some(id), Some(name)) => CheckoutUser { id, name },
+                _ => CheckoutUser::anonymized(),
             },
             checked_out_at,
+            due_at,
             // 返却済みなので returned_at には日時データが入る
             returned_at: Some(returned_at),
+            renewal_count,
+            // 返却済みのものは延滞中とはみなさない
+            is_overdue: false,
             book: CheckoutBook {
                 book_id,
+                copy_id,
                 title,
                 author,
                 isbn,
@@ -102,3 +134,56 @@ impl From<ReturnedCheckoutRow> for Checkout {
         }
     }
 }
+
+// 延滞中の貸出一覧を取得する際に使う型
+pub struct OverdueCheckoutRow {
+    pub checkout_id: CheckoutId,
+    pub book_id: BookId,
+    pub copy_id: BookCopyId,
+    pub user_id: UserId,
+    pub user_name: String,
+    pub checked_out_at: DateTime<Utc>,
+    pub due_at: DateTime<Utc>,
+    pub renewal_count: i32,
+    pub days_overdue: i64,
+    pub title: String,
+    pub author: String,
+    pub isbn: String,
+}
+
+impl From<OverdueCheckoutRow> for OverdueCheckout {
+    fn from(value: OverdueCheckoutRow) -> Self {
+        let OverdueCheckoutRow {
+            checkout_id,
+            book_id,
+            copy_id,
+            user_id,
+            user_name,
+            checked_out_at,
+            due_at,
+            renewal_count,
+            days_overdue,
+            title,
+            author,
+            isbn,
+        } = value;
+        OverdueCheckout {
+            checkout: CheckoutRow {
+                checkout_id,
+                book_id,
+                copy_id,
+                user_id,
+                user_name,
+                checked_out_at,
+                due_at,
+                renewal_count,
+                is_overdue: true,
+                title,
+                author,
+                isbn,
+            }
+            .into(),
+            days_overdue,
+        }
+    }
+}
@@ -1,4 +1,6 @@
 pub mod auth;
 pub mod book;
 pub mod checkout;
+pub mod reservation;
+pub mod tag;
 pub mod user;
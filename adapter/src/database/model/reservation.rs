@@ -0,0 +1,53 @@
+use kernel::model::{
+    id::{BookId, ReservationId, UserId},
+    reservation::{Reservation, ReservationBook},
+    user::ReservationUser,
+};
+use sqlx::types::chrono::{DateTime, Utc};
+
+// 予約一覧を取得する際に使う型
+pub struct ReservationRow {
+    pub reservation_id: ReservationId,
+    pub book_id: BookId,
+    pub user_id: UserId,
+    pub user_name: String,
+    pub reserved_at: DateTime<Utc>,
+    pub position: i64,
+    pub hold_expires_at: Option<DateTime<Utc>>,
+    pub title: String,
+    pub author: String,
+    pub isbn: String,
+}
+
+impl From<ReservationRow> for Reservation {
+    fn from(value: ReservationRow) -> Self {
+        let ReservationRow {
+            reservation_id,
+            book_id,
+            user_id,
+            user_name,
+            reserved_at,
+            position,
+            hold_expires_at,
+            title,
+            author,
+            isbn,
+        } = value;
+        Reservation {
+            id: reservation_id,
+            reserved_by: ReservationUser {
+                id: user_id,
+                name: user_name,
+            },
+            reserved_at,
+            position,
+            hold_expires_at,
+            book: ReservationBook {
+                book_id,
+                title,
+                author,
+                isbn,
+            },
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use kernel::model::{
+    id::{BookId, TagId},
+    tag::{Tag, TagFacet},
+};
+
+pub struct TagRow {
+    pub tag_id: TagId,
+    pub name: String,
+}
+
+impl From<TagRow> for Tag {
+    fn from(value: TagRow) -> Self {
+        let TagRow { tag_id, name } = value;
+        Tag { id: tag_id, name }
+    }
+}
+
+// 蔵書に付いているタグを取得する際に使う型
+pub struct BookTagRow {
+    pub book_id: BookId,
+    pub tag_id: TagId,
+    pub name: String,
+}
+
+pub struct TagFacetRow {
+    pub tag_id: TagId,
+    pub name: String,
+    pub count: i64,
+}
+
+impl From<TagFacetRow> for TagFacet {
+    fn from(value: TagFacetRow) -> Self {
+        let TagFacetRow {
+            tag_id,
+            name,
+            count,
+        } = value;
+        TagFacet {
+            tag: Tag { id: tag_id, name },
+            count,
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use redis::{AsyncTypedCommands, Client};
+use redis::{AsyncTypedCommands, Client, Expiry};
 use shared::{config::RedisConfig, error::AppResult};
 
 use crate::redis::model::{RedisKey, RedisValue};
@@ -28,12 +28,82 @@ impl RedisClient {
         result.map(T::Value::try_from).transpose()
     }
 
+    // 値を取得すると同時に有効期間を延長する
+    pub async fn get_ex<T: RedisKey>(&self, key: &T, ttl: u64) -> AppResult<Option<T::Value>> {
+        let mut connection = self.client.get_multiplexed_async_connection().await?;
+        let result: Option<String> = connection.get_ex(key.inner(), Expiry::EX(ttl)).await?;
+        result.map(T::Value::try_from).transpose()
+    }
+
+    // 値を取得すると同時にキーを削除する
+    // 同じキーを同時に取得しようとしても、値を受け取れるのは 1 回だけになる
+    pub async fn get_del<T: RedisKey>(&self, key: &T) -> AppResult<Option<T::Value>> {
+        let mut connection = self.client.get_multiplexed_async_connection().await?;
+        let result: Option<String> = connection.get_del(key.inner()).await?;
+        result.map(T::Value::try_from).transpose()
+    }
+
     pub async fn delete<T: RedisKey>(&self, key: &T) -> AppResult<()> {
         let mut connection = self.client.get_multiplexed_async_connection().await?;
         connection.del(key.inner()).await?;
         Ok(())
     }
 
+    // 値を 1 増やして増やした後の値を返し、キーの有効期間を設定し直す
+    pub async fn increment<T: RedisKey>(&self, key: &T, ttl: u64) -> AppResult<u64> {
+        let mut connection = self.client.get_multiplexed_async_connection().await?;
+        let (count,): (u64,) = redis::pipe()
+            .atomic()
+            .incr(key.inner(), 1)
+            .expire(key.inner(), ttl as i64)
+            .ignore()
+            .query_async(&mut connection)
+            .await?;
+        Ok(count)
+    }
+
+    // キーの残りの有効期間（秒）を返す。キーが存在しない場合は None を返す
+    pub async fn ttl<T: RedisKey>(&self, key: &T) -> AppResult<Option<u64>> {
+        let mut connection = self.client.get_multiplexed_async_connection().await?;
+        let ttl: i64 = redis::cmd("TTL")
+            .arg(key.inner())
+            .query_async(&mut connection)
+            .await?;
+        Ok(u64::try_from(ttl).ok())
+    }
+
+    // 複数のキーを 1 つのコマンドでまとめて削除する
+    pub async fn delete_keys(&self, keys: Vec<String>) -> AppResult<()> {
+        let mut connection = self.client.get_multiplexed_async_connection().await?;
+        connection.del(keys).await?;
+        Ok(())
+    }
+
+    // 集合に値を追加し、集合の有効期間を設定し直す
+    pub async fn add_member<T: RedisKey>(
+        &self,
+        key: &T,
+        value: &T::Value,
+        ttl: u64,
+    ) -> AppResult<()> {
+        let mut connection = self.client.get_multiplexed_async_connection().await?;
+        connection.sadd(key.inner(), value.inner()).await?;
+        connection.expire(key.inner(), ttl as i64).await?;
+        Ok(())
+    }
+
+    pub async fn remove_member<T: RedisKey>(&self, key: &T, value: &T::Value) -> AppResult<()> {
+        let mut connection = self.client.get_multiplexed_async_connection().await?;
+        connection.srem(key.inner(), value.inner()).await?;
+        Ok(())
+    }
+
+    pub async fn members<T: RedisKey>(&self, key: &T) -> AppResult<Vec<T::Value>> {
+        let mut connection = self.client.get_multiplexed_async_connection().await?;
+        let result = connection.smembers(key.inner()).await?;
+        result.into_iter().map(T::Value::try_from).collect()
+    }
+
     pub async fn try_connect(&self) -> AppResult<()> {
         let _ = self.client.get_multiplexed_async_connection().await?;
         Ok(())
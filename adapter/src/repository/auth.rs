@@ -1,29 +1,57 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use derive_new::new;
+use chrono::{DateTime, Utc};
 use kernel::{
     model::{
-        auth::{AccessToken, event::CreateToken},
-        id::UserId,
+        auth::{
+            AccessToken, AuthTokens, RefreshToken, Session,
+            event::{CreateToken, VerifyUser},
+        },
+        id::{SessionId, UserId},
     },
     repository::auth::AuthRepository,
 };
-use shared::error::{AppError, AppResult};
+use shared::{
+    config::{AuthConfig, LoginThrottleConfig},
+    error::{AppError, AppResult},
+};
+use uuid::Uuid;
 
 use crate::{
     database::{
         ConnectionPool,
-        model::auth::{AuthorizationKey, AuthorizedUserId, UserItem, from},
+        model::auth::{
+            AuthorizationKey, AuthorizedSessionId, ConsumedRefreshToken, LoginFailureCount,
+            LoginFailureKey, RefreshTokenKey, SessionKey, SessionLastUsedAt, SessionLastUsedKey,
+            SessionsRevoked, SessionsRevokedKey, TokenHasher, UserItem, UserSessionsKey, from,
+        },
     },
-    redis::RedisClient,
+    redis::{RedisClient, model::RedisKey},
 };
 
-#[derive(new)]
 pub struct AuthRepositoryImpl {
     db: ConnectionPool,
     kv: Arc<RedisClient>,
-    ttl: u64,
+    config: AuthConfig,
+    hasher: TokenHasher,
+    // 存在しないメールアドレスでログインしようとしたときに照合するハッシュ値
+    dummy_password_hash: String,
+}
+
+impl AuthRepositoryImpl {
+    pub fn new(db: ConnectionPool, kv: Arc<RedisClient>, config: AuthConfig) -> Self {
+        let hasher = TokenHasher::new(&config.token_secret);
+        let dummy_password_hash = bcrypt::hash(Uuid::new_v4().to_string(), bcrypt::DEFAULT_COST)
+            .expect("bcrypt hashing with the default cost never fails");
+        Self {
+            db,
+            kv,
+            config,
+            hasher,
+            dummy_password_hash,
+        }
+    }
 }
 
 #[async_trait]
@@ -32,14 +60,65 @@ impl AuthRepository for AuthRepositoryImpl {
         &self,
         access_token: &AccessToken,
     ) -> AppResult<Option<UserId>> {
-        let key: AuthorizationKey = access_token.into();
+        let key = AuthorizationKey::new(access_token, &self.hasher);
+        let value = if self.config.sliding_expiration {
+            // 使われるたびにアクセストークンの有効期間を延長する
+            self.kv.get_ex(&key, self.config.ttl).await?
+        } else {
+            self.kv.get(&key).await?
+        };
+        let Some(value) = value else {
+            return Ok(None);
+        };
+        // セッションが終了している場合は、アクセストークンが残っていても無効とする
+        if self
+            .kv
+            .get(&SessionKey::from(value.session_id()))
+            .await?
+            .is_none()
+        {
+            self.kv.delete(&key).await?;
+            return Ok(None);
+        }
+
         self.kv
-            .get(&key)
-            .await
-            .map(|x| x.map(AuthorizedUserId::into_inner))
+            .set_ex(
+                &SessionLastUsedKey::from(value.session_id()),
+                &SessionLastUsedAt::now(),
+                self.config.refresh_token_ttl,
+            )
+            .await?;
+
+        Ok(Some(value.user_id()))
     }
 
-    async fn verify_user(&self, email: &str, password: &str) -> AppResult<UserId> {
+    async fn verify_user(&self, event: VerifyUser) -> AppResult<UserId> {
+        let VerifyUser {
+            email,
+            password,
+            ip_address,
+        } = event;
+        let throttle = self.config.login_throttle;
+        let mut failure_keys = vec![(
+            LoginFailureKey::account(&email, &self.hasher),
+            throttle.max_failures_per_account,
+        )];
+        if let Some(ip_address) = &ip_address {
+            failure_keys.push((
+                LoginFailureKey::ip_address(ip_address),
+                throttle.max_failures_per_ip,
+            ));
+        }
+
+        // ロック中はパスワードを照合せずに断る
+        let mut retry_after = None;
+        for (key, _) in &failure_keys {
+            retry_after = retry_after.max(self.kv.ttl(&key.lock_key()).await?);
+        }
+        if let Some(retry_after) = retry_after {
+            return Err(AppError::TooManyLoginAttempts { retry_after });
+        }
+
         let user_item = sqlx::query_as!(
             UserItem,
             r#"
@@ -48,26 +127,457 @@ impl AuthRepository for AuthRepositoryImpl {
             "#,
             email
         )
-        .fetch_one(self.db.inner_ref())
+        .fetch_optional(self.db.inner_ref())
         .await
         .map_err(AppError::SpecificOperationError)?;
 
-        let valid = bcrypt::verify(password, &user_item.password_hash)?;
-        if !valid {
-            return Err(AppError::UnauthenticatedError);
+        // 存在しないメールアドレスでもダミーのハッシュ値と照合し、
+        // パスワードが誤っている場合と応答や処理時間で区別できないようにする
+        let password_hash = user_item
+            .as_ref()
+            .map_or(self.dummy_password_hash.as_str(), |x| {
+                x.password_hash.as_str()
+            });
+        let valid = bcrypt::verify(&password, password_hash)?;
+        match user_item {
+            Some(user_item) if valid => {
+                // ログインに成功したらアカウントの失敗回数をリセットする
+                // IP アドレスの失敗回数は、別のアカウントへの攻撃を続けられないようリセットしない
+                self.kv
+                    .delete(&LoginFailureKey::account(&email, &self.hasher))
+                    .await?;
+                Ok(user_item.user_id)
+            }
+            _ => {
+                for (key, max_failures) in &failure_keys {
+                    self.record_login_failure(key, *max_failures).await?;
+                }
+                Err(AppError::UnauthenticatedError)
+            }
         }
+    }
+
+    async fn unlock_account(&self, user_id: UserId) -> AppResult<()> {
+        let email = sqlx::query_scalar!(
+            r#"
+                SELECT email FROM users
+                WHERE user_id = $1;
+            "#,
+            user_id as _
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .ok_or_else(|| AppError::EntityNotFound("Specified user not found".into()))?;
 
-        Ok(user_item.user_id)
+        let key = LoginFailureKey::account(&email, &self.hasher);
+        self.kv
+            .delete_keys(vec![key.inner(), key.lock_key().inner()])
+            .await
     }
 
-    async fn create_token(&self, event: CreateToken) -> AppResult<AccessToken> {
-        let (key, value) = from(event);
-        self.kv.set_ex(&key, &value, self.ttl).await?;
-        Ok(key.into())
+    async fn create_token(&self, event: CreateToken) -> AppResult<AuthTokens> {
+        self.store_tokens(event, Utc::now(), Vec::new()).await
+    }
+
+    async fn refresh_token(&self, refresh_token: RefreshToken) -> AppResult<AuthTokens> {
+        let session_id = refresh_token
+            .session_id()
+            .ok_or(AppError::UnauthorizedError)?;
+
+        // リフレッシュトークンは取得と同時に削除し、一度しか使えないようにする
+        let refresh_token_key = RefreshTokenKey::new(&refresh_token, &self.hasher);
+        let consumed = self
+            .kv
+            .get_del(&refresh_token_key)
+            .await?
+            .filter(|x| x.session_id() == session_id);
+        let Some(session) = self.kv.get(&SessionKey::from(session_id)).await? else {
+            // ログアウト済みや期限切れのセッション
+            return Err(AppError::UnauthorizedError);
+        };
+        if consumed.is_none() {
+            // セッション ID はトークンの先頭から分かるため、見覚えのないトークンでは
+            // セッションを終了させず、認証エラーにするだけにする
+            if !session.is_consumed_refresh_token(&refresh_token_key) {
+                return Err(AppError::UnauthorizedError);
+            }
+            // ローテーションで使用済みになったトークンが再び使われた場合は、
+            // トークンが漏洩したとみなし、セッションのトークンをすべて無効にする
+            tracing::warn!(
+                user_id = %session.user_id,
+                %session_id,
+                "Refresh token reuse detected; revoking session"
+            );
+            self.revoke_session(session_id).await?;
+            return Err(AppError::UnauthorizedError);
+        }
+
+        // 前のアクセストークンを無効にしてから、同じセッションのトークンを発行し直す
+        // 使ったリフレッシュトークンは、再使用を検出できるようセッションに記録しておく
+        self.kv.delete(&session.access_token_key()).await?;
+        let consumed_refresh_tokens =
+            session.consume_refresh_token(Utc::now(), self.config.refresh_token_ttl);
+        let (user_id, created_at) = (session.user_id, session.created_at);
+        let event =
+            CreateToken::for_session(user_id, session_id, session.user_agent, session.ip_address);
+        let tokens = self
+            .store_tokens(event, created_at, consumed_refresh_tokens)
+            .await?;
+
+        // セッションをまとめて終了する処理と同時にリフレッシュされた場合に、終了したセッションを
+        // 作り直さないよう、保存した後で終了の記録を確認する。保存より前に記録されていれば
+        // ここで終了し、保存より後に記録された場合は終了する処理が保存したセッションを削除する
+        let revoked = self
+            .kv
+            .get(&SessionsRevokedKey::from(user_id))
+            .await?
+            .is_some_and(|x| x.revokes(session_id, created_at));
+        if revoked {
+            self.revoke_session(session_id).await?;
+            return Err(AppError::UnauthorizedError);
+        }
+
+        Ok(tokens)
     }
 
     async fn delete_token(&self, access_token: AccessToken) -> AppResult<()> {
-        let key: AuthorizationKey = access_token.into();
+        let key = AuthorizationKey::new(&access_token, &self.hasher);
+        if let Some(value) = self.kv.get(&key).await? {
+            self.revoke_session(value.session_id()).await?;
+        }
         self.kv.delete(&key).await
     }
+
+    async fn find_session_id(&self, access_token: &AccessToken) -> AppResult<Option<SessionId>> {
+        let key = AuthorizationKey::new(access_token, &self.hasher);
+        Ok(self.kv.get(&key).await?.map(|x| x.session_id()))
+    }
+
+    async fn find_sessions(&self, user_id: UserId) -> AppResult<Vec<Session>> {
+        let sessions_key = UserSessionsKey::from(user_id);
+        let mut sessions = Vec::new();
+        for member in self.kv.members(&sessions_key).await? {
+            let session_id = member.session_id();
+            let session = self
+                .kv
+                .get(&SessionKey::from(session_id))
+                .await?
+                .filter(|x| x.user_id == user_id);
+            let Some(session) = session else {
+                // 期限切れで消えたセッションは集合からも取り除く
+                self.kv
+                    .remove_member(&sessions_key, &AuthorizedSessionId::from(session_id))
+                    .await?;
+                continue;
+            };
+            let last_used_at = self
+                .kv
+                .get(&SessionLastUsedKey::from(session_id))
+                .await?
+                .map(SessionLastUsedAt::into_inner);
+            sessions.push(session.into_session(session_id, last_used_at));
+        }
+        sessions.sort_by(|a, b| b.last_used_at.cmp(&a.last_used_at));
+
+        Ok(sessions)
+    }
+
+    async fn delete_session(&self, user_id: UserId, session_id: SessionId) -> AppResult<()> {
+        let session = self
+            .kv
+            .get(&SessionKey::from(session_id))
+            .await?
+            .filter(|x| x.user_id == user_id);
+        if session.is_none() {
+            return Err(AppError::EntityNotFound(
+                "Specified session not found".into(),
+            ));
+        }
+
+        self.revoke_session(session_id).await
+    }
+
+    async fn delete_sessions(&self, user_id: UserId) -> AppResult<()> {
+        self.record_sessions_revoked(user_id, None).await?;
+
+        let sessions_key = UserSessionsKey::from(user_id);
+        let session_ids = self
+            .kv
+            .members(&sessions_key)
+            .await?
+            .iter()
+            .map(AuthorizedSessionId::session_id)
+            .collect();
+        self.revoke_sessions(session_ids).await?;
+        self.kv.delete(&sessions_key).await
+    }
+
+    async fn delete_other_sessions(&self, user_id: UserId, session_id: SessionId) -> AppResult<()> {
+        self.record_sessions_revoked(user_id, Some(session_id))
+            .await?;
+
+        let session_ids = self
+            .kv
+            .members(&UserSessionsKey::from(user_id))
+            .await?
+            .iter()
+            .map(AuthorizedSessionId::session_id)
+            .filter(|id| *id != session_id)
+            .collect();
+        self.revoke_sessions(session_ids).await
+    }
+}
+
+impl AuthRepositoryImpl {
+    // 失敗回数を数え、上限に達したらロックする
+    // ロックする秒数は上限を超えて失敗するたびに倍にする
+    async fn record_login_failure(
+        &self,
+        key: &LoginFailureKey,
+        max_failures: u64,
+    ) -> AppResult<()> {
+        let throttle = self.config.login_throttle;
+        let failures = self
+            .kv
+            .increment(key, throttle.failure_window_seconds)
+            .await?;
+        if failures < max_failures {
+            return Ok(());
+        }
+
+        let lockout_seconds = lockout_seconds(&throttle, failures, max_failures);
+        self.kv
+            .set_ex(
+                &key.lock_key(),
+                &LoginFailureCount::from(failures),
+                lockout_seconds,
+            )
+            .await
+    }
+
+    async fn store_tokens(
+        &self,
+        event: CreateToken,
+        created_at: DateTime<Utc>,
+        consumed_refresh_tokens: Vec<ConsumedRefreshToken>,
+    ) -> AppResult<AuthTokens> {
+        let ttl = self.config.refresh_token_ttl;
+        // 呼び出し元に返すのは元のトークンで、Redis にはハッシュ値だけを保存する
+        let tokens = AuthTokens {
+            user_id: event.user_id,
+            access_token: AccessToken(event.access_token.clone()),
+            refresh_token: RefreshToken(event.refresh_token.clone()),
+        };
+        let (
+            (access_token_key, access_token_value),
+            (refresh_token_key, refresh_token_value),
+            (session_key, session_value),
+        ) = from(event, created_at, consumed_refresh_tokens, &self.hasher);
+        let session_id = access_token_value.session_id();
+
+        self.kv.set_ex(&session_key, &session_value, ttl).await?;
+        self.kv
+            .add_member(
+                &UserSessionsKey::from(tokens.user_id),
+                &AuthorizedSessionId::from(session_id),
+                ttl,
+            )
+            .await?;
+        self.kv
+            .set_ex(&refresh_token_key, &refresh_token_value, ttl)
+            .await?;
+        self.kv
+            .set_ex(&access_token_key, &access_token_value, self.config.ttl)
+            .await?;
+
+        Ok(tokens)
+    }
+
+    // セッションをまとめて終了したことを、セッションの有効期間のあいだ記録しておく
+    async fn record_sessions_revoked(
+        &self,
+        user_id: UserId,
+        kept_session_id: Option<SessionId>,
+    ) -> AppResult<()> {
+        self.kv
+            .set_ex(
+                &SessionsRevokedKey::from(user_id),
+                &SessionsRevoked::now(kept_session_id),
+                self.config.refresh_token_ttl,
+            )
+            .await
+    }
+
+    async fn revoke_session(&self, session_id: SessionId) -> AppResult<()> {
+        self.revoke_sessions(vec![session_id]).await
+    }
+
+    // セッションと、そのセッションで有効なアクセストークンとリフレッシュトークンを削除する
+    // アクセストークンはセッションが存在する間だけ有効なので、複数のセッションのキーを
+    // 1 回の DEL でまとめて削除すると、すべてのトークンが同時に無効になる
+    async fn revoke_sessions(&self, session_ids: Vec<SessionId>) -> AppResult<()> {
+        let mut keys = Vec::new();
+        let mut revoked = Vec::new();
+        for session_id in session_ids {
+            let session_key = SessionKey::from(session_id);
+            if let Some(session) = self.kv.get(&session_key).await? {
+                keys.push(session.access_token_key().inner());
+                keys.push(session.refresh_token_key().inner());
+                revoked.push((session.user_id, session_id));
+            }
+            keys.push(session_key.inner());
+            keys.push(SessionLastUsedKey::from(session_id).inner());
+        }
+        if keys.is_empty() {
+            return Ok(());
+        }
+        self.kv.delete_keys(keys).await?;
+
+        for (user_id, session_id) in revoked {
+            self.kv
+                .remove_member(
+                    &UserSessionsKey::from(user_id),
+                    &AuthorizedSessionId::from(session_id),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+// 失敗回数が上限に達したときにロックする秒数
+fn lockout_seconds(throttle: &LoginThrottleConfig, failures: u64, max_failures: u64) -> u64 {
+    let exponent = u32::try_from(failures.saturating_sub(max_failures)).unwrap_or(u32::MAX);
+    2u64.checked_pow(exponent)
+        .and_then(|x| x.checked_mul(throttle.lockout_seconds))
+        .map_or(throttle.max_lockout_seconds, |x| {
+            x.min(throttle.max_lockout_seconds)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::model::auth::{MAX_CONSUMED_REFRESH_TOKENS, SessionItem};
+
+    #[test]
+    fn test_token_keys_are_hashed() {
+        let hasher = TokenHasher::new("secret");
+        let access_token = AccessToken("3f11016015344d64956c234d50589092".into());
+
+        // キーにはトークンそのものを含めず、同じトークンからは同じキーを作る
+        let key = AuthorizationKey::new(&access_token, &hasher).inner();
+        assert!(!key.contains(&access_token.0));
+        assert_eq!(key, AuthorizationKey::new(&access_token, &hasher).inner());
+
+        // 秘密鍵が異なればキーも異なる
+        let other_hasher = TokenHasher::new("other-secret");
+        assert_ne!(
+            key,
+            AuthorizationKey::new(&access_token, &other_hasher).inner()
+        );
+
+        // 同じ文字列でもアクセストークンとリフレッシュトークンのキーは区別する
+        let refresh_token = RefreshToken(access_token.0.clone());
+        assert_ne!(key, RefreshTokenKey::new(&refresh_token, &hasher).inner());
+    }
+
+    // ログインしてから、指定した回数だけリフレッシュしたセッションと、その間に発行したリフレッシュトークン
+    fn rotate(
+        hasher: &TokenHasher,
+        rotations: usize,
+        interval: chrono::Duration,
+        refresh_token_ttl: u64,
+    ) -> (SessionItem, Vec<RefreshToken>) {
+        let (user_id, session_id) = (UserId::new(), SessionId::new());
+        let mut now = Utc::now();
+        let event = CreateToken::for_session(user_id, session_id, None, None);
+        let mut tokens = vec![RefreshToken(event.refresh_token.clone())];
+        let (_, _, (_, mut session)) = from(event, now, Vec::new(), hasher);
+        for _ in 0..rotations {
+            now += interval;
+            let consumed = session.consume_refresh_token(now, refresh_token_ttl);
+            let event = CreateToken::for_session(user_id, session_id, None, None);
+            tokens.push(RefreshToken(event.refresh_token.clone()));
+            (_, _, (_, session)) = from(event, now, consumed, hasher);
+        }
+        (session, tokens)
+    }
+
+    #[test]
+    fn test_consumed_refresh_token() {
+        let hasher = TokenHasher::new("secret");
+        let key = |token: &RefreshToken| RefreshTokenKey::new(token, &hasher);
+
+        // ログインした直後のセッションには使用済みのトークンがない
+        let (session, tokens) = rotate(&hasher, 0, chrono::Duration::minutes(1), 3600);
+        assert!(!session.is_consumed_refresh_token(&key(&tokens[0])));
+
+        // 直前のトークンだけでなく、2 回前のローテーションで使用済みになったトークンも再使用とみなす
+        let (session, tokens) = rotate(&hasher, 2, chrono::Duration::minutes(1), 3600);
+        assert!(session.is_consumed_refresh_token(&key(&tokens[0])));
+        assert!(session.is_consumed_refresh_token(&key(&tokens[1])));
+        assert!(!session.is_consumed_refresh_token(&key(&tokens[2])));
+        // セッション ID だけを合わせた推測のトークンは再使用とみなさない
+        let session_id = tokens[0].session_id().unwrap();
+        let guessed = RefreshToken(format!("{session_id}.garbage"));
+        assert!(!session.is_consumed_refresh_token(&key(&guessed)));
+    }
+
+    #[test]
+    fn test_consumed_refresh_tokens_are_bounded() {
+        let hasher = TokenHasher::new("secret");
+        let key = |token: &RefreshToken| RefreshTokenKey::new(token, &hasher);
+
+        // リフレッシュトークンの有効期間より前に使用済みになったトークンは記録から消す
+        let (session, tokens) = rotate(&hasher, 3, chrono::Duration::minutes(30), 3600);
+        assert_eq!(session.consumed_refresh_tokens.len(), 2);
+        assert!(!session.is_consumed_refresh_token(&key(&tokens[0])));
+        assert!(session.is_consumed_refresh_token(&key(&tokens[1])));
+
+        // 有効期間内でも、上限を超えた古いトークンは記録から消す
+        let rotations = MAX_CONSUMED_REFRESH_TOKENS + 2;
+        let (session, tokens) = rotate(&hasher, rotations, chrono::Duration::seconds(1), 3600);
+        assert_eq!(
+            session.consumed_refresh_tokens.len(),
+            MAX_CONSUMED_REFRESH_TOKENS
+        );
+        assert!(!session.is_consumed_refresh_token(&key(&tokens[1])));
+        assert!(session.is_consumed_refresh_token(&key(&tokens[2])));
+        assert!(session.is_consumed_refresh_token(&key(&tokens[rotations - 1])));
+    }
+
+    #[test]
+    fn test_sessions_revoked() {
+        let kept = SessionId::new();
+        let revoked = SessionsRevoked::now(Some(kept));
+
+        // 記録する前に作られたセッションは、残したセッションを除いて終了したものとする
+        let before = Utc::now() - chrono::Duration::minutes(1);
+        assert!(revoked.revokes(SessionId::new(), before));
+        assert!(!revoked.revokes(kept, before));
+        // 記録した後にログインして作られたセッションは終了していない
+        let after = Utc::now() + chrono::Duration::minutes(1);
+        assert!(!revoked.revokes(SessionId::new(), after));
+    }
+
+    #[test]
+    fn test_lockout_seconds() {
+        let throttle = LoginThrottleConfig {
+            max_failures_per_account: 5,
+            max_failures_per_ip: 20,
+            lockout_seconds: 60,
+            max_lockout_seconds: 3600,
+            failure_window_seconds: 86400,
+        };
+
+        // 上限に達したら最初の秒数だけロックし、以降は失敗するたびに倍にする
+        assert_eq!(lockout_seconds(&throttle, 5, 5), 60);
+        assert_eq!(lockout_seconds(&throttle, 6, 5), 120);
+        assert_eq!(lockout_seconds(&throttle, 8, 5), 480);
+        // 最大の秒数で頭打ちになる
+        assert_eq!(lockout_seconds(&throttle, 11, 5), 3600);
+        assert_eq!(lockout_seconds(&throttle, u64::MAX, 5), 3600);
+    }
 }
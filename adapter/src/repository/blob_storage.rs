@@ -0,0 +1,143 @@
+use std::{
+    io::ErrorKind,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use kernel::repository::blob_storage::BlobStorage;
+use shared::{
+    config::BlobStorageConfig,
+    error::{AppError, AppResult},
+};
+
+// 設定に応じたファイルの保存先を組み立てる
+pub fn build_blob_storage(config: &BlobStorageConfig) -> AppResult<Arc<dyn BlobStorage>> {
+    Ok(Arc::new(LocalBlobStorage::new(&config.root_dir)?))
+}
+
+// ローカルのファイルシステムのディレクトリにファイルを保存する
+// キーをディレクトリからの相対パスとして扱う
+pub struct LocalBlobStorage {
+    root_dir: PathBuf,
+}
+
+impl LocalBlobStorage {
+    pub fn new(root_dir: impl AsRef<Path>) -> AppResult<Self> {
+        let root_dir = root_dir.as_ref();
+        std::fs::create_dir_all(root_dir).map_err(AppError::BlobStorageError)?;
+        Ok(Self {
+            root_dir: root_dir.into(),
+        })
+    }
+
+    // ディレクトリの外を指すキーは受け付けない
+    fn path(&self, key: &str) -> AppResult<PathBuf> {
+        let relative = Path::new(key);
+        if key.is_empty()
+            || !relative
+                .components()
+                .all(|c| matches!(c, Component::Normal(_)))
+        {
+            return Err(AppError::UnprocessableEntity(format!(
+                "ファイルのキー（{key}）が不正です。"
+            )));
+        }
+        Ok(self.root_dir.join(relative))
+    }
+}
+
+#[async_trait]
+impl BlobStorage for LocalBlobStorage {
+    async fn put(&self, key: &str, data: Vec<u8>) -> AppResult<()> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(AppError::BlobStorageError)?;
+        }
+
+        // 書き込みの途中のファイルを読まれないよう、一時ファイルに書き込んでから置き換える
+        let temp_path = path.with_extension(format!("{}.tmp", uuid::Uuid::new_v4().simple()));
+        tokio::fs::write(&temp_path, data)
+            .await
+            .map_err(AppError::BlobStorageError)?;
+        if let Err(e) = tokio::fs::rename(&temp_path, &path).await {
+            let _ = tokio::fs::remove_file(&temp_path).await;
+            return Err(AppError::BlobStorageError(e));
+        }
+
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> AppResult<Option<Vec<u8>>> {
+        match tokio::fs::read(self.path(key)?).await {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(AppError::BlobStorageError(e)),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> AppResult<()> {
+        match tokio::fs::remove_file(self.path(key)?).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(AppError::BlobStorageError(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("blob-storage-test-{}", uuid::Uuid::new_v4()))
+    }
+
+    #[tokio::test]
+    async fn test_put_get_delete() -> AppResult<()> {
+        let root_dir = temp_dir();
+        let storage = LocalBlobStorage::new(&root_dir)?;
+
+        assert_eq!(storage.get("covers/a").await?, None);
+
+        storage.put("covers/a", b"first".to_vec()).await?;
+        assert_eq!(storage.get("covers/a").await?, Some(b"first".to_vec()));
+
+        // 同じキーで保存すると置き換える
+        storage.put("covers/a", b"second".to_vec()).await?;
+        assert_eq!(storage.get("covers/a").await?, Some(b"second".to_vec()));
+
+        storage.delete("covers/a").await?;
+        assert_eq!(storage.get("covers/a").await?, None);
+        // ないファイルの削除も成功する
+        storage.delete("covers/a").await?;
+
+        // 一時ファイルは残らない
+        let remaining = std::fs::read_dir(root_dir.join("covers"))
+            .map_err(AppError::BlobStorageError)?
+            .count();
+        assert_eq!(remaining, 0);
+
+        std::fs::remove_dir_all(root_dir).map_err(AppError::BlobStorageError)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reject_key_outside_root() -> AppResult<()> {
+        let root_dir = temp_dir();
+        let storage = LocalBlobStorage::new(&root_dir)?;
+
+        for key in ["", "../escape", "/etc/passwd", "covers/../../escape"] {
+            let res = storage.put(key, b"data".to_vec()).await;
+            assert!(
+                matches!(res, Err(AppError::UnprocessableEntity(_))),
+                "{key}"
+            );
+        }
+
+        std::fs::remove_dir_all(root_dir).map_err(AppError::BlobStorageError)?;
+        Ok(())
+    }
+}
@@ -1,22 +1,39 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use async_trait::async_trait;
 use derive_new::new;
 use kernel::{
     model::{
-        book::{Book, BookListOptions, Checkout, DeleteBook, UpdateBook, event::CreateBook},
-        id::{BookId, CheckoutId, UserId},
-        list::PaginatedList,
+        book::{
+            Book, BookAvailability, BookCopy, BookCover, BookImportDuplicate, BookImportError,
+            BookImportReport, BookListOptions, BookOwnerTransfer, BookSortKey, DeleteBook,
+            ImportedBook, PatchBook, RestoreBook, UpdateBook,
+            event::{
+                CreateBook, CreateBookCopy, DeleteBookCopy, ImportBookRow, ImportBooks,
+                TransferBookOwner, UpdateBookCover,
+            },
+        },
+        id::{BookCopyId, BookId, CheckoutId, CoverId, TagId, UserId},
+        isbn::Isbn,
+        list::{Cursor, PaginatedList},
+        tag::{Tag, TagFacet},
     },
-    repository::book::BookRepository,
+    repository::book::{BookRepository, BookStream},
 };
 use shared::error::{AppError, AppResult};
+use tokio_stream::wrappers::ReceiverStream;
 
 use crate::database::{
     ConnectionPool,
-    model::book::{BookCheckoutRow, BookRow, PaginatedBookRow},
+    model::{
+        book::{BookCopyRow, BookOwnerTransferRow, BookRow, PaginatedBookRow},
+        tag::{BookTagRow, TagFacetRow},
+    },
 };
 
+// 蔵書のエクスポートで一度に取得する件数
+const EXPORT_BATCH_SIZE: i64 = 100;
+
 #[derive(new)]
 pub struct BookRepositoryImpl {
     db: ConnectionPool,
@@ -25,46 +42,286 @@ pub struct BookRepositoryImpl {
 #[async_trait]
 impl BookRepository for BookRepositoryImpl {
     async fn create(&self, event: CreateBook, requested_user: UserId) -> AppResult<()> {
-        sqlx::query!(
+        let mut tx = self.db.begin().await?;
+
+        insert_book(&mut tx, event, requested_user).await?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
+        Ok(())
+    }
+
+    async fn import(&self, event: ImportBooks) -> AppResult<BookImportReport> {
+        let ImportBooks {
+            rows,
+            requested_user,
+            dry_run,
+        } = event;
+
+        let mut tx = self.db.begin().await?;
+
+        // 取り込む ISBN・バーコードのうち、すでに登録されているものと、指定されたタグのうち
+        // 存在するものをまとめて調べる
+        let isbns: Vec<String> = rows.iter().map(|row| row.book.isbn.to_string()).collect();
+        let mut existing_books: HashMap<Isbn, BookId> = HashMap::new();
+        for (isbn, book_id) in sqlx::query!(
             r#"
-                INSERT INTO books (title, author, isbn, description, user_id)
-                VALUES($1, $2, $3, $4, $5)
+                SELECT isbn AS "isbn: Isbn", book_id AS "book_id: BookId"
+                FROM books
+                WHERE isbn = ANY($1)
+                ORDER BY created_at ASC, book_id ASC
             "#,
-            event.title,
-            event.author,
-            event.isbn,
-            event.description,
-            requested_user as _
+            &isbns
         )
-        .execute(self.db.inner_ref())
+        .fetch_all(&mut *tx)
         .await
-        .map_err(AppError::SpecificOperationError)?;
+        .map_err(AppError::SpecificOperationError)?
+        .into_iter()
+        .map(|row| (row.isbn, row.book_id))
+        {
+            existing_books.entry(isbn).or_insert(book_id);
+        }
 
-        Ok(())
+        let barcodes: Vec<String> = rows
+            .iter()
+            .filter_map(|row| row.book.barcode.clone())
+            .collect();
+        let mut used_barcodes: HashSet<String> = sqlx::query_scalar!(
+            r#"SELECT barcode FROM book_copies WHERE barcode = ANY($1)"#,
+            &barcodes
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .into_iter()
+        .collect();
+
+        let tag_ids: Vec<TagId> = dedup_tags(
+            rows.iter()
+                .flat_map(|row| row.book.tags.iter().copied())
+                .collect(),
+        );
+        let existing_tags: HashSet<TagId> = sqlx::query_scalar!(
+            r#"SELECT tag_id AS "tag_id: TagId" FROM tags WHERE tag_id = ANY($1)"#,
+            &tag_ids as _
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .into_iter()
+        .collect();
+
+        let mut report = BookImportReport::default();
+        let mut seen_isbns: HashMap<Isbn, usize> = HashMap::new();
+        for ImportBookRow { line, book } in rows {
+            if let Some(&book_id) = existing_books.get(&book.isbn) {
+                report.duplicates.push(BookImportDuplicate {
+                    line,
+                    isbn: book.isbn,
+                    existing_book_id: Some(book_id),
+                    duplicate_of_line: None,
+                });
+                continue;
+            }
+            if let Some(&first_line) = seen_isbns.get(&book.isbn) {
+                report.duplicates.push(BookImportDuplicate {
+                    line,
+                    isbn: book.isbn,
+                    existing_book_id: None,
+                    duplicate_of_line: Some(first_line),
+                });
+                continue;
+            }
+            // 存在しないタグを指定した行は、他の行の取り込みを止めずにエラーとして報告する
+            if let Some(tag_id) = book.tags.iter().find(|t| !existing_tags.contains(t)) {
+                report.errors.push(BookImportError {
+                    line,
+                    message: format!("タグ（{tag_id}）は存在しません。"),
+                });
+                continue;
+            }
+
+            if let Some(barcode) = &book.barcode
+                && !used_barcodes.insert(barcode.clone())
+            {
+                report.errors.push(BookImportError {
+                    line,
+                    message: format!("バーコード（{barcode}）はすでに使われています。"),
+                });
+                continue;
+            }
+
+            seen_isbns.insert(book.isbn.clone(), line);
+            let book_id = insert_book(&mut tx, book, requested_user).await?;
+            report.imported.push(ImportedBook {
+                line,
+                book_id: (!dry_run).then_some(book_id),
+            });
+        }
+
+        // ドライランの場合はロールバックして、登録した場合と同じ結果だけを返す
+        if dry_run {
+            tx.rollback().await.map_err(AppError::TransactionError)?;
+        } else {
+            tx.commit().await.map_err(AppError::TransactionError)?;
+        }
+
+        Ok(report)
     }
 
     async fn find_all(&self, options: BookListOptions) -> AppResult<PaginatedList<Book>> {
-        let BookListOptions { limit, offset } = options;
+        let BookListOptions {
+            limit,
+            offset,
+            query,
+            availability,
+            owner,
+            author,
+            isbn,
+            sort,
+            cursor,
+            tags,
+            include_archived,
+        } = options;
+
+        // カーソルは登録日時と蔵書 ID の組なので、登録日時による並び順でのみ使える
+        let keyset = matches!(sort, BookSortKey::CreatedAtDesc | BookSortKey::CreatedAtAsc);
+        if cursor.is_some() && !keyset {
+            return Err(AppError::UnprocessableEntity(
+                "カーソルは登録日時による並び順でのみ指定できます。".into(),
+            ));
+        }
+        // カーソルを指定した場合は offset を使わない
+        let offset = if cursor.is_some() { 0 } else { offset };
 
-        let rows: Vec<PaginatedBookRow> = sqlx::query_as!(
+        let query = query
+            .map(|q| q.trim().to_string())
+            .filter(|q| !q.is_empty());
+        // 検索語が ISBN として解釈できる場合は、ハイフンの有無や ISBN-10 / 13 の違いによらずマッチさせる
+        let query_isbn = query.as_deref().and_then(|q| q.parse::<Isbn>().ok());
+        // 貸出中はコピーがあり、そのすべてが貸し出されている蔵書とする（コピーのない蔵書はどちらにも含めない）
+        let checked_out = availability.map(|a| a == BookAvailability::CheckedOut);
+        let tags = dedup_tags(tags);
+        let sort = match sort {
+            BookSortKey::CreatedAtDesc => "created_at_desc",
+            BookSortKey::CreatedAtAsc => "created_at_asc",
+            BookSortKey::TitleAsc => "title_asc",
+            BookSortKey::TitleDesc => "title_desc",
+            BookSortKey::AuthorAsc => "author_asc",
+            BookSortKey::AuthorDesc => "author_desc",
+            BookSortKey::Relevance => "relevance",
+        };
+
+        // 検索語は以下のいずれかに当てはまる蔵書にマッチする
+        // - 空白区切りの単語による全文検索
+        // - 部分一致（空白で区切られない日本語のタイトルなど）
+        // - pg_trgm によるあいまい検索（表記ゆれや入力ミス）
+        //
+        // 総件数は検索条件に当てはまる蔵書の件数とし、カーソルによる絞り込みの前に数える。
+        // 次のページの有無を判定するため limit + 1 件まで取得する。
+        let mut rows: Vec<PaginatedBookRow> = sqlx::query_as!(
             PaginatedBookRow,
             r#"
+                WITH filtered AS (
+                    SELECT
+                        b.book_id,
+                        b.title,
+                        b.author,
+                        b.created_at,
+                        CASE WHEN $9 = 'relevance' AND $3::TEXT IS NOT NULL THEN
+                            ts_rank(
+                                to_tsvector('simple', b.search_document),
+                                websearch_to_tsquery('simple', $3)
+                            ) + word_similarity($3, b.search_document)
+                        END AS relevance,
+                        COUNT(*) OVER() AS total
+                    FROM books AS b
+                    WHERE (
+                        $3::TEXT IS NULL
+                        OR to_tsvector('simple', b.search_document) @@ websearch_to_tsquery('simple', $3)
+                        OR b.search_document ILIKE '%' || $4 || '%'
+                        OR $3 <% b.search_document
+                        OR b.isbn = $12
+                    )
+                    AND ($5::UUID IS NULL OR b.user_id = $5)
+                    AND ($13::TEXT IS NULL OR b.isbn = $13)
+                    AND ($6::TEXT IS NULL OR b.author ILIKE '%' || $7 || '%')
+                    AND (
+                        $8::BOOLEAN IS NULL
+                        OR (
+                            EXISTS(
+                                SELECT 1 FROM book_copies AS bc
+                                WHERE bc.book_id = b.book_id
+                                AND NOT EXISTS(SELECT 1 FROM checkouts AS c WHERE c.copy_id = bc.copy_id)
+                            ) <> $8
+                            AND EXISTS(SELECT 1 FROM book_copies AS bc WHERE bc.book_id = b.book_id)
+                        )
+                    )
+                    AND (
+                        cardinality($14::UUID[]) = 0
+                        OR (
+                            SELECT COUNT(*) FROM book_tags AS bt
+                            WHERE bt.book_id = b.book_id AND bt.tag_id = ANY($14)
+                        ) = cardinality($14)
+                    )
+                    AND ($15 OR b.deleted_at IS NULL)
+                )
                 SELECT
-                    COUNT(*) OVER() AS "total!",
-                    b.book_id AS id
-                FROM books AS b
-                ORDER BY b.created_at DESC
+                    f.total AS "total!",
+                    f.book_id AS id,
+                    f.created_at
+                FROM filtered AS f
+                WHERE $10::TIMESTAMPTZ IS NULL
+                OR (
+                    CASE WHEN $9 = 'created_at_asc'
+                        THEN (f.created_at, f.book_id) > ($10, $11::UUID)
+                        ELSE (f.created_at, f.book_id) < ($10, $11::UUID)
+                    END
+                )
+                ORDER BY
+                    f.relevance DESC NULLS LAST,
+                    CASE WHEN $9 = 'title_asc' THEN f.title END ASC,
+                    CASE WHEN $9 = 'title_desc' THEN f.title END DESC,
+                    CASE WHEN $9 = 'author_asc' THEN f.author END ASC,
+                    CASE WHEN $9 = 'author_desc' THEN f.author END DESC,
+                    CASE WHEN $9 = 'created_at_asc' THEN f.created_at END ASC,
+                    CASE WHEN $9 = 'created_at_asc' THEN f.book_id END ASC,
+                    f.created_at DESC,
+                    f.book_id DESC
                 LIMIT $1
                 OFFSET $2
             "#,
-            limit,
-            offset
+            limit.saturating_add(1),
+            offset,
+            query.as_deref(),
+            query.as_deref().map(escape_like),
+            owner as _,
+            author.as_deref(),
+            author.as_deref().map(escape_like),
+            checked_out,
+            sort,
+            cursor.map(|c| c.created_at),
+            cursor.map(|c| c.id),
+            query_isbn as _,
+            isbn as _,
+            &tags as _,
+            include_archived,
         )
         .fetch_all(self.db.inner_ref())
         .await
         .map_err(AppError::SpecificOperationError)?;
 
         let total = rows.first().map(|r| r.total).unwrap_or_default();
+        let limit_len = usize::try_from(limit).unwrap_or_default();
+        let next_cursor = if rows.len() > limit_len {
+            rows.truncate(limit_len);
+            rows.last().filter(|_| keyset).map(|r| Cursor {
+                created_at: r.created_at,
+                id: r.id.raw(),
+            })
+        } else {
+            None
+        };
         let book_ids = rows.into_iter().map(|r| r.id).collect::<Vec<BookId>>();
 
         let rows: Vec<BookRow> = sqlx::query_as!(
@@ -74,14 +331,19 @@ impl BookRepository for BookRepositoryImpl {
                     b.book_id AS book_id,
                     b.title AS title,
                     b.author AS author,
-                    b.isbn AS isbn,
+                    b.isbn AS "isbn: Isbn",
                     b.description AS description,
                     u.user_id AS owned_by,
-                    u.name AS owner_name
+                    u.name AS owner_name,
+                    b.deleted_at,
+                    b.archive_reason,
+                    b.version,
+                    b.cover_id AS "cover_id: CoverId",
+                    b.cover_content_type
                 FROM books AS b
                 INNER JOIN users AS u USING(user_id)
                 WHERE b.book_id IN (SELECT * FROM UNNEST($1::uuid[]))
-                ORDER BY b.created_at DESC
+                ORDER BY array_position($1::uuid[], b.book_id)
             "#,
             &book_ids as _
         )
@@ -93,13 +355,16 @@ impl BookRepository for BookRepositoryImpl {
             .iter()
             .map(|book| BookId::from(book.book_id))
             .collect::<Vec<_>>();
-        let mut checkouts = self.find_checkouts(&book_ids).await?;
+        let mut copies = self.find_copies(&book_ids).await?;
+        let mut tags = self.find_tags(&book_ids).await?;
 
         let items = rows
             .into_iter()
             .map(|row| {
-                let checkout = checkouts.remove(&BookId::from(row.book_id));
-                row.into_book(checkout)
+                let book_id = BookId::from(row.book_id);
+                let copies = copies.remove(&book_id).unwrap_or_default();
+                let tags = tags.remove(&book_id).unwrap_or_default();
+                row.into_book(copies, tags)
             })
             .collect();
 
@@ -108,9 +373,87 @@ impl BookRepository for BookRepositoryImpl {
             limit,
             offset,
             items,
+            next_cursor,
         })
     }
 
+    async fn find_tag_facets(&self, options: &BookListOptions) -> AppResult<Vec<TagFacet>> {
+        let query = options
+            .query
+            .as_deref()
+            .map(str::trim)
+            .filter(|q| !q.is_empty());
+        let query_isbn = query.and_then(|q| q.parse::<Isbn>().ok());
+        let checked_out = options
+            .availability
+            .map(|a| a == BookAvailability::CheckedOut);
+        let tags = dedup_tags(options.tags.clone());
+
+        // 絞り込みの条件は find_all と同じにする
+        let rows: Vec<TagFacetRow> = sqlx::query_as!(
+            TagFacetRow,
+            r#"
+                WITH filtered AS (
+                    SELECT b.book_id
+                    FROM books AS b
+                    WHERE (
+                        $1::TEXT IS NULL
+                        OR to_tsvector('simple', b.search_document) @@ websearch_to_tsquery('simple', $1)
+                        OR b.search_document ILIKE '%' || $2 || '%'
+                        OR $1 <% b.search_document
+                        OR b.isbn = $8
+                    )
+                    AND ($3::UUID IS NULL OR b.user_id = $3)
+                    AND ($9::TEXT IS NULL OR b.isbn = $9)
+                    AND ($4::TEXT IS NULL OR b.author ILIKE '%' || $5 || '%')
+                    AND (
+                        $6::BOOLEAN IS NULL
+                        OR (
+                            EXISTS(
+                                SELECT 1 FROM book_copies AS bc
+                                WHERE bc.book_id = b.book_id
+                                AND NOT EXISTS(SELECT 1 FROM checkouts AS c WHERE c.copy_id = bc.copy_id)
+                            ) <> $6
+                            AND EXISTS(SELECT 1 FROM book_copies AS bc WHERE bc.book_id = b.book_id)
+                        )
+                    )
+                    AND (
+                        cardinality($7::UUID[]) = 0
+                        OR (
+                            SELECT COUNT(*) FROM book_tags AS bt
+                            WHERE bt.book_id = b.book_id AND bt.tag_id = ANY($7)
+                        ) = cardinality($7)
+                    )
+                    AND ($10 OR b.deleted_at IS NULL)
+                )
+                SELECT
+                    t.tag_id AS "tag_id: TagId",
+                    t.name,
+                    COUNT(*) AS "count!"
+                FROM filtered AS f
+                INNER JOIN book_tags AS bt ON bt.book_id = f.book_id
+                INNER JOIN tags AS t ON t.tag_id = bt.tag_id
+                GROUP BY t.tag_id, t.name
+                ORDER BY COUNT(*) DESC, LOWER(t.name) ASC
+            "#,
+            query,
+            query.map(escape_like),
+            options.owner as _,
+            options.author.as_deref(),
+            options.author.as_deref().map(escape_like),
+            checked_out,
+            &tags as _,
+            query_isbn as _,
+            options.isbn as _,
+            options.include_archived,
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        Ok(rows.into_iter().map(TagFacet::from).collect())
+    }
+
     async fn find_by_id(&self, book_id: BookId) -> AppResult<Option<Book>> {
         let row: Option<BookRow> = sqlx::query_as!(
             BookRow,
@@ -119,10 +462,15 @@ impl BookRepository for BookRepositoryImpl {
                     b.book_id AS book_id,
                     b.title AS title,
                     b.author AS author,
-                    b.isbn AS isbn,
+                    b.isbn AS "isbn: Isbn",
                     b.description AS description,
                     u.user_id AS owned_by,
-                    u.name AS owner_name
+                    u.name AS owner_name,
+                    b.deleted_at,
+                    b.archive_reason,
+                    b.version,
+                    b.cover_id AS "cover_id: CoverId",
+                    b.cover_content_type
                 FROM books AS b
                 INNER JOIN users AS u USING(user_id)
                 WHERE b.book_id = $1
@@ -135,18 +483,62 @@ impl BookRepository for BookRepositoryImpl {
 
         match row {
             Some(r) => {
-                let checkout = self
-                    .find_checkouts(&[BookId::from(r.book_id)])
+                let book_id = BookId::from(r.book_id);
+                let copies = self
+                    .find_copies(&[book_id])
                     .await?
-                    .remove(&BookId::from(r.book_id));
-                Ok(Some(r.into_book(checkout)))
+                    .remove(&book_id)
+                    .unwrap_or_default();
+                let tags = self
+                    .find_tags(&[book_id])
+                    .await?
+                    .remove(&book_id)
+                    .unwrap_or_default();
+                Ok(Some(r.into_book(copies, tags)))
             }
             None => Ok(None),
         }
     }
 
+    fn export_all(&self) -> BookStream {
+        let repo = Self::new(self.db.clone());
+        let (tx, rx) = tokio::sync::mpsc::channel(EXPORT_BATCH_SIZE as usize);
+
+        // 一定件数ずつキーセットページネーションで取得し、チャネル経由で返す
+        // 受け取り側が破棄された場合は取得を打ち切る
+        tokio::spawn(async move {
+            let mut cursor = None;
+            loop {
+                let (books, next_cursor) = match repo.find_export_batch(cursor).await {
+                    Ok(batch) => batch,
+                    Err(e) => {
+                        let _ = tx.send(Err(e)).await;
+                        return;
+                    }
+                };
+                for book in books {
+                    if tx.send(Ok(book)).await.is_err() {
+                        return;
+                    }
+                }
+                match next_cursor {
+                    Some(next_cursor) => cursor = Some(next_cursor),
+                    None => return,
+                }
+            }
+        });
+
+        Box::pin(ReceiverStream::new(rx))
+    }
+
     async fn update(&self, event: UpdateBook) -> AppResult<()> {
-        let res = sqlx::query!(
+        let mut tx = self.db.begin().await?;
+
+        let version =
+            lock_book_version(&mut tx, event.book_id, event.requested_user, event.is_admin).await?;
+        check_version(event.expected_version, version)?;
+
+        sqlx::query!(
             r#"
                 UPDATE books
                 SET
@@ -155,33 +547,134 @@ impl BookRepository for BookRepositoryImpl {
                     isbn = $3,
                     description = $4
                 WHERE book_id = $5
-                AND ($6 OR user_id = $7)
             "#,
             event.title,
             event.author,
-            event.isbn,
+            event.isbn as _,
             event.description,
             event.book_id as _,
-            event.is_admin,
-            event.requested_user as _
         )
-        .execute(self.db.inner_ref())
+        .execute(&mut *tx)
         .await
         .map_err(AppError::SpecificOperationError)?;
 
-        if res.rows_affected() < 1 {
-            return Err(AppError::EntityNotFound("Specified book not found".into()));
+        if let Some(tags) = event.tags {
+            replace_book_tags(&mut tx, event.book_id, tags).await?;
+        }
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
+        Ok(())
+    }
+
+    async fn patch(&self, event: PatchBook) -> AppResult<()> {
+        let mut tx = self.db.begin().await?;
+
+        let version =
+            lock_book_version(&mut tx, event.book_id, event.requested_user, event.is_admin).await?;
+        check_version(event.expected_version, version)?;
+
+        // 指定された項目の SET 句だけを組み立てる
+        let has_columns = event.title.is_some()
+            || event.author.is_some()
+            || event.isbn.is_some()
+            || event.description.is_some();
+        let mut query = sqlx::QueryBuilder::new("UPDATE books SET ");
+        let mut set = query.separated(", ");
+        if let Some(title) = event.title {
+            set.push("title = ").push_bind_unseparated(title);
+        }
+        if let Some(author) = event.author {
+            set.push("author = ").push_bind_unseparated(author);
+        }
+        if let Some(isbn) = event.isbn {
+            set.push("isbn = ").push_bind_unseparated(isbn);
+        }
+        if let Some(description) = event.description {
+            set.push("description = ")
+                .push_bind_unseparated(description);
         }
+        // タグだけを変更する場合も、蔵書のバージョンは更新する
+        if !has_columns {
+            set.push("version = version");
+        }
+
+        if has_columns || event.tags.is_some() {
+            query.push(" WHERE book_id = ").push_bind(event.book_id);
+            query
+                .build()
+                .execute(&mut *tx)
+                .await
+                .map_err(AppError::SpecificOperationError)?;
+        }
+
+        if let Some(tags) = event.tags {
+            replace_book_tags(&mut tx, event.book_id, tags).await?;
+        }
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
 
         Ok(())
     }
 
     async fn delete(&self, event: DeleteBook) -> AppResult<()> {
+        let mut tx = self.db.begin().await?;
+
+        // 蔵書の行をロックし、アーカイブするまでの間に貸し出されないようにする
+        let version =
+            lock_book_version(&mut tx, event.book_id, event.requested_user, event.is_admin).await?;
+        check_version(event.expected_version, version)?;
+
+        let checked_out = sqlx::query_scalar!(
+            r#"SELECT EXISTS(SELECT 1 FROM checkouts WHERE book_id = $1) AS "checked_out!""#,
+            event.book_id as _,
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        if checked_out {
+            return Err(AppError::UnprocessableEntity(format!(
+                "書籍（{}）は貸出中のため削除できません。",
+                event.book_id
+            )));
+        }
+
+        sqlx::query!(
+            r#"
+                UPDATE books
+                SET deleted_at = CURRENT_TIMESTAMP(3), archive_reason = $2
+                WHERE book_id = $1
+            "#,
+            event.book_id as _,
+            event.reason,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        // アーカイブした蔵書は貸し出せないため、予約は取り消す
+        sqlx::query!(
+            r#"DELETE FROM reservations WHERE book_id = $1"#,
+            event.book_id as _
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
+        Ok(())
+    }
+
+    async fn restore(&self, event: RestoreBook) -> AppResult<()> {
         let res = sqlx::query!(
             r#"
-                DELETE FROM books
+                UPDATE books
+                SET deleted_at = NULL, archive_reason = NULL
                 WHERE book_id = $1
                 AND ($2 OR user_id = $3)
+                AND deleted_at IS NOT NULL
             "#,
             event.book_id as _,
             event.is_admin,
@@ -192,105 +685,1574 @@ impl BookRepository for BookRepositoryImpl {
         .map_err(AppError::SpecificOperationError)?;
 
         if res.rows_affected() < 1 {
-            return Err(AppError::EntityNotFound("Specified book not found".into()));
+            return Err(AppError::EntityNotFound(
+                "Specified archived book not found".into(),
+            ));
         }
 
         Ok(())
     }
-}
 
-impl BookRepositoryImpl {
-    async fn find_checkouts(&self, book_ids: &[BookId]) -> AppResult<HashMap<BookId, Checkout>> {
-        let res = sqlx::query_as!(
-            BookCheckoutRow,
+    async fn transfer_owner(&self, event: TransferBookOwner) -> AppResult<()> {
+        let mut tx = self.db.begin().await?;
+
+        // 蔵書の行をロックし、所有者を変更するまでの間に貸し出されないようにする
+        lock_book_version(&mut tx, event.book_id, event.requested_user, event.is_admin).await?;
+
+        let row = sqlx::query!(
             r#"
                 SELECT
-                    c.checkout_id AS "checkout_id!: CheckoutId",
-                    c.book_id AS "book_id!: BookId",
-                    u.user_id AS "user_id!: UserId",
-                    u.name AS user_name,
-                    c.checked_out_at
-                FROM checkouts AS c
-                INNER JOIN users AS u USING(user_id)
-                WHERE c.book_id = ANY($1)
-                ;
+                    b.user_id AS "owner: UserId",
+                    EXISTS(SELECT 1 FROM checkouts AS c WHERE c.book_id = b.book_id) AS "checked_out!",
+                    EXISTS(SELECT 1 FROM users AS u WHERE u.user_id = $2) AS "new_owner_exists!"
+                FROM books AS b
+                WHERE b.book_id = $1
             "#,
-            &book_ids as _
+            event.book_id as _,
+            event.new_owner as _
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        if row.checked_out {
+            return Err(AppError::UnprocessableEntity(format!(
+                "書籍（{}）は貸出中のため所有者を変更できません。",
+                event.book_id
+            )));
+        }
+        if !row.new_owner_exists {
+            return Err(AppError::UnprocessableEntity(format!(
+                "新しい所有者のユーザー（{}）が見つかりませんでした。",
+                event.new_owner
+            )));
+        }
+        if row.owner == event.new_owner {
+            return Err(AppError::UnprocessableEntity(format!(
+                "ユーザー（{}）はすでに書籍（{}）の所有者です。",
+                event.new_owner, event.book_id
+            )));
+        }
+
+        transfer_book(
+            &mut tx,
+            event.book_id,
+            row.owner,
+            event.new_owner,
+            event.requested_user,
+        )
+        .await?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
+        Ok(())
+    }
+
+    async fn find_owner_transfers(&self, book_id: BookId) -> AppResult<Vec<BookOwnerTransfer>> {
+        sqlx::query_as!(
+            BookOwnerTransferRow,
+            r#"
+                SELECT
+                    t.from_user_id AS "from_user_id: UserId",
+                    fu.name AS "from_user_name?",
+                    t.to_user_id AS "to_user_id: UserId",
+                    tu.name AS "to_user_name?",
+                    t.transferred_by AS "transferred_by: UserId",
+                    bu.name AS "transferred_by_name?",
+                    t.transferred_at
+                FROM book_owner_transfers AS t
+                LEFT OUTER JOIN users AS fu ON fu.user_id = t.from_user_id
+                LEFT OUTER JOIN users AS tu ON tu.user_id = t.to_user_id
+                LEFT OUTER JOIN users AS bu ON bu.user_id = t.transferred_by
+                WHERE t.book_id = $1
+                ORDER BY t.transferred_at ASC, t.book_owner_transfer_id ASC
+            "#,
+            book_id as _
         )
         .fetch_all(self.db.inner_ref())
         .await
-        .map_err(AppError::SpecificOperationError)?
-        .into_iter()
-        .map(|checkout| (checkout.book_id, Checkout::from(checkout)))
-        .collect();
+        .map(|rows| rows.into_iter().map(BookOwnerTransfer::from).collect())
+        .map_err(AppError::SpecificOperationError)
+    }
 
-        Ok(res)
+    async fn update_cover(&self, event: UpdateBookCover) -> AppResult<Option<BookCover>> {
+        let mut tx = self.db.begin().await?;
+
+        lock_book_version(&mut tx, event.book_id, event.requested_user, event.is_admin).await?;
+
+        let previous = sqlx::query!(
+            r#"
+                SELECT
+                    cover_id AS "cover_id: CoverId",
+                    cover_content_type
+                FROM books
+                WHERE book_id = $1
+            "#,
+            event.book_id as _
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        sqlx::query!(
+            r#"
+                UPDATE books
+                SET cover_id = $2, cover_content_type = $3
+                WHERE book_id = $1
+            "#,
+            event.book_id as _,
+            event.cover.id as _,
+            event.cover.content_type
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
+        Ok(previous
+            .cover_id
+            .zip(previous.cover_content_type)
+            .map(|(id, content_type)| BookCover { id, content_type }))
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::repository::user::UserRepositoryImpl;
-    use kernel::{
-        model::{book::BookListOptions, user::event::CreateUser},
-        repository::user::UserRepository,
-    };
+    async fn create_copy(&self, event: CreateBookCopy) -> AppResult<()> {
+        let mut tx = self.db.begin().await?;
 
-    #[sqlx::test]
-    async fn test_register_book(pool: sqlx::PgPool) -> AppResult<()> {
-        sqlx::query!(r#"INSERT INTO roles(name) VALUES ('Admin'), ('User');"#)
-            .execute(&pool)
-            .await
-            .map_err(AppError::SpecificOperationError)?;
+        let exists = sqlx::query_scalar!(
+            r#"SELECT EXISTS(SELECT 1 FROM books WHERE book_id = $1 AND deleted_at IS NULL) AS "exists!""#,
+            event.book_id as _,
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
 
-        let user_repo = UserRepositoryImpl::new(ConnectionPool::new(pool.clone()));
-        let book_repo = BookRepositoryImpl::new(ConnectionPool::new(pool));
+        if !exists {
+            return Err(AppError::EntityNotFound("Specified book not found".into()));
+        }
 
-        let user = user_repo
-            .create(CreateUser {
-                name: "Test User".into(),
-                email: "test@example.com".into(),
-                password: "password".into(),
-            })
-            .await?;
+        insert_copy(&mut tx, event.book_id, event.barcode, event.requested_user).await?;
 
-        let book = CreateBook {
-            title: "Test Title".into(),
-            author: "Test Author".into(),
-            isbn: "Test ISBN".into(),
-            description: "Test Description".into(),
-        };
+        tx.commit().await.map_err(AppError::TransactionError)?;
 
-        book_repo.create(book, user.id).await?;
+        Ok(())
+    }
 
-        let options = BookListOptions {
-            limit: 20,
-            offset: 0,
-        };
+    async fn delete_copy(&self, event: DeleteBookCopy) -> AppResult<()> {
+        let mut tx = self.db.begin().await?;
 
-        let res = book_repo.find_all(options).await?;
-        assert_eq!(res.items.len(), 1);
+        // 同じ蔵書のコピーを同時に削除してコピーがなくならないよう、先に蔵書の行をロックする
+        sqlx::query!(
+            r#"SELECT book_id FROM books WHERE book_id = $1 FOR NO KEY UPDATE"#,
+            event.book_id as _,
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
 
-        let book_id = res.items[0].id;
-        let book = book_repo.find_by_id(BookId::from(book_id)).await?;
-        assert!(book.is_some());
+        // コピーの所有者か管理者のみ削除できる
+        let checked_out = sqlx::query_scalar!(
+            r#"
+                SELECT
+                    EXISTS(SELECT 1 FROM checkouts WHERE copy_id = bc.copy_id) AS "checked_out!"
+                FROM book_copies AS bc
+                WHERE bc.copy_id = $1
+                AND bc.book_id = $2
+                AND ($3 OR bc.user_id = $4)
+                FOR UPDATE
+            "#,
+            event.copy_id as _,
+            event.book_id as _,
+            event.is_admin,
+            event.requested_user as _,
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .ok_or_else(|| AppError::EntityNotFound("Specified book copy not found".into()))?;
 
-        let Book {
-            id,
-            title,
-            author,
-            isbn,
-            description,
-            owner,
-            ..
-        } = book.unwrap();
-        assert_eq!(id, book_id);
-        assert_eq!(title, "Test Title");
-        assert_eq!(author, "Test Author");
-        assert_eq!(isbn, "Test ISBN");
-        assert_eq!(description, "Test Description");
-        assert_eq!(owner.name, "Test User");
+        if checked_out {
+            return Err(AppError::UnprocessableEntity(format!(
+                "コピー（{}）は貸出中のため削除できません。",
+                event.copy_id
+            )));
+        }
+
+        // コピーのない蔵書は貸し出せないため、最後のコピーは削除できないようにする
+        let copies = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "count!" FROM book_copies WHERE book_id = $1"#,
+            event.book_id as _,
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        if copies <= 1 {
+            return Err(AppError::UnprocessableEntity(format!(
+                "コピー（{}）は蔵書の最後のコピーのため削除できません。蔵書ごと不要な場合はアーカイブしてください。",
+                event.copy_id
+            )));
+        }
+
+        sqlx::query!(
+            r#"DELETE FROM book_copies WHERE copy_id = $1"#,
+            event.copy_id as _,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
+        Ok(())
+    }
+}
+
+impl BookRepositoryImpl {
+    // 登録日時の古い順に、カーソルの位置の次から EXPORT_BATCH_SIZE 件の蔵書を取得する
+    // 続きがある場合は、次の取得に使うカーソルもあわせて返す
+    async fn find_export_batch(
+        &self,
+        cursor: Option<Cursor>,
+    ) -> AppResult<(Vec<Book>, Option<Cursor>)> {
+        let mut rows = sqlx::query!(
+            r#"
+                SELECT
+                    b.book_id,
+                    b.title,
+                    b.author,
+                    b.isbn AS "isbn: Isbn",
+                    b.description,
+                    u.user_id AS "owned_by: UserId",
+                    u.name AS owner_name,
+                    b.version,
+                    b.cover_id AS "cover_id: CoverId",
+                    b.cover_content_type,
+                    b.created_at
+                FROM books AS b
+                INNER JOIN users AS u USING(user_id)
+                WHERE b.deleted_at IS NULL
+                AND (
+                    $1::TIMESTAMPTZ IS NULL
+                    OR (b.created_at, b.book_id) > ($1, $2::UUID)
+                )
+                ORDER BY b.created_at ASC, b.book_id ASC
+                LIMIT $3
+            "#,
+            cursor.map(|c| c.created_at),
+            cursor.map(|c| c.id),
+            EXPORT_BATCH_SIZE + 1,
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        // 次のバッチの有無を判定するため 1 件多く取得している
+        let next_cursor = if rows.len() > EXPORT_BATCH_SIZE as usize {
+            rows.truncate(EXPORT_BATCH_SIZE as usize);
+            rows.last().map(|r| Cursor {
+                created_at: r.created_at,
+                id: r.book_id,
+            })
+        } else {
+            None
+        };
+
+        let book_ids: Vec<BookId> = rows.iter().map(|r| BookId::from(r.book_id)).collect();
+        let mut copies = self.find_copies(&book_ids).await?;
+        let mut tags = self.find_tags(&book_ids).await?;
+        let books = rows
+            .into_iter()
+            .map(|r| {
+                let book_id = BookId::from(r.book_id);
+                let book_copies = copies.remove(&book_id).unwrap_or_default();
+                let book_tags = tags.remove(&book_id).unwrap_or_default();
+                BookRow {
+                    book_id: r.book_id,
+                    title: r.title,
+                    author: r.author,
+                    isbn: r.isbn,
+                    description: r.description,
+                    owned_by: r.owned_by,
+                    owner_name: r.owner_name,
+                    deleted_at: None,
+                    archive_reason: None,
+                    version: r.version,
+                    cover_id: r.cover_id,
+                    cover_content_type: r.cover_content_type,
+                }
+                .into_book(book_copies, book_tags)
+            })
+            .collect();
+
+        Ok((books, next_cursor))
+    }
+
+    async fn find_tags(&self, book_ids: &[BookId]) -> AppResult<HashMap<BookId, Vec<Tag>>> {
+        let rows = sqlx::query_as!(
+            BookTagRow,
+            r#"
+                SELECT
+                    bt.book_id AS "book_id: BookId",
+                    t.tag_id AS "tag_id: TagId",
+                    t.name
+                FROM book_tags AS bt
+                INNER JOIN tags AS t USING(tag_id)
+                WHERE bt.book_id = ANY($1)
+                ORDER BY LOWER(t.name) ASC, t.tag_id ASC
+            "#,
+            &book_ids as _
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        let mut res: HashMap<BookId, Vec<Tag>> = HashMap::new();
+        for row in rows {
+            res.entry(row.book_id).or_default().push(Tag {
+                id: row.tag_id,
+                name: row.name,
+            });
+        }
+
+        Ok(res)
+    }
+
+    async fn find_copies(&self, book_ids: &[BookId]) -> AppResult<HashMap<BookId, Vec<BookCopy>>> {
+        let rows = sqlx::query_as!(
+            BookCopyRow,
+            r#"
+                SELECT
+                    bc.copy_id AS "copy_id!: BookCopyId",
+                    bc.book_id AS "book_id!: BookId",
+                    bc.barcode,
+                    o.user_id AS "owned_by!: UserId",
+                    o.name AS owner_name,
+                    c.checkout_id AS "checkout_id?: CheckoutId",
+                    cu.user_id AS "checked_out_by?: UserId",
+                    cu.name AS "checked_out_by_name?",
+                    c.checked_out_at AS "checked_out_at?",
+                    c.due_at AS "due_at?"
+                FROM book_copies AS bc
+                INNER JOIN users AS o ON o.user_id = bc.user_id
+                LEFT OUTER JOIN checkouts AS c ON c.copy_id = bc.copy_id
+                LEFT OUTER JOIN users AS cu ON cu.user_id = c.user_id
+                WHERE bc.book_id = ANY($1)
+                ORDER BY bc.created_at ASC, bc.copy_id ASC
+                ;
+            "#,
+            &book_ids as _
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        let mut res: HashMap<BookId, Vec<BookCopy>> = HashMap::new();
+        for row in rows {
+            res.entry(row.book_id).or_default().push(row.into());
+        }
+
+        Ok(res)
+    }
+}
+
+// 蔵書と、登録したユーザーが所有する最初のコピーを登録する
+async fn insert_book(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    event: CreateBook,
+    requested_user: UserId,
+) -> AppResult<BookId> {
+    let book_id = sqlx::query_scalar!(
+        r#"
+            INSERT INTO books (title, author, isbn, description, user_id)
+            VALUES($1, $2, $3, $4, $5)
+            RETURNING book_id AS "book_id: BookId"
+        "#,
+        event.title,
+        event.author,
+        event.isbn as _,
+        event.description,
+        requested_user as _
+    )
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+
+    insert_copy(tx, book_id, event.barcode, requested_user).await?;
+    replace_book_tags(tx, book_id, event.tags).await?;
+
+    Ok(book_id)
+}
+
+// 変更を依頼したユーザーが変更できる（アーカイブ済みでない）蔵書の行をロックし、現在のバージョンを返す
+async fn lock_book_version(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    book_id: BookId,
+    requested_user: UserId,
+    is_admin: bool,
+) -> AppResult<i64> {
+    sqlx::query_scalar!(
+        r#"
+            SELECT version FROM books
+            WHERE book_id = $1
+            AND ($2 OR user_id = $3)
+            AND deleted_at IS NULL
+            FOR UPDATE
+        "#,
+        book_id as _,
+        is_admin,
+        requested_user as _
+    )
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?
+    .ok_or_else(|| AppError::EntityNotFound("Specified book not found".into()))
+}
+
+// 蔵書と、元の所有者が追加したコピーの所有者を変更し、変更履歴に記録する
+// 他のユーザーが追加したコピーの所有者は変更しない
+async fn transfer_book(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    book_id: BookId,
+    from: UserId,
+    to: UserId,
+    transferred_by: UserId,
+) -> AppResult<()> {
+    sqlx::query!(
+        r#"UPDATE books SET user_id = $2 WHERE book_id = $1"#,
+        book_id as _,
+        to as _
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+    sqlx::query!(
+        r#"UPDATE book_copies SET user_id = $3 WHERE book_id = $1 AND user_id = $2"#,
+        book_id as _,
+        from as _,
+        to as _
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+    sqlx::query!(
+        r#"
+            INSERT INTO book_owner_transfers (book_id, from_user_id, to_user_id, transferred_by)
+            VALUES ($1, $2, $3, $4)
+        "#,
+        book_id as _,
+        from as _,
+        to as _,
+        transferred_by as _
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+
+    Ok(())
+}
+
+// 指定されたバージョンがある場合は、現在のバージョンと一致するかを確認する
+fn check_version(expected: Option<i64>, current: i64) -> AppResult<()> {
+    match expected {
+        Some(expected) if expected != current => Err(AppError::PreconditionFailed(format!(
+            "蔵書は他のユーザーによって更新されています（バージョン {expected} が指定されましたが、現在のバージョンは {current} です）。"
+        ))),
+        _ => Ok(()),
+    }
+}
+
+// 蔵書に付けるタグを指定したタグに置き換える
+async fn replace_book_tags(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    book_id: BookId,
+    tags: Vec<TagId>,
+) -> AppResult<()> {
+    let tags = dedup_tags(tags);
+
+    let found = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM tags WHERE tag_id = ANY($1)"#,
+        &tags as _
+    )
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+    if found != tags.len() as i64 {
+        return Err(AppError::UnprocessableEntity(
+            "存在しないタグが指定されています。".into(),
+        ));
+    }
+
+    sqlx::query!(r#"DELETE FROM book_tags WHERE book_id = $1"#, book_id as _)
+        .execute(&mut **tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+    sqlx::query!(
+        r#"
+            INSERT INTO book_tags (book_id, tag_id)
+            SELECT $1, tag_id FROM UNNEST($2::UUID[]) AS tag_id
+        "#,
+        book_id as _,
+        &tags as _
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+
+    Ok(())
+}
+
+// 同じタグが複数回指定されても 1 つとして扱う
+fn dedup_tags(mut tags: Vec<TagId>) -> Vec<TagId> {
+    let mut seen = HashSet::new();
+    tags.retain(|tag| seen.insert(*tag));
+    tags
+}
+
+// 蔵書のコピーを登録する（バーコードの指定がない場合は採番する）
+async fn insert_copy(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    book_id: BookId,
+    barcode: Option<String>,
+    owner: UserId,
+) -> AppResult<()> {
+    let res = sqlx::query!(
+        r#"
+            INSERT INTO book_copies (copy_id, book_id, barcode, user_id)
+            VALUES ($1, $2, COALESCE($3, next_book_copy_barcode()), $4)
+            ON CONFLICT (barcode) DO NOTHING
+        "#,
+        BookCopyId::new() as _,
+        book_id as _,
+        barcode.as_deref(),
+        owner as _,
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+
+    if res.rows_affected() < 1 {
+        return Err(AppError::UnprocessableEntity(format!(
+            "バーコード（{}）はすでに使われています。",
+            barcode.unwrap_or_default()
+        )));
+    }
+
+    Ok(())
+}
+
+// LIKE のパターンとして特別な意味を持つ文字をエスケープする
+fn escape_like(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::{tag::TagRepositoryImpl, user::UserRepositoryImpl};
+    use kernel::{
+        model::{
+            book::BookListOptions,
+            id::CoverId,
+            tag::event::{CreateTag, DeleteTag, UpdateTag},
+            user::event::CreateUser,
+        },
+        repository::{tag::TagRepository, user::UserRepository},
+    };
+    use tokio_stream::StreamExt;
+
+    fn list_options() -> BookListOptions {
+        BookListOptions {
+            limit: 20,
+            offset: 0,
+            query: None,
+            availability: None,
+            owner: None,
+            author: None,
+            isbn: None,
+            sort: BookSortKey::default(),
+            cursor: None,
+            tags: vec![],
+            include_archived: false,
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_register_book(pool: sqlx::PgPool) -> AppResult<()> {
+        sqlx::query!(r#"INSERT INTO roles(name) VALUES ('Admin'), ('User');"#)
+            .execute(&pool)
+            .await
+            .map_err(AppError::SpecificOperationError)?;
+
+        let user_repo = UserRepositoryImpl::new(ConnectionPool::new(pool.clone()), 3);
+        let book_repo = BookRepositoryImpl::new(ConnectionPool::new(pool));
+
+        let user = user_repo
+            .create(CreateUser {
+                name: "Test User".into(),
+                email: "test@example.com".into(),
+                password: "password".into(),
+            })
+            .await?;
+
+        let book = CreateBook {
+            title: "Test Title".into(),
+            author: "Test Author".into(),
+            isbn: "978-1593278281".parse().unwrap(),
+            description: "Test Description".into(),
+            barcode: None,
+            tags: vec![],
+        };
+
+        book_repo.create(book, user.id).await?;
+
+        let res = book_repo.find_all(list_options()).await?;
+        assert_eq!(res.items.len(), 1);
+
+        let book_id = res.items[0].id;
+        let book = book_repo.find_by_id(BookId::from(book_id)).await?;
+        assert!(book.is_some());
+
+        let Book {
+            id,
+            title,
+            author,
+            isbn,
+            description,
+            owner,
+            ..
+        } = book.unwrap();
+        assert_eq!(id, book_id);
+        assert_eq!(title, "Test Title");
+        assert_eq!(author, "Test Author");
+        assert_eq!(isbn.as_str(), "9781593278281");
+        assert_eq!(description, "Test Description");
+        assert_eq!(owner.name, "Test User");
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_search_books(pool: sqlx::PgPool) -> AppResult<()> {
+        sqlx::query!(r#"INSERT INTO roles(name) VALUES ('Admin'), ('User');"#)
+            .execute(&pool)
+            .await
+            .map_err(AppError::SpecificOperationError)?;
+
+        let user_repo = UserRepositoryImpl::new(ConnectionPool::new(pool.clone()), 3);
+        let book_repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+
+        let mut users = Vec::new();
+        for email in ["test1@example.com", "test2@example.com"] {
+            let user = user_repo
+                .create(CreateUser {
+                    name: "Test User".into(),
+                    email: email.into(),
+                    password: "password".into(),
+                })
+                .await?;
+            users.push(user.id);
+        }
+
+        for (title, author, isbn, owner) in [
+            (
+                "RustによるWebアプリケーション開発",
+                "Yuki Toyoda",
+                "9780306406157",
+                users[0],
+            ),
+            (
+                "The Rust Programming Language",
+                "Steve Klabnik",
+                "9781593278281",
+                users[0],
+            ),
+            (
+                "プログラミング言語Go",
+                "Alan Donovan",
+                "9784621300251",
+                users[1],
+            ),
+        ] {
+            book_repo
+                .create(
+                    CreateBook {
+                        title: title.into(),
+                        author: author.into(),
+                        isbn: isbn.parse().unwrap(),
+                        description: "Test Description".into(),
+                        barcode: None,
+                        tags: vec![],
+                    },
+                    owner,
+                )
+                .await?;
+        }
+
+        let titles = |books: PaginatedList<Book>| {
+            books.items.into_iter().map(|b| b.title).collect::<Vec<_>>()
+        };
+
+        // 空白区切りの単語による全文検索
+        let res = book_repo
+            .find_all(BookListOptions {
+                query: Some("rust".into()),
+                sort: BookSortKey::TitleAsc,
+                ..list_options()
+            })
+            .await?;
+        assert_eq!(res.total, 2);
+        assert_eq!(
+            titles(res),
+            vec![
+                "RustによるWebアプリケーション開発",
+                "The Rust Programming Language"
+            ]
+        );
+
+        // 空白で区切られない日本語のタイトルの部分一致
+        let res = book_repo
+            .find_all(BookListOptions {
+                query: Some("アプリケーション".into()),
+                ..list_options()
+            })
+            .await?;
+        assert_eq!(titles(res), vec!["RustによるWebアプリケーション開発"]);
+
+        // 入力ミスを含む検索語でもあいまい検索でマッチする
+        let res = book_repo
+            .find_all(BookListOptions {
+                query: Some("Klabnick".into()),
+                ..list_options()
+            })
+            .await?;
+        assert_eq!(titles(res), vec!["The Rust Programming Language"]);
+
+        // LIKE のワイルドカードは文字どおりに扱われる
+        let res = book_repo
+            .find_all(BookListOptions {
+                query: Some("%".into()),
+                ..list_options()
+            })
+            .await?;
+        assert_eq!(res.total, 0);
+
+        // ISBN-10 やハイフン付きの表記でも、ISBN-13 で登録された蔵書が見つかる
+        for isbn in ["1-59327-828-4", "1593278284", "978-1-59327-828-1"] {
+            let res = book_repo
+                .find_all(BookListOptions {
+                    isbn: Some(isbn.parse().unwrap()),
+                    ..list_options()
+                })
+                .await?;
+            assert_eq!(titles(res), vec!["The Rust Programming Language"]);
+
+            let res = book_repo
+                .find_all(BookListOptions {
+                    query: Some(isbn.into()),
+                    ..list_options()
+                })
+                .await?;
+            assert_eq!(titles(res), vec!["The Rust Programming Language"]);
+        }
+
+        // 所有者と著者名での絞り込み
+        let res = book_repo
+            .find_all(BookListOptions {
+                owner: Some(users[0]),
+                author: Some("klabnik".into()),
+                ..list_options()
+            })
+            .await?;
+        assert_eq!(titles(res), vec!["The Rust Programming Language"]);
+
+        // 貸出状況での絞り込み
+        let go_book = sqlx::query_scalar!(
+            r#"SELECT book_id AS "book_id: BookId" FROM books WHERE author = 'Alan Donovan'"#
+        )
+        .fetch_one(&pool)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        sqlx::query!(
+            r#"
+                INSERT INTO checkouts
+                (checkout_id, book_id, copy_id, user_id, checked_out_at, due_at)
+                SELECT $1, $2, copy_id, $3, CURRENT_TIMESTAMP(3), CURRENT_TIMESTAMP(3)
+                FROM book_copies
+                WHERE book_id = $2
+            "#,
+            CheckoutId::new() as _,
+            go_book as _,
+            users[0] as _,
+        )
+        .execute(&pool)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        let res = book_repo
+            .find_all(BookListOptions {
+                availability: Some(BookAvailability::CheckedOut),
+                ..list_options()
+            })
+            .await?;
+        assert_eq!(titles(res), vec!["プログラミング言語Go"]);
+        let res = book_repo
+            .find_all(BookListOptions {
+                availability: Some(BookAvailability::Available),
+                ..list_options()
+            })
+            .await?;
+        assert_eq!(res.total, 2);
+
+        // コピーのない蔵書は、貸出中にも貸出可能にも含めない
+        sqlx::query!(
+            r#"
+                DELETE FROM book_copies
+                WHERE book_id IN (SELECT book_id FROM books WHERE author = 'Steve Klabnik')
+            "#
+        )
+        .execute(&pool)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        let res = book_repo
+            .find_all(BookListOptions {
+                availability: Some(BookAvailability::CheckedOut),
+                ..list_options()
+            })
+            .await?;
+        assert_eq!(titles(res), vec!["プログラミング言語Go"]);
+        let res = book_repo
+            .find_all(BookListOptions {
+                availability: Some(BookAvailability::Available),
+                ..list_options()
+            })
+            .await?;
+        assert_eq!(res.total, 1);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_find_all_with_cursor(pool: sqlx::PgPool) -> AppResult<()> {
+        sqlx::query!(r#"INSERT INTO roles(name) VALUES ('Admin'), ('User');"#)
+            .execute(&pool)
+            .await
+            .map_err(AppError::SpecificOperationError)?;
+
+        let user_repo = UserRepositoryImpl::new(ConnectionPool::new(pool.clone()), 3);
+        let book_repo = BookRepositoryImpl::new(ConnectionPool::new(pool));
+
+        let user = user_repo
+            .create(CreateUser {
+                name: "Test User".into(),
+                email: "test@example.com".into(),
+                password: "password".into(),
+            })
+            .await?;
+        let create_book = |title: &str| CreateBook {
+            title: title.into(),
+            author: "Test Author".into(),
+            isbn: "9781593278281".parse().unwrap(),
+            description: "Test Description".into(),
+            barcode: None,
+            tags: vec![],
+        };
+        for i in 0..5 {
+            book_repo
+                .create(create_book(&format!("Book {i}")), user.id)
+                .await?;
+        }
+
+        let first = book_repo
+            .find_all(BookListOptions {
+                limit: 2,
+                ..list_options()
+            })
+            .await?;
+        assert_eq!(first.total, 5);
+        assert_eq!(first.items.len(), 2);
+        assert!(first.next_cursor.is_some());
+
+        // ページを取得している間に蔵書が追加されても、重複や取りこぼしは起きない
+        book_repo.create(create_book("Book 5"), user.id).await?;
+
+        let mut ids = first.items.iter().map(|b| b.id).collect::<Vec<_>>();
+        let mut cursor = first.next_cursor;
+        while let Some(c) = cursor {
+            let page = book_repo
+                .find_all(BookListOptions {
+                    limit: 2,
+                    cursor: Some(c),
+                    ..list_options()
+                })
+                .await?;
+            ids.extend(page.items.iter().map(|b| b.id));
+            cursor = page.next_cursor;
+        }
+        assert_eq!(ids.len(), 5);
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), 5);
+
+        // 登録日時以外の並び順ではカーソルを指定できない
+        let res = book_repo
+            .find_all(BookListOptions {
+                sort: BookSortKey::TitleAsc,
+                cursor: first.next_cursor,
+                ..list_options()
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_import_books(pool: sqlx::PgPool) -> AppResult<()> {
+        sqlx::query!(r#"INSERT INTO roles(name) VALUES ('Admin'), ('User');"#)
+            .execute(&pool)
+            .await
+            .map_err(AppError::SpecificOperationError)?;
+
+        let user_repo = UserRepositoryImpl::new(ConnectionPool::new(pool.clone()), 3);
+        let book_repo = BookRepositoryImpl::new(ConnectionPool::new(pool));
+
+        let user = user_repo
+            .create(CreateUser {
+                name: "Test User".into(),
+                email: "test@example.com".into(),
+                password: "password".into(),
+            })
+            .await?;
+        book_repo
+            .create(
+                CreateBook {
+                    title: "Registered".into(),
+                    author: "Test Author".into(),
+                    isbn: "9781593278281".parse().unwrap(),
+                    description: "".into(),
+                    barcode: Some("BC-REGISTERED".into()),
+                    tags: vec![],
+                },
+                user.id,
+            )
+            .await?;
+        let registered = book_repo.find_all(list_options()).await?.items[0].id;
+
+        let rows = |dry_run| {
+            let row = |line, isbn: &str, barcode: Option<&str>| ImportBookRow {
+                line,
+                book: CreateBook {
+                    title: format!("Book {line}"),
+                    author: "Test Author".into(),
+                    isbn: isbn.parse().unwrap(),
+                    description: "".into(),
+                    barcode: barcode.map(String::from),
+                    tags: vec![],
+                },
+            };
+            ImportBooks {
+                rows: vec![
+                    row(2, "9784798186016", None),
+                    // 登録済みの蔵書と ISBN-10 で重複
+                    row(3, "1-59327-828-4", None),
+                    // 2 行目と重複
+                    row(4, "978-4-7981-8601-6", None),
+                    // 登録済みのバーコード
+                    row(5, "9784873119786", Some("BC-REGISTERED")),
+                    row(6, "9784873119786", Some("BC-NEW")),
+                ],
+                requested_user: user.id,
+                dry_run,
+            }
+        };
+
+        // ドライランでは結果だけを返し、蔵書は登録しない
+        let report = book_repo.import(rows(true)).await?;
+        let imported = report.imported.iter().map(|b| b.line).collect::<Vec<_>>();
+        assert_eq!(imported, vec![2, 6]);
+        assert!(report.imported.iter().all(|b| b.book_id.is_none()));
+        assert_eq!(book_repo.find_all(list_options()).await?.total, 1);
+
+        let report = book_repo.import(rows(false)).await?;
+        let imported = report.imported.iter().map(|b| b.line).collect::<Vec<_>>();
+        assert_eq!(imported, vec![2, 6]);
+        assert_eq!(report.duplicates.len(), 2);
+        assert_eq!(report.duplicates[0].line, 3);
+        assert_eq!(
+            report.duplicates[0].existing_book_id,
+            Some(registered.into())
+        );
+        assert_eq!(report.duplicates[1].line, 4);
+        assert_eq!(report.duplicates[1].duplicate_of_line, Some(2));
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].line, 5);
+
+        let book_id = report.imported[1].book_id.unwrap();
+        let book = book_repo.find_by_id(book_id).await?.unwrap();
+        assert_eq!(book.title, "Book 6");
+        assert_eq!(book.copies[0].barcode, "BC-NEW");
+        assert_eq!(book_repo.find_all(list_options()).await?.total, 3);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_import_books_with_unknown_tags(pool: sqlx::PgPool) -> AppResult<()> {
+        sqlx::query!(r#"INSERT INTO roles(name) VALUES ('Admin'), ('User');"#)
+            .execute(&pool)
+            .await
+            .map_err(AppError::SpecificOperationError)?;
+
+        let user_repo = UserRepositoryImpl::new(ConnectionPool::new(pool.clone()), 3);
+        let tag_repo = TagRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let book_repo = BookRepositoryImpl::new(ConnectionPool::new(pool));
+
+        let user = user_repo
+            .create(CreateUser {
+                name: "Test User".into(),
+                email: "test@example.com".into(),
+                password: "password".into(),
+            })
+            .await?;
+        let rust = tag_repo
+            .create(CreateTag {
+                name: "Rust".into(),
+            })
+            .await?;
+
+        let row = |line, isbn: &str, barcode: &str, tags| ImportBookRow {
+            line,
+            book: CreateBook {
+                title: format!("Book {line}"),
+                author: "Test Author".into(),
+                isbn: isbn.parse().unwrap(),
+                description: "".into(),
+                barcode: Some(barcode.into()),
+                tags,
+            },
+        };
+        let report = book_repo
+            .import(ImportBooks {
+                rows: vec![
+                    row(2, "9784798186016", "BC-A", vec![rust.id]),
+                    // 存在しないタグを指定した行
+                    row(3, "9781593278281", "BC-B", vec![rust.id, TagId::new()]),
+                    // 3 行目はエラーのため、同じ ISBN・バーコードでも取り込める
+                    row(4, "9781593278281", "BC-B", vec![]),
+                ],
+                requested_user: user.id,
+                dry_run: false,
+            })
+            .await?;
+
+        // 存在しないタグを指定した行だけがエラーになり、他の行は取り込まれる
+        let imported = report.imported.iter().map(|b| b.line).collect::<Vec<_>>();
+        assert_eq!(imported, vec![2, 4]);
+        assert!(report.duplicates.is_empty());
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].line, 3);
+
+        let book_id = report.imported[0].book_id.unwrap();
+        let book = book_repo.find_by_id(book_id).await?.unwrap();
+        assert_eq!(book.tags.len(), 1);
+        assert_eq!(book_repo.find_all(list_options()).await?.total, 2);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_export_all(pool: sqlx::PgPool) -> AppResult<()> {
+        sqlx::query!(r#"INSERT INTO roles(name) VALUES ('Admin'), ('User');"#)
+            .execute(&pool)
+            .await
+            .map_err(AppError::SpecificOperationError)?;
+
+        let user_repo = UserRepositoryImpl::new(ConnectionPool::new(pool.clone()), 3);
+        let book_repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+
+        let user = user_repo
+            .create(CreateUser {
+                name: "Test User".into(),
+                email: "test@example.com".into(),
+                password: "password".into(),
+            })
+            .await?;
+
+        // 登録日時が同じ蔵書が複数のバッチにまたがっても、漏れや重複なく取得できる
+        let count = EXPORT_BATCH_SIZE * 2 + 1;
+        sqlx::query!(
+            r#"
+                WITH inserted AS (
+                    INSERT INTO books (title, author, isbn, description, user_id)
+                    SELECT 'Book ' || n, 'Test Author', '9781593278281', '', $1
+                    FROM generate_series(1, $2::INT) AS n
+                    RETURNING book_id
+                )
+                INSERT INTO book_copies (book_id, user_id)
+                SELECT book_id, $1 FROM inserted
+            "#,
+            user.id as _,
+            count as i32,
+        )
+        .execute(&pool)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        let books = book_repo
+            .export_all()
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<AppResult<Vec<_>>>()?;
+        assert_eq!(books.len() as i64, count);
+        let ids = books.iter().map(|b| b.id).collect::<HashSet<_>>();
+        assert_eq!(ids.len() as i64, count);
+        assert!(books.iter().all(|b| b.total_copies() == 1));
+        assert!(books.iter().all(|b| b.owner.name == "Test User"));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_book_tags(pool: sqlx::PgPool) -> AppResult<()> {
+        sqlx::query!(r#"INSERT INTO roles(name) VALUES ('Admin'), ('User');"#)
+            .execute(&pool)
+            .await
+            .map_err(AppError::SpecificOperationError)?;
+
+        let user_repo = UserRepositoryImpl::new(ConnectionPool::new(pool.clone()), 3);
+        let book_repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let tag_repo = TagRepositoryImpl::new(ConnectionPool::new(pool));
+
+        let user = user_repo
+            .create(CreateUser {
+                name: "Test User".into(),
+                email: "test@example.com".into(),
+                password: "password".into(),
+            })
+            .await?;
+
+        let create_tag = |name: &str| CreateTag { name: name.into() };
+        let rust = tag_repo.create(create_tag("Rust")).await?;
+        let web = tag_repo.create(create_tag("Web")).await?;
+        let db = tag_repo.create(create_tag("Database")).await?;
+
+        // タグ名は大文字・小文字を区別せずに一意
+        let res = tag_repo.create(create_tag("rust")).await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+        let res = tag_repo
+            .update(UpdateTag {
+                tag_id: web.id,
+                name: "RUST".into(),
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+
+        let names = tag_repo
+            .find_all()
+            .await?
+            .into_iter()
+            .map(|t| t.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["Database", "Rust", "Web"]);
+
+        let create_book = |title: &str, tags: Vec<TagId>| CreateBook {
+            title: title.into(),
+            author: "Test Author".into(),
+            isbn: "9781593278281".parse().unwrap(),
+            description: "".into(),
+            barcode: None,
+            tags,
+        };
+        // 同じタグを重複して指定しても 1 つとして扱う
+        book_repo
+            .create(create_book("Both", vec![web.id, rust.id, rust.id]), user.id)
+            .await?;
+        book_repo
+            .create(create_book("Rust Only", vec![rust.id]), user.id)
+            .await?;
+        book_repo
+            .create(create_book("No Tags", vec![]), user.id)
+            .await?;
+
+        // 存在しないタグは指定できない
+        let res = book_repo
+            .create(create_book("Unknown", vec![TagId::new()]), user.id)
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+
+        let both = book_repo
+            .find_all(list_options())
+            .await?
+            .items
+            .into_iter()
+            .find(|b| b.title == "Both")
+            .map(|b| BookId::from(b.id))
+            .unwrap();
+        let book = book_repo.find_by_id(both).await?.unwrap();
+        assert_eq!(book.tags, vec![rust.clone(), web.clone()]);
+
+        let titles = |books: PaginatedList<Book>| {
+            let mut titles = books.items.into_iter().map(|b| b.title).collect::<Vec<_>>();
+            titles.sort();
+            titles
+        };
+        let with_tags = |tags: Vec<TagId>| BookListOptions {
+            tags,
+            ..list_options()
+        };
+
+        // 指定したすべてのタグが付いている蔵書に絞り込む
+        let res = book_repo.find_all(with_tags(vec![rust.id])).await?;
+        assert_eq!(res.total, 2);
+        assert_eq!(titles(res), vec!["Both", "Rust Only"]);
+        let res = book_repo.find_all(with_tags(vec![rust.id, web.id])).await?;
+        assert_eq!(titles(res), vec!["Both"]);
+
+        // タグごとの冊数は冊数の多い順に返す
+        let facets = book_repo
+            .find_tag_facets(&list_options())
+            .await?
+            .into_iter()
+            .map(|f| (f.tag.name, f.count))
+            .collect::<Vec<_>>();
+        assert_eq!(facets, vec![("Rust".into(), 2), ("Web".into(), 1)]);
+        let facets = book_repo
+            .find_tag_facets(&BookListOptions {
+                query: Some("Rust Only".into()),
+                ..list_options()
+            })
+            .await?
+            .into_iter()
+            .map(|f| (f.tag.name, f.count))
+            .collect::<Vec<_>>();
+        assert_eq!(facets, vec![("Rust".to_string(), 1)]);
+
+        // タグを指定しない更新では、付いているタグを変更しない
+        let update = |tags: Option<Vec<TagId>>| UpdateBook {
+            book_id: both,
+            title: "Both".into(),
+            author: "Test Author".into(),
+            isbn: "9781593278281".parse().unwrap(),
+            description: "".into(),
+            tags,
+            requested_user: user.id,
+            is_admin: false,
+            expected_version: None,
+        };
+        book_repo.update(update(None)).await?;
+        let book = book_repo.find_by_id(both).await?.unwrap();
+        assert_eq!(book.tags.len(), 2);
+
+        book_repo.update(update(Some(vec![db.id]))).await?;
+        let book = book_repo.find_by_id(both).await?.unwrap();
+        assert_eq!(book.tags, vec![db.clone()]);
+
+        // 存在しないタグを指定した更新は、蔵書情報も含めて反映しない
+        let res = book_repo.update(update(Some(vec![TagId::new()]))).await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+        let book = book_repo.find_by_id(both).await?.unwrap();
+        assert_eq!(book.tags, vec![db.clone()]);
+
+        // タグを削除すると、蔵書に付いているタグも外れる
+        tag_repo.delete(DeleteTag { tag_id: db.id }).await?;
+        let book = book_repo.find_by_id(both).await?.unwrap();
+        assert!(book.tags.is_empty());
+        let res = tag_repo.delete(DeleteTag { tag_id: db.id }).await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_book_version(pool: sqlx::PgPool) -> AppResult<()> {
+        sqlx::query!(r#"INSERT INTO roles(name) VALUES ('Admin'), ('User');"#)
+            .execute(&pool)
+            .await
+            .map_err(AppError::SpecificOperationError)?;
+
+        let user_repo = UserRepositoryImpl::new(ConnectionPool::new(pool.clone()), 3);
+        let book_repo = BookRepositoryImpl::new(ConnectionPool::new(pool));
+
+        let user = user_repo
+            .create(CreateUser {
+                name: "Test User".into(),
+                email: "test@example.com".into(),
+                password: "password".into(),
+            })
+            .await?;
+        book_repo
+            .create(
+                CreateBook {
+                    title: "Test Title".into(),
+                    author: "Test Author".into(),
+                    isbn: "9781593278281".parse().unwrap(),
+                    description: "".into(),
+                    barcode: None,
+                    tags: vec![],
+                },
+                user.id,
+            )
+            .await?;
+        let book_id = BookId::from(book_repo.find_all(list_options()).await?.items[0].id);
+        let book = book_repo.find_by_id(book_id).await?.unwrap();
+        assert_eq!(book.version, 1);
+
+        let update = |title: &str, expected_version| UpdateBook {
+            book_id,
+            title: title.into(),
+            author: "Test Author".into(),
+            isbn: "9781593278281".parse().unwrap(),
+            description: "".into(),
+            tags: None,
+            requested_user: user.id,
+            is_admin: false,
+            expected_version,
+        };
+
+        // 現在のバージョンを指定した更新は成功し、バージョンが増える
+        book_repo.update(update("Updated", Some(1))).await?;
+        let book = book_repo.find_by_id(book_id).await?.unwrap();
+        assert_eq!(book.title, "Updated");
+        assert_eq!(book.version, 2);
+
+        // 古いバージョンを指定した更新は反映しない
+        let res = book_repo.update(update("Stale", Some(1))).await;
+        assert!(matches!(res, Err(AppError::PreconditionFailed(_))));
+        let book = book_repo.find_by_id(book_id).await?.unwrap();
+        assert_eq!(book.title, "Updated");
+        assert_eq!(book.version, 2);
+
+        // バージョンを指定しない更新は常に反映する
+        book_repo.update(update("Unconditional", None)).await?;
+        let book = book_repo.find_by_id(book_id).await?.unwrap();
+        assert_eq!(book.version, 3);
+
+        // 削除も同様に、古いバージョンを指定した場合は反映しない
+        let delete = |expected_version| DeleteBook {
+            book_id,
+            reason: None,
+            requested_user: user.id,
+            is_admin: false,
+            expected_version,
+        };
+        let res = book_repo.delete(delete(Some(2))).await;
+        assert!(matches!(res, Err(AppError::PreconditionFailed(_))));
+        assert!(
+            book_repo
+                .find_by_id(book_id)
+                .await?
+                .unwrap()
+                .archive
+                .is_none()
+        );
+        book_repo.delete(delete(Some(3))).await?;
+        assert!(
+            book_repo
+                .find_by_id(book_id)
+                .await?
+                .unwrap()
+                .archive
+                .is_some()
+        );
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_patch_book(pool: sqlx::PgPool) -> AppResult<()> {
+        sqlx::query!(r#"INSERT INTO roles(name) VALUES ('Admin'), ('User');"#)
+            .execute(&pool)
+            .await
+            .map_err(AppError::SpecificOperationError)?;
+
+        let user_repo = UserRepositoryImpl::new(ConnectionPool::new(pool.clone()), 3);
+        let book_repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let tag_repo = TagRepositoryImpl::new(ConnectionPool::new(pool));
+
+        let owner = user_repo
+            .create(CreateUser {
+                name: "Owner".into(),
+                email: "owner@example.com".into(),
+                password: "password".into(),
+            })
+            .await?;
+        let other = user_repo
+            .create(CreateUser {
+                name: "Other".into(),
+                email: "other@example.com".into(),
+                password: "password".into(),
+            })
+            .await?;
+        let tag = tag_repo
+            .create(CreateTag {
+                name: "Rust".into(),
+            })
+            .await?;
+        book_repo
+            .create(
+                CreateBook {
+                    title: "Test Title".into(),
+                    author: "Test Author".into(),
+                    isbn: "9781593278281".parse().unwrap(),
+                    description: "Test Description".into(),
+                    barcode: None,
+                    tags: vec![],
+                },
+                owner.id,
+            )
+            .await?;
+        let book_id = BookId::from(book_repo.find_all(list_options()).await?.items[0].id);
+
+        let patch = |requested_user| PatchBook {
+            book_id,
+            title: None,
+            author: None,
+            isbn: None,
+            description: None,
+            tags: None,
+            expected_version: None,
+            requested_user,
+            is_admin: false,
+        };
+
+        // 指定した項目だけを更新し、ほかの項目は変更しない
+        book_repo
+            .patch(PatchBook {
+                description: Some("Patched".into()),
+                ..patch(owner.id)
+            })
+            .await?;
+        let book = book_repo.find_by_id(book_id).await?.unwrap();
+        assert_eq!(book.title, "Test Title");
+        assert_eq!(book.author, "Test Author");
+        assert_eq!(book.description, "Patched");
+        assert_eq!(book.version, 2);
+
+        // タグだけを変更した場合も、バージョンは増える
+        book_repo
+            .patch(PatchBook {
+                tags: Some(vec![tag.id]),
+                ..patch(owner.id)
+            })
+            .await?;
+        let book = book_repo.find_by_id(book_id).await?.unwrap();
+        assert_eq!(book.tags, vec![tag]);
+        assert_eq!(book.version, 3);
+
+        // 何も指定しない場合は何も変更しない
+        book_repo.patch(patch(owner.id)).await?;
+        let book = book_repo.find_by_id(book_id).await?.unwrap();
+        assert_eq!(book.version, 3);
+
+        // 所有者以外は更新できない
+        let res = book_repo
+            .patch(PatchBook {
+                title: Some("Hijacked".into()),
+                ..patch(other.id)
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+
+        // 古いバージョンを指定した場合は更新しない
+        let res = book_repo
+            .patch(PatchBook {
+                title: Some("Stale".into()),
+                expected_version: Some(2),
+                ..patch(owner.id)
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::PreconditionFailed(_))));
+        let book = book_repo.find_by_id(book_id).await?.unwrap();
+        assert_eq!(book.title, "Test Title");
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_update_cover(pool: sqlx::PgPool) -> AppResult<()> {
+        sqlx::query!(r#"INSERT INTO roles(name) VALUES ('Admin'), ('User');"#)
+            .execute(&pool)
+            .await
+            .map_err(AppError::SpecificOperationError)?;
+
+        let user_repo = UserRepositoryImpl::new(ConnectionPool::new(pool.clone()), 3);
+        let book_repo = BookRepositoryImpl::new(ConnectionPool::new(pool));
+
+        let owner = user_repo
+            .create(CreateUser {
+                name: "Owner".into(),
+                email: "owner@example.com".into(),
+                password: "password".into(),
+            })
+            .await?;
+        let other = user_repo
+            .create(CreateUser {
+                name: "Other".into(),
+                email: "other@example.com".into(),
+                password: "password".into(),
+            })
+            .await?;
+        book_repo
+            .create(
+                CreateBook {
+                    title: "Test Title".into(),
+                    author: "Test Author".into(),
+                    isbn: "9781593278281".parse().unwrap(),
+                    description: "".into(),
+                    barcode: None,
+                    tags: vec![],
+                },
+                owner.id,
+            )
+            .await?;
+        let book_id = BookId::from(book_repo.find_all(list_options()).await?.items[0].id);
+        assert!(
+            book_repo
+                .find_by_id(book_id)
+                .await?
+                .unwrap()
+                .cover
+                .is_none()
+        );
+
+        let cover = |content_type: &str| BookCover {
+            id: CoverId::new(),
+            content_type: content_type.into(),
+        };
+        let update = |cover, requested_user| UpdateBookCover {
+            book_id,
+            cover,
+            requested_user,
+            is_admin: false,
+        };
+
+        // 最初の登録では、置き換えた書影はない
+        let first = cover("image/png");
+        let previous = book_repo
+            .update_cover(update(first.clone(), owner.id))
+            .await?;
+        assert_eq!(previous, None);
+        let book = book_repo.find_by_id(book_id).await?.unwrap();
+        assert_eq!(book.cover, Some(first.clone()));
+
+        // 置き換えると、以前の書影を返す
+        let second = cover("image/jpeg");
+        let previous = book_repo
+            .update_cover(update(second.clone(), owner.id))
+            .await?;
+        assert_eq!(previous, Some(first));
+
+        // 所有者以外は登録できない
+        let res = book_repo
+            .update_cover(update(cover("image/webp"), other.id))
+            .await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+        let book = book_repo.find_by_id(book_id).await?.unwrap();
+        assert_eq!(book.cover, Some(second));
 
         Ok(())
     }
@@ -0,0 +1,329 @@
+use std::{collections::HashMap, path::Path, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use kernel::{
+    model::{book::BookMetadata, isbn::Isbn},
+    repository::book_metadata::BookMetadataProvider,
+};
+use serde::Deserialize;
+use shared::{
+    config::BookMetadataConfig,
+    error::{AppError, AppResult},
+};
+
+// 書誌情報サービスへのリクエストのタイムアウト
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+// 設定に応じた書誌情報の取得元を組み立てる
+pub fn build_book_metadata_provider(
+    config: &BookMetadataConfig,
+) -> AppResult<Arc<dyn BookMetadataProvider>> {
+    Ok(match config {
+        BookMetadataConfig::Disabled => Arc::new(DisabledBookMetadataProvider),
+        BookMetadataConfig::Http { base_url } => Arc::new(HttpBookMetadataProvider::new(base_url)?),
+        BookMetadataConfig::File { path } => Arc::new(FileBookMetadataProvider::load(path)?),
+    })
+}
+
+// 取得元が設定されていない場合に使う、常に見つからない取得元
+pub struct DisabledBookMetadataProvider;
+
+#[async_trait]
+impl BookMetadataProvider for DisabledBookMetadataProvider {
+    async fn find_by_isbn(&self, _isbn: &Isbn) -> AppResult<Option<BookMetadata>> {
+        Ok(None)
+    }
+}
+
+// OpenLibrary の Books API（/api/books?bibkeys=ISBN:...&jscmd=data）と互換のサービスから取得する
+pub struct HttpBookMetadataProvider {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl HttpBookMetadataProvider {
+    pub fn new(base_url: &str) -> AppResult<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| AppError::ExternalServiceError(e.to_string()))?;
+        Ok(Self {
+            client,
+            base_url: base_url.trim_end_matches('/').into(),
+        })
+    }
+}
+
+#[async_trait]
+impl BookMetadataProvider for HttpBookMetadataProvider {
+    async fn find_by_isbn(&self, isbn: &Isbn) -> AppResult<Option<BookMetadata>> {
+        let bibkey = format!("ISBN:{isbn}");
+        let mut books: HashMap<String, OpenLibraryBook> = self
+            .client
+            .get(format!("{}/api/books", self.base_url))
+            .query(&[
+                ("bibkeys", bibkey.as_str()),
+                ("format", "json"),
+                ("jscmd", "data"),
+            ])
+            .send()
+            .await
+            .and_then(|res| res.error_for_status())
+            .map_err(|e| AppError::ExternalServiceError(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| AppError::ExternalServiceError(e.to_string()))?;
+
+        Ok(books
+            .remove(&bibkey)
+            .map(|book| book.into_metadata(isbn.clone())))
+    }
+}
+
+#[derive(Deserialize)]
+struct OpenLibraryBook {
+    title: String,
+    #[serde(default)]
+    subtitle: Option<String>,
+    #[serde(default)]
+    authors: Vec<OpenLibraryAuthor>,
+    #[serde(default)]
+    notes: Option<OpenLibraryText>,
+    #[serde(default)]
+    excerpts: Vec<OpenLibraryExcerpt>,
+}
+
+#[derive(Deserialize)]
+struct OpenLibraryAuthor {
+    name: String,
+}
+
+// 説明文は文字列の場合と {"type": ..., "value": ...} の場合がある
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OpenLibraryText {
+    Plain(String),
+    Typed { value: String },
+}
+
+#[derive(Deserialize)]
+struct OpenLibraryExcerpt {
+    text: String,
+}
+
+impl OpenLibraryBook {
+    fn into_metadata(self, isbn: Isbn) -> BookMetadata {
+        let author = self
+            .authors
+            .into_iter()
+            .map(|a| a.name)
+            .collect::<Vec<_>>()
+            .join(", ");
+        // 説明文がない場合は抜粋、副題の順に使う
+        let description = match self.notes {
+            Some(OpenLibraryText::Plain(value) | OpenLibraryText::Typed { value }) => value,
+            None => self
+                .excerpts
+                .into_iter()
+                .next()
+                .map(|e| e.text)
+                .or(self.subtitle)
+                .unwrap_or_default(),
+        };
+        BookMetadata {
+            isbn,
+            title: self.title,
+            author,
+            description,
+        }
+    }
+}
+
+// ローカルの JSON・JSON Lines・CSV のダンプファイルから取得する
+// 起動時にファイル全体を読み込み、ISBN-13 に正規化した ISBN で引けるようにしておく
+pub struct FileBookMetadataProvider {
+    books: HashMap<Isbn, BookMetadata>,
+}
+
+// ダンプファイルの 1 件分（CSV の場合はヘッダー行の列名）
+#[derive(Deserialize)]
+struct BookMetadataRecord {
+    isbn: String,
+    title: String,
+    #[serde(default)]
+    author: String,
+    #[serde(default)]
+    description: String,
+}
+
+impl FileBookMetadataProvider {
+    pub fn load(path: impl AsRef<Path>) -> AppResult<Self> {
+        let path = path.as_ref();
+        let invalid = |e: &dyn std::fmt::Display| {
+            AppError::ConversionEntityError(format!(
+                "書誌情報のファイル（{}）を読み込めません: {e}",
+                path.display()
+            ))
+        };
+        let content = std::fs::read_to_string(path).map_err(|e| invalid(&e))?;
+        let records: Vec<BookMetadataRecord> = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => serde_json::from_str(&content).map_err(|e| invalid(&e))?,
+            Some("jsonl") => content
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(serde_json::from_str)
+                .collect::<Result<_, _>>()
+                .map_err(|e| invalid(&e))?,
+            Some("csv") => csv::ReaderBuilder::new()
+                .trim(csv::Trim::All)
+                .from_reader(content.as_bytes())
+                .deserialize()
+                .collect::<Result<_, _>>()
+                .map_err(|e| invalid(&e))?,
+            _ => {
+                return Err(invalid(
+                    &"拡張子は json・jsonl・csv のいずれかにしてください",
+                ));
+            }
+        };
+
+        Ok(Self::from_records(records))
+    }
+
+    // ISBN が不正な行は読み飛ばし、同じ ISBN が複数ある場合は最初の行を使う
+    fn from_records(records: Vec<BookMetadataRecord>) -> Self {
+        let mut books = HashMap::new();
+        for record in records {
+            let Ok(isbn) = record.isbn.parse::<Isbn>() else {
+                continue;
+            };
+            books.entry(isbn.clone()).or_insert(BookMetadata {
+                isbn,
+                title: record.title,
+                author: record.author,
+                description: record.description,
+            });
+        }
+        Self { books }
+    }
+}
+
+#[async_trait]
+impl BookMetadataProvider for FileBookMetadataProvider {
+    async fn find_by_isbn(&self, isbn: &Isbn) -> AppResult<Option<BookMetadata>> {
+        Ok(self.books.get(isbn).cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    // 決まったレスポンスを返すスタブの書誌情報サービスを起動し、ベース URL を返す
+    // 受け取ったリクエスト行はチャネルに送る
+    async fn stub_server(
+        status: &'static str,
+        body: &'static str,
+    ) -> (String, tokio::sync::mpsc::UnboundedReceiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = vec![0; 4096];
+                let n = stream.read(&mut buf).await.unwrap();
+                let request = String::from_utf8_lossy(&buf[..n]);
+                let _ = tx.send(request.lines().next().unwrap_or_default().to_string());
+                let response = format!(
+                    "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (format!("http://{addr}/"), rx)
+    }
+
+    fn isbn() -> Isbn {
+        "978-1-59327-828-1".parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_http_provider_found() -> AppResult<()> {
+        let (base_url, mut requests) = stub_server(
+            "200 OK",
+            r#"{"ISBN:9781593278281": {
+                "title": "The Rust Programming Language",
+                "authors": [{"name": "Steve Klabnik"}, {"name": "Carol Nichols"}],
+                "notes": {"type": "/type/text", "value": "The official book"}
+            }}"#,
+        )
+        .await;
+        let provider = HttpBookMetadataProvider::new(&base_url)?;
+
+        let metadata = provider.find_by_isbn(&isbn()).await?.unwrap();
+        assert_eq!(metadata.isbn, isbn());
+        assert_eq!(metadata.title, "The Rust Programming Language");
+        assert_eq!(metadata.author, "Steve Klabnik, Carol Nichols");
+        assert_eq!(metadata.description, "The official book");
+
+        let request = requests.recv().await.unwrap();
+        assert!(request.starts_with("GET /api/books?bibkeys=ISBN%3A9781593278281&"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_http_provider_not_found() -> AppResult<()> {
+        let (base_url, _requests) = stub_server("200 OK", "{}").await;
+        let provider = HttpBookMetadataProvider::new(&base_url)?;
+
+        assert!(provider.find_by_isbn(&isbn()).await?.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_http_provider_server_error() -> AppResult<()> {
+        let (base_url, _requests) = stub_server("500 Internal Server Error", "{}").await;
+        let provider = HttpBookMetadataProvider::new(&base_url)?;
+
+        let res = provider.find_by_isbn(&isbn()).await;
+        assert!(matches!(res, Err(AppError::ExternalServiceError(_))));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_file_provider() -> AppResult<()> {
+        let path = std::env::temp_dir().join(format!("book_metadata_{}.csv", uuid::Uuid::new_v4()));
+        std::fs::write(
+            &path,
+            "isbn,title,author,description\n\
+             not an isbn,Broken,,\n\
+             1-59327-828-4,The Rust Programming Language,Steve Klabnik,The official book\n\
+             9781593278281,Duplicated,,\n",
+        )
+        .unwrap();
+        let provider = FileBookMetadataProvider::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        let provider = provider?;
+
+        // ISBN-10 で登録された行も ISBN-13 で引ける
+        let metadata = provider.find_by_isbn(&isbn()).await?.unwrap();
+        assert_eq!(metadata.title, "The Rust Programming Language");
+        assert_eq!(metadata.author, "Steve Klabnik");
+        assert!(
+            provider
+                .find_by_isbn(&"9784798186016".parse().unwrap())
+                .await?
+                .is_none()
+        );
+
+        Ok(())
+    }
+}
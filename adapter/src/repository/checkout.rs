@@ -3,113 +3,549 @@ use derive_new::new;
 use kernel::{
     model::{
         checkout::{
-            Checkout,
-            event::{CreateCheckout, UpdateReturned},
+            Checkout, OverdueCheckout,
+            event::{CreateCheckout, RenewCheckout, UpdateReturned},
         },
-        id::{BookId, CheckoutId, UserId},
+        id::{BookCopyId, BookId, CheckoutId, UserId},
+        list::{Cursor, CursorListOptions, CursorPaginatedList},
+        role::Role,
     },
     repository::checkout::CheckoutRepository,
 };
-use shared::error::{AppError, AppResult};
+use shared::{
+    config::CheckoutConfig,
+    error::{AppError, AppResult},
+};
+use std::str::FromStr;
 
-use crate::database::{
-    ConnectionPool,
-    model::checkout::{CheckoutRow, CheckoutStateRow, ReturnedCheckoutRow},
+use crate::{
+    database::{
+        ConnectionPool, PgTransaction,
+        model::checkout::{
+            CheckoutLimitRow, CheckoutRow, CheckoutStateRow, OverdueCheckoutRow,
+            ReturnedCheckoutRow,
+        },
+    },
+    repository::reservation::refresh_holds,
 };
 
 #[derive(new)]
 pub struct CheckoutRepositoryImpl {
     db: ConnectionPool,
+    config: CheckoutConfig,
 }
 
 #[async_trait]
 impl CheckoutRepository for CheckoutRepositoryImpl {
     // 貸出操作
     async fn create(&self, event: CreateCheckout) -> AppResult<()> {
-        let mut tx = self.db.begin().await?;
+        let config = self.config;
+        // 同じ蔵書への同時操作で直列化に失敗した場合はトランザクションごとやり直す
+        self.db
+            .transaction_serializable(|tx| Box::pin(Self::create_in_tx(tx, config, event.clone())))
+            .await
+    }
+
+    // 返却期限の延長操作
+    async fn renew(&self, event: RenewCheckout) -> AppResult<()> {
+        let config = self.config;
+        self.db
+            .transaction_serializable(|tx| Box::pin(Self::renew_in_tx(tx, config, event.clone())))
+            .await
+    }
+
+    // 返却操作
+    async fn update_returned(&self, event: UpdateReturned) -> AppResult<()> {
+        let config = self.config;
+        // 同じ蔵書への同時操作で直列化に失敗した場合はトランザクションごとやり直す
+        self.db
+            .transaction_serializable(|tx| {
+                Box::pin(Self::update_returned_in_tx(tx, config, event.clone()))
+            })
+            .await
+    }
+
+    // 未返却一覧
+    async fn find_unreturned_all(
+        &self,
+        options: CursorListOptions,
+    ) -> AppResult<CursorPaginatedList<Checkout>> {
+        let CursorListOptions { limit, cursor } = options;
+
+        // 次のページの有無を判定するため limit + 1 件まで取得する
+        let checkouts = sqlx::query_as!(
+            CheckoutRow,
+            r#"
+                SELECT
+                c.checkout_id AS "checkout_id!: CheckoutId",
+                c.book_id AS "book_id!: BookId",
+                c.copy_id AS "copy_id!: BookCopyId",
+                c.user_id AS "user_id!: UserId",
+                u.name AS user_name,
+                c.checked_out_at,
+                c.due_at,
+                c.renewal_count,
+                c.due_at < CURRENT_TIMESTAMP(3) AS "is_overdue!",
+                b.title,
+                b.author,
+                b.isbn
+                FROM checkouts AS c
+                INNER JOIN books AS b ON b.book_id = c.book_id
+                INNER JOIN users AS u ON u.user_id = c.user_id
+                WHERE $2::TIMESTAMPTZ IS NULL
+                OR (c.checked_out_at, c.checkout_id) > ($2, $3::UUID)
+                ORDER BY c.checked_out_at ASC, c.checkout_id ASC
+                LIMIT $1
+                ;
+            "#,
+            limit.saturating_add(1),
+            cursor.map(|c| c.created_at),
+            cursor.map(|c| c.id),
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .into_iter()
+        .map(Checkout::from)
+        .collect();
+
+        Ok(CursorPaginatedList::from_overfetched(
+            checkouts,
+            limit,
+            checkout_cursor,
+        ))
+    }
+
+    // 延滞中の貸出一覧
+    async fn find_overdue_all(&self) -> AppResult<Vec<OverdueCheckout>> {
+        sqlx::query_as!(
+            OverdueCheckoutRow,
+            r#"
+                SELECT
+                c.checkout_id AS "checkout_id!: CheckoutId",
+                c.book_id AS "book_id!: BookId",
+                c.copy_id AS "copy_id!: BookCopyId",
+                c.user_id AS "user_id!: UserId",
+                u.name AS user_name,
+                c.checked_out_at,
+                c.due_at,
+                c.renewal_count,
+                DATE_PART('day', CURRENT_TIMESTAMP(3) - c.due_at)::BIGINT AS "days_overdue!",
+                b.title,
+                b.author,
+                b.isbn
+                FROM checkouts AS c
+                INNER JOIN books AS b ON b.book_id = c.book_id
+                INNER JOIN users AS u ON u.user_id = c.user_id
+                WHERE c.due_at < CURRENT_TIMESTAMP(3)
+                ORDER BY c.due_at ASC
+                ;
+            "#,
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map(|rows| rows.into_iter().map(OverdueCheckout::from).collect())
+        .map_err(AppError::SpecificOperationError)
+    }
+
+    // ユーザーID に紐づく未返却の貸出情報を取得
+    async fn find_unreturned_by_user_id(&self, user_id: UserId) -> AppResult<Vec<Checkout>> {
+        sqlx::query_as!(
+            CheckoutRow,
+            r#"
+                SELECT
+                c.checkout_id AS "checkout_id!: CheckoutId",
+                c.book_id AS "book_id!: BookId",
+                c.copy_id AS "copy_id!: BookCopyId",
+                c.user_id AS "user_id!: UserId",
+                u.name AS user_name,
+                c.checked_out_at,
+                c.due_at,
+                c.renewal_count,
+                c.due_at < CURRENT_TIMESTAMP(3) AS "is_overdue!",
+                b.title,
+                b.author,
+                b.isbn
+                FROM checkouts AS c
+                INNER JOIN books AS b ON b.book_id = c.book_id
+                INNER JOIN users AS u ON u.user_id = c.user_id
+                WHERE c.user_id = $1
+                ORDER BY c.checked_out_at ASC
+                ;
+            "#,
+            user_id as _
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map(|rows| rows.into_iter().map(Checkout::from).collect())
+        .map_err(AppError::SpecificOperationError)
+    }
+
+    // 蔵書の貸出履歴
+    async fn find_history_by_book_id(
+        &self,
+        book_id: BookId,
+        options: CursorListOptions,
+    ) -> AppResult<CursorPaginatedList<Checkout>> {
+        let CursorListOptions { limit, cursor } = options;
+
+        // 未返却の貸出情報（コピーごとに最大 1 件）を取得し、カーソルより後ろにあるものだけ残す
+        let checkouts = self
+            .find_unreturned_by_book_id(book_id)
+            .await?
+            .into_iter()
+            .filter(|co| {
+                cursor.is_none_or(|c| (co.checked_out_at, co.id.raw()) < (c.created_at, c.id))
+            });
 
-        //トランザクション分離レベルを SERIALIZABLEに設定
-        self.set_transaction_serializable(&mut tx).await?;
+        // 返却済みの貸出情報を取得
+        // 次のページの有無を判定するため limit + 1 件まで取得する
+        let checkout_histories = sqlx::query_as!(
+            ReturnedCheckoutRow,
+            r#"
+                SELECT
+                    rc.checkout_id AS "checkout_id!: CheckoutId",
+                    rc.book_id AS "book_id!: BookId",
+                    rc.copy_id AS "copy_id!: BookCopyId",
+                    rc.user_id AS "user_id: UserId",
+                    u.name AS "user_name?",
+                    rc.checked_out_at,
+                    rc.due_at,
+                    rc.renewal_count,
+                    rc.returned_at,
+                    b.title,
+                    b.author,
+                    b.isbn
+                FROM returned_checkouts AS rc
+                INNER JOIN books AS b ON b.book_id = rc.book_id
+                LEFT OUTER JOIN users AS u ON u.user_id = rc.user_id
+                WHERE rc.book_id = $1
+                AND (
+                    $3::TIMESTAMPTZ IS NULL
+                    OR (rc.checked_out_at, rc.checkout_id) < ($3, $4::UUID)
+                )
+                ORDER BY rc.checked_out_at DESC, rc.checkout_id DESC
+                LIMIT $2
+            "#,
+            book_id as _,
+            limit.saturating_add(1),
+            cursor.map(|c| c.created_at),
+            cursor.map(|c| c.id),
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .into_iter()
+        .map(Checkout::from);
 
-        // チェックアウトの作成処理
+        // 貸出中のコピーより後に借りられて返却済みのコピーもあるため、貸出日時の新しい順に並べ直す
+        let mut checkouts: Vec<Checkout> = checkouts.chain(checkout_histories).collect();
+        checkouts
+            .sort_by(|a, b| (b.checked_out_at, b.id.raw()).cmp(&(a.checked_out_at, a.id.raw())));
+
+        Ok(CursorPaginatedList::from_overfetched(
+            checkouts,
+            limit,
+            checkout_cursor,
+        ))
+    }
+}
+
+impl CheckoutRepositoryImpl {
+    // ロールごとに同時に借りられる冊数の上限
+    fn checkout_limit_for(config: &CheckoutConfig, role_name: &str) -> AppResult<i64> {
+        let role = Role::from_str(role_name)
+            .map_err(|e| AppError::ConversionEntityError(e.to_string()))?;
+        Ok(match role {
+            Role::Admin => config.checkout_limit_admin,
+            Role::User => config.checkout_limit_user,
+        })
+    }
+
+    // 貸出操作のトランザクション内の処理
+    async fn create_in_tx(
+        tx: &mut PgTransaction,
+        config: CheckoutConfig,
+        event: CreateCheckout,
+    ) -> AppResult<()> {
+        // 蔵書が存在し（アーカイブ済みでなく）、貸出中でないコピーが残っているかを確認する
+        // 蔵書の行をロックし、貸出までの間にアーカイブされないようにする
+        let available = sqlx::query_scalar!(
+            r#"
+                SELECT
+                    (
+                        SELECT COUNT(*) FROM book_copies AS bc
+                        WHERE bc.book_id = b.book_id
+                        AND NOT EXISTS(SELECT 1 FROM checkouts AS c WHERE c.copy_id = bc.copy_id)
+                    ) AS "available!"
+                FROM books AS b
+                WHERE b.book_id = $1
+                AND b.deleted_at IS NULL
+                FOR SHARE
+            "#,
+            event.book_id as _
+        )
+        .fetch_optional(&mut **tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .ok_or_else(|| {
+            AppError::EntityNotFound(format!("書籍({})が見つかりませんでした。", event.book_id))
+        })?;
+
+        if available == 0 {
+            return Err(AppError::UnprocessableEntity(format!(
+                "書籍({})はすでにチェックアウトされています。",
+                event.book_id
+            )));
+        }
+
+        // 取り置かれているコピーは、取り置かれている予約者本人しか借りられない
+        // 予約者以外が借りられるのは、貸出中でないコピーが取り置きの冊数より多い場合のみ
+        {
+            refresh_holds(
+                tx,
+                event.book_id,
+                event.checked_out_at,
+                config.hold_pickup_days,
+            )
+            .await?;
+
+            let held_for = sqlx::query_scalar!(
+                r#"
+                    SELECT user_id AS "user_id: UserId" FROM reservations
+                    WHERE book_id = $1 AND hold_expires_at IS NOT NULL
+                "#,
+                event.book_id as _
+            )
+            .fetch_all(&mut **tx)
+            .await
+            .map_err(AppError::SpecificOperationError)?;
+
+            if !held_for.contains(&event.checked_out_by) && available <= held_for.len() as i64 {
+                return Err(AppError::UnprocessableEntity(format!(
+                    "書籍({})は他のユーザーのために取り置かれています。",
+                    event.book_id
+                )));
+            }
+        }
+
+        // 借りるユーザーの貸出数が上限に達していないかを確認する
+        {
+            let row = sqlx::query_as!(
+                CheckoutLimitRow,
+                r#"
+                    SELECT
+                        r.name AS role_name,
+                        u.checkout_limit,
+                        (
+                            SELECT COUNT(*) FROM checkouts AS c
+                            WHERE c.user_id = u.user_id
+                        ) AS "checkout_count!"
+                    FROM users AS u
+                    INNER JOIN roles AS r USING(role_id)
+                    WHERE u.user_id = $1
+                "#,
+                event.checked_out_by as _
+            )
+            .fetch_optional(&mut **tx)
+            .await
+            .map_err(AppError::SpecificOperationError)?
+            .ok_or_else(|| {
+                AppError::EntityNotFound(format!(
+                    "ユーザー({})が見つかりませんでした。",
+                    event.checked_out_by
+                ))
+            })?;
+
+            let limit = match row.checkout_limit {
+                Some(limit) => i64::from(limit),
+                None => Self::checkout_limit_for(&config, &row.role_name)?,
+            };
+            if row.checkout_count >= limit {
+                return Err(AppError::CheckoutLimitExceeded { limit });
+            }
+        }
+
+        // 返却期限は貸出日時から設定された貸出期間が経過した日時とする
+        let due_at = event.checked_out_at + chrono::Duration::days(config.loan_period_days);
+
+        // 貸し出すコピーを決める（指定がない場合は登録の古いコピーから選ぶ）
+        let copy_id = sqlx::query_scalar!(
+            r#"
+                SELECT bc.copy_id AS "copy_id: BookCopyId"
+                FROM book_copies AS bc
+                WHERE bc.book_id = $1
+                AND ($2::UUID IS NULL OR bc.copy_id = $2)
+                AND NOT EXISTS(SELECT 1 FROM checkouts AS c WHERE c.copy_id = bc.copy_id)
+                ORDER BY bc.created_at ASC, bc.copy_id ASC
+                LIMIT 1
+            "#,
+            event.book_id as _,
+            event.copy_id as _,
+        )
+        .fetch_optional(&mut **tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .ok_or_else(|| {
+            AppError::UnprocessableEntity(format!(
+                "書籍({})の指定のコピーは存在しないか、貸出中です。",
+                event.book_id
+            ))
+        })?;
+
+        let checkout_id = CheckoutId::new();
+        let res = sqlx::query!(
+            r#"
+                INSERT INTO checkouts
+                (checkout_id, book_id, copy_id, user_id, checkout_operator_id, checked_out_at, due_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                ;
+            "#,
+            checkout_id as _,
+            event.book_id as _,
+            copy_id as _,
+            event.checked_out_by as _,
+            event.operated_by as _,
+            event.checked_out_at,
+            due_at,
+        )
+        .execute(&mut **tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        if res.rows_affected() == 0 {
+            return Err(AppError::UnprocessableEntity(
+                "No checkout record has been created".into(),
+            ));
+        }
+
+        // 予約していた蔵書を借りた場合は、その予約を完了として削除する
+        sqlx::query!(
+            r#"
+                DELETE FROM reservations WHERE book_id = $1 AND user_id = $2;
+            "#,
+            event.book_id as _,
+            event.checked_out_by as _,
+        )
+        .execute(&mut **tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        Ok(())
+    }
+
+    // 返却期限の延長操作のトランザクション内の処理
+    async fn renew_in_tx(
+        tx: &mut PgTransaction,
+        config: CheckoutConfig,
+        event: RenewCheckout,
+    ) -> AppResult<()> {
+        // 延長操作時は返却操作と同様に、指定の蔵書が存在し、
+        // 指定の貸出が延長を依頼したユーザーによるものかを確認する
         {
             let res = sqlx::query_as!(
                 CheckoutStateRow,
                 r#"
                     SELECT
-                        b.book_id,
-                        c.checkout_id AS "checkout_id?: CheckoutId",
-                        NULL AS "user_id?: UserId"
+                    b.book_id,
+                    c.checkout_id AS "checkout_id?: CheckoutId",
+                    c.user_id AS "user_id?: UserId"
                     FROM books AS b
-                    LEFT OUTER JOIN checkouts AS c USING(book_id)
-                    WHERE book_id = $1
+                    LEFT OUTER JOIN checkouts AS c
+                    ON c.book_id = b.book_id AND c.checkout_id = $2
+                    WHERE b.book_id = $1;
                 "#,
-                event.book_id as _
+                event.book_id as _,
+                event.checkout_id as _,
             )
-            .fetch_optional(&mut *tx)
+            .fetch_optional(&mut **tx)
             .await
             .map_err(AppError::SpecificOperationError)?;
 
             match res {
-                // 指定した書籍がない場合
                 None => {
                     return Err(AppError::EntityNotFound(format!(
-                        "書籍({})が見つかりませんでした。",
+                        "書籍（{}）が見つかりませんでした。",
                         event.book_id
                     )));
                 }
+                // 指定した書籍が貸出中であり、貸出 ID および借りたユーザーが一致する場合のみ延長できる
                 Some(CheckoutStateRow {
-                    checkout_id: Some(_),
+                    checkout_id: Some(c),
+                    user_id: Some(u),
                     ..
-                }) => {
+                }) if (c, u) == (event.checkout_id, event.renewed_by) => {}
+                _ => {
                     return Err(AppError::UnprocessableEntity(format!(
-                        "書籍({})はすでにチェックアウトされています。",
-                        event.book_id
+                        "指定の貸出（ID（{}）, ユーザー（{}）, 書籍（{}））は延長できません。",
+                        event.checkout_id, event.renewed_by, event.book_id
                     )));
                 }
-                _ => {}
             }
         }
 
-        let checkout_id = CheckoutId::new();
+        // 他のユーザーが予約している蔵書は延長できない
+        let reserved = sqlx::query_scalar!(
+            r#"
+                SELECT EXISTS(
+                    SELECT 1 FROM reservations
+                    WHERE book_id = $1 AND user_id <> $2
+                ) AS "reserved!"
+            "#,
+            event.book_id as _,
+            event.renewed_by as _,
+        )
+        .fetch_one(&mut **tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        if reserved {
+            return Err(AppError::UnprocessableEntity(format!(
+                "書籍（{}）は他のユーザーが予約しているため延長できません。",
+                event.book_id
+            )));
+        }
+
+        // 延長回数が上限に達していない場合に限り、返却期限を貸出期間の分だけ延ばす
         let res = sqlx::query!(
             r#"
-                INSERT INTO checkouts
-                (checkout_id, book_id, user_id, checked_out_at)
-                VALUES ($1, $2, $3, $4)
+                UPDATE checkouts
+                SET
+                    due_at = due_at + ($2::BIGINT * INTERVAL '1 day'),
+                    renewal_count = renewal_count + 1
+                WHERE checkout_id = $1
+                AND renewal_count < $3
                 ;
             "#,
-            checkout_id as _,
-            event.book_id as _,
-            event.checked_out_by as _,
-            event.checked_out_at,
+            event.checkout_id as _,
+            config.loan_period_days,
+            config.max_renewals,
         )
-        .execute(&mut *tx)
+        .execute(&mut **tx)
         .await
         .map_err(AppError::SpecificOperationError)?;
 
-        if res.rows_affected() == 0 {
-            return Err(AppError::UnprocessableEntity(
-                "No checkout record has been created".into(),
-            ));
+        if res.rows_affected() < 1 {
+            return Err(AppError::UnprocessableEntity(format!(
+                "貸出（{}）は延長回数の上限（{} 回）に達しています。",
+                event.checkout_id, config.max_renewals
+            )));
         }
 
-        tx.commit().await.map_err(AppError::TransactionError)?;
         Ok(())
     }
 
-    // 返却操作
-    async fn update_returned(&self, event: UpdateReturned) -> AppResult<()> {
-        let mut tx = self.db.begin().await?;
-
-        // トランザクション分離レベルを SERIALIZABLE に設定する
-        self.set_transaction_serializable(&mut tx).await?;
-
+    // 返却操作のトランザクション内の処理
+    async fn update_returned_in_tx(
+        tx: &mut PgTransaction,
+        config: CheckoutConfig,
+        event: UpdateReturned,
+    ) -> AppResult<()> {
         // 返却操作時は事前のチェックとして、以下を調べる。
         // - 指定の蔵書 ID をもつ蔵書が存在するか
         // - 存在した場合、
         // - この蔵書は貸出中であり
-        // - かつ、借りたユーザーが指定のユーザーと同じか
+        // - かつ、借りたユーザーが指定のユーザーと同じか（管理者の場合は問わない）
         //
         // 上記の両方が Yes だった場合、このブロック以降の処理に進む
         {
@@ -121,12 +557,14 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
                     c.checkout_id AS "checkout_id?: CheckoutId",
                     c.user_id AS "user_id?: UserId"
                     FROM books AS b
-                    LEFT OUTER JOIN checkouts AS c USING(book_id)
-                    WHERE book_id = $1;
+                    LEFT OUTER JOIN checkouts AS c
+                    ON c.book_id = b.book_id AND c.checkout_id = $2
+                    WHERE b.book_id = $1;
                 "#,
                 event.book_id as _,
+                event.checkout_id as _,
             )
-            .fetch_optional(&mut *tx)
+            .fetch_optional(&mut **tx)
             .await
             .map_err(AppError::SpecificOperationError)?;
 
@@ -142,7 +580,7 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
                     checkout_id: Some(c),
                     user_id: Some(u),
                     ..
-                }) if (c, u) != (event.checkout_id, event.returned_by) => {
+                }) if c != event.checkout_id || (!event.is_admin && u != event.returned_by) => {
                     return Err(AppError::UnprocessableEntity(format!(
                         " 指定の貸出（ID（{}）, ユーザー（{}）, 書籍（{}））は返却できません。",
                         event.checkout_id, event.returned_by, event.book_id
@@ -157,16 +595,22 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
         let res = sqlx::query!(
             r#"
                 INSERT INTO returned_checkouts
-                (checkout_id, book_id, user_id, checked_out_at, returned_at)
-                SELECT checkout_id, book_id, user_id, checked_out_at, $2
+                (
+                    checkout_id, book_id, copy_id, user_id, checkout_operator_id,
+                    checked_out_at, due_at, renewal_count, returned_at, return_operator_id
+                )
+                SELECT
+                    checkout_id, book_id, copy_id, user_id, checkout_operator_id,
+                    checked_out_at, due_at, renewal_count, $2, $3
                 FROM checkouts
                 WHERE checkout_id = $1
                 ;
             "#,
             event.checkout_id as _,
             event.returned_at,
+            event.returned_by as _,
         )
-        .execute(&mut *tx)
+        .execute(&mut **tx)
         .await
         .map_err(AppError::SpecificOperationError)?;
 
@@ -183,7 +627,7 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
                 "#,
             event.checkout_id as _,
         )
-        .execute(&mut *tx)
+        .execute(&mut **tx)
         .await
         .map_err(AppError::SpecificOperationError)?;
 
@@ -193,147 +637,952 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
             ));
         }
 
-        tx.commit().await.map_err(AppError::TransactionError)?;
+        // 予約がある場合は先頭の予約者のために取り置きを開始する
+        refresh_holds(
+            tx,
+            event.book_id,
+            event.returned_at,
+            config.hold_pickup_days,
+        )
+        .await?;
+
         Ok(())
     }
 
-    // 未返却一覧
-    async fn find_unreturned_all(&self) -> AppResult<Vec<Checkout>> {
-        sqlx::query_as!(
+    // find_history_by_book_id で未返却の貸出情報を取得するためのメソッド
+    async fn find_unreturned_by_book_id(&self, book_id: BookId) -> AppResult<Vec<Checkout>> {
+        let res = sqlx::query_as!(
             CheckoutRow,
             r#"
                 SELECT
-                c.checkout_id AS "checkout_id!: CheckoutId",
-                c.book_id AS "book_id!: BookId",
-                c.user_id AS "user_id!: UserId",
-                u.name AS user_name,
-                c.checked_out_at,
-                b.title,
-                b.author,
-                b.isbn
+                    c.checkout_id AS "checkout_id!: CheckoutId",
+                    c.book_id AS "book_id!: BookId",
+                    c.copy_id AS "copy_id!: BookCopyId",
+                    c.user_id AS "user_id!: UserId",
+                    u.name AS user_name,
+                    c.checked_out_at,
+                    c.due_at,
+                    c.renewal_count,
+                    c.due_at < CURRENT_TIMESTAMP(3) AS "is_overdue!",
+                    b.title,
+                    b.author,
+                    b.isbn
                 FROM checkouts AS c
                 INNER JOIN books AS b ON b.book_id = c.book_id
                 INNER JOIN users AS u ON u.user_id = c.user_id
-                ORDER BY c.checked_out_at ASC
-                ;
+                WHERE c.book_id = $1
+                ORDER BY c.checked_out_at DESC, c.checkout_id DESC
             "#,
+            book_id as _,
         )
         .fetch_all(self.db.inner_ref())
         .await
-        .map(|rows| rows.into_iter().map(Checkout::from).collect())
-        .map_err(AppError::SpecificOperationError)
+        .map_err(AppError::SpecificOperationError)?
+        .into_iter()
+        .map(Checkout::from)
+        .collect();
+
+        Ok(res)
     }
+}
 
-    // ユーザーID に紐づく未返却の貸出情報を取得
-    async fn find_unreturned_by_user_id(&self, user_id: UserId) -> AppResult<Vec<Checkout>> {
-        sqlx::query_as!(
-            CheckoutRow,
-            r#"
-                SELECT
-                c.checkout_id AS "checkout_id!: CheckoutId",
-                c.book_id AS "book_id!: BookId",
-                c.user_id AS "user_id!: UserId",
-                u.name AS user_name,
-                c.checked_out_at,
-                b.title,
-                b.author,
-                b.isbn
-                FROM checkouts AS c
-                INNER JOIN books AS b ON b.book_id = c.book_id
-                INNER JOIN users AS u ON u.user_id = c.user_id
-                WHERE c.user_id = $1
-                ORDER BY c.checked_out_at ASC
-                ;
-            "#,
-            user_id as _
-        )
-        .fetch_all(self.db.inner_ref())
-        .await
-        .map(|rows| rows.into_iter().map(Checkout::from).collect())
-        .map_err(AppError::SpecificOperationError)
+// 貸出一覧のカーソルは貸出日時と貸出 ID の組とする
+fn checkout_cursor(checkout: &Checkout) -> Cursor {
+    Cursor {
+        created_at: checkout.checked_out_at,
+        id: checkout.id.raw(),
     }
+}
 
-    // 蔵書の貸出履歴
-    async fn find_history_by_book_id(&self, book_id: BookId) -> AppResult<Vec<Checkout>> {
-        // 未返却の貸出情報を取得
-        let checkout: Option<Checkout> = self.find_unreturned_by_book_id(book_id).await?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::{
+        book::BookRepositoryImpl, reservation::ReservationRepositoryImpl, user::UserRepositoryImpl,
+    };
+    use chrono::{Duration, SubsecRound, Utc};
+    use kernel::{
+        model::{
+            book::{
+                BookListOptions, BookSortKey, DeleteBook, RestoreBook,
+                event::{CreateBook, CreateBookCopy, DeleteBookCopy, TransferBookOwner},
+            },
+            reservation::event::{CreateReservation, DeleteReservation},
+            user::event::{CreateUser, UpdateUserCheckoutLimit},
+        },
+        repository::{
+            book::BookRepository, reservation::ReservationRepository, user::UserRepository,
+        },
+    };
 
-        // 返却済みの貸出情報を取得
-        let mut checkout_histories: Vec<Checkout> = sqlx::query_as!(
-            ReturnedCheckoutRow,
-            r#"
-                SELECT
-                    rc.checkout_id AS "checkout_id!: CheckoutId",
-                    rc.book_id AS "book_id!: BookId",
-                    rc.user_id AS "user_id!: UserId",
-                    u.name AS user_name,
-                    rc.checked_out_at,
-                    rc.returned_at,
-                    b.title,
-                    b.author,
-                    b.isbn
-                FROM returned_checkouts AS rc
-                INNER JOIN books AS b ON b.book_id = rc.book_id
-                INNER JOIN users AS u ON u.user_id = rc.user_id
-                WHERE rc.book_id = $1
-                ORDER BY rc.checked_out_at DESC
-            "#,
-            book_id as _
+    async fn create_user(pool: &sqlx::PgPool, email: &str) -> AppResult<UserId> {
+        let user = UserRepositoryImpl::new(ConnectionPool::new(pool.clone()), 3)
+            .create(CreateUser {
+                name: "Test User".into(),
+                email: email.into(),
+                password: "password".into(),
+            })
+            .await?;
+        Ok(user.id)
+    }
+
+    async fn create_book(pool: &sqlx::PgPool, owner: UserId, title: &str) -> AppResult<BookId> {
+        BookRepositoryImpl::new(ConnectionPool::new(pool.clone()))
+            .create(
+                CreateBook {
+                    title: title.into(),
+                    author: "Test Author".into(),
+                    isbn: "9781593278281".parse().unwrap(),
+                    description: "Test Description".into(),
+                    barcode: None,
+                    tags: vec![],
+                },
+                owner,
+            )
+            .await?;
+        let book_id = sqlx::query_scalar!(
+            r#"SELECT book_id AS "book_id: BookId" FROM books WHERE title = $1"#,
+            title
         )
-        .fetch_all(self.db.inner_ref())
+        .fetch_one(pool)
         .await
-        .map_err(AppError::SpecificOperationError)?
-        .into_iter()
-        .map(Checkout::from)
-        .collect();
+        .map_err(AppError::SpecificOperationError)?;
+        Ok(book_id)
+    }
 
-        // 貸出中である場合は返却済みの履歴の先頭に追加する
-        if let Some(co) = checkout {
-            checkout_histories.insert(0, co);
+    fn config() -> CheckoutConfig {
+        CheckoutConfig {
+            loan_period_days: 14,
+            max_renewals: 2,
+            hold_pickup_days: 3,
+            checkout_limit_admin: 3,
+            checkout_limit_user: 2,
         }
-
-        Ok(checkout_histories)
     }
-}
 
-impl CheckoutRepositoryImpl {
-    async fn set_transaction_serializable(
-        &self,
-        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
-    ) -> AppResult<()> {
-        sqlx::query!("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE")
-            .execute(&mut **tx)
+    async fn setup(pool: &sqlx::PgPool) -> AppResult<()> {
+        sqlx::query!(r#"INSERT INTO roles(name) VALUES ('Admin'), ('User');"#)
+            .execute(pool)
             .await
             .map_err(AppError::SpecificOperationError)?;
         Ok(())
     }
 
-    // find_history_by_book_id で未返却の貸出情報を取得するためのメソッド
-    async fn find_unreturned_by_book_id(&self, book_id: BookId) -> AppResult<Option<Checkout>> {
-        let res = sqlx::query_as!(
-            CheckoutRow,
-            r#"
-                SELECT
-                    c.checkout_id AS "checkout_id!: CheckoutId",
-                    c.book_id AS "book_id!: BookId",
-                    c.user_id AS "user_id!: UserId",
-                    u.name AS user_name,
-                    c.checked_out_at,
-                    b.title,
-                    b.author,
-                    b.isbn
-                FROM checkouts AS c
-                INNER JOIN books AS b ON b.book_id = c.book_id
-                INNER JOIN users AS u ON u.user_id = c.user_id
-                WHERE c.book_id = $1
-            "#,
-            book_id as _,
-        )
-        .fetch_optional(self.db.inner_ref())
-        .await
-        .map_err(AppError::SpecificOperationError)?
-        .map(Checkout::from);
+    #[sqlx::test]
+    async fn test_overdue_checkouts(pool: sqlx::PgPool) -> AppResult<()> {
+        setup(&pool).await?;
+        let user_id = create_user(&pool, "test@example.com").await?;
+        let overdue_book_id = create_book(&pool, user_id, "Overdue Book").await?;
+        let borrowed_book_id = create_book(&pool, user_id, "Borrowed Book").await?;
+        let checkout_repo = CheckoutRepositoryImpl::new(ConnectionPool::new(pool), config());
 
-        Ok(res)
+        // 20 日前に借りた蔵書は 6 日延滞している
+        let now = Utc::now();
+        checkout_repo
+            .create(CreateCheckout::new(
+                overdue_book_id,
+                None,
+                user_id,
+                user_id,
+                now - Duration::days(20),
+            ))
+            .await?;
+        checkout_repo
+            .create(CreateCheckout::new(
+                borrowed_book_id,
+                None,
+                user_id,
+                user_id,
+                now,
+            ))
+            .await?;
+
+        let overdue = checkout_repo.find_overdue_all().await?;
+        assert_eq!(overdue.len(), 1);
+        assert_eq!(overdue[0].checkout.book.book_id, overdue_book_id);
+        assert_eq!(overdue[0].days_overdue, 6);
+        assert!(overdue[0].checkout.is_overdue);
+
+        let checkouts = checkout_repo.find_unreturned_by_user_id(user_id).await?;
+        assert_eq!(checkouts.len(), 2);
+        for checkout in checkouts {
+            assert_eq!(
+                checkout.due_at,
+                checkout.checked_out_at + Duration::days(14)
+            );
+            assert_eq!(
+                checkout.is_overdue,
+                checkout.book.book_id == overdue_book_id
+            );
+        }
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_renew_checkout(pool: sqlx::PgPool) -> AppResult<()> {
+        setup(&pool).await?;
+        let user_id = create_user(&pool, "test@example.com").await?;
+        let other_user_id = create_user(&pool, "other@example.com").await?;
+        let book_id = create_book(&pool, user_id, "Test Book").await?;
+        let checkout_repo = CheckoutRepositoryImpl::new(ConnectionPool::new(pool), config());
+
+        checkout_repo
+            .create(CreateCheckout::new(
+                book_id,
+                None,
+                user_id,
+                user_id,
+                Utc::now(),
+            ))
+            .await?;
+        let checkout = checkout_repo
+            .find_unreturned_by_book_id(book_id)
+            .await?
+            .remove(0);
+
+        // 借りたユーザー以外は延長できない
+        let res = checkout_repo
+            .renew(RenewCheckout::new(checkout.id, book_id, other_user_id))
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+
+        // 上限回数までは延長するたびに返却期限が貸出期間の分だけ延びる
+        for count in 1..=2 {
+            checkout_repo
+                .renew(RenewCheckout::new(checkout.id, book_id, user_id))
+                .await?;
+            let renewed = checkout_repo
+                .find_unreturned_by_book_id(book_id)
+                .await?
+                .remove(0);
+            assert_eq!(renewed.renewal_count, count);
+            assert_eq!(
+                renewed.due_at,
+                checkout.due_at + Duration::days(14 * count as i64)
+            );
+        }
+
+        // 上限を超える延長は拒否される
+        let res = checkout_repo
+            .renew(RenewCheckout::new(checkout.id, book_id, user_id))
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_hold_queue(pool: sqlx::PgPool) -> AppResult<()> {
+        setup(&pool).await?;
+        let borrower = create_user(&pool, "borrower@example.com").await?;
+        let first = create_user(&pool, "first@example.com").await?;
+        let second = create_user(&pool, "second@example.com").await?;
+        let book_id = create_book(&pool, borrower, "Test Book").await?;
+        let checkout_repo =
+            CheckoutRepositoryImpl::new(ConnectionPool::new(pool.clone()), config());
+        let reservation_repo = ReservationRepositoryImpl::new(ConnectionPool::new(pool), 3);
+
+        // 貸出可能な蔵書は予約できない
+        let res = reservation_repo
+            .create(CreateReservation::new(book_id, first, Utc::now()))
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+
+        checkout_repo
+            .create(CreateCheckout::new(
+                book_id,
+                None,
+                borrower,
+                borrower,
+                Utc::now(),
+            ))
+            .await?;
+        for user_id in [first, second] {
+            reservation_repo
+                .create(CreateReservation::new(book_id, user_id, Utc::now()))
+                .await?;
+        }
+        let queue = reservation_repo.find_by_book_id(book_id).await?;
+        assert_eq!(
+            queue
+                .iter()
+                .map(|r| (r.reserved_by.id, r.position))
+                .collect::<Vec<_>>(),
+            vec![(first, 1), (second, 2)]
+        );
+
+        // 他のユーザーが予約している蔵書は延長できない
+        let checkout = checkout_repo
+            .find_unreturned_by_book_id(book_id)
+            .await?
+            .remove(0);
+        let res = checkout_repo
+            .renew(RenewCheckout::new(checkout.id, book_id, borrower))
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+
+        // 返却されると先頭の予約者のために取り置かれる
+        let returned_at = Utc::now().trunc_subsecs(3);
+        checkout_repo
+            .update_returned(UpdateReturned::new(
+                checkout.id,
+                book_id,
+                borrower,
+                false,
+                returned_at,
+            ))
+            .await?;
+        let queue = reservation_repo.find_by_book_id(book_id).await?;
+        assert_eq!(
+            queue[0].hold_expires_at,
+            Some(returned_at + Duration::days(3))
+        );
+        assert_eq!(queue[1].hold_expires_at, None);
+
+        // 取り置き中は予約者本人しか借りられない
+        let res = checkout_repo
+            .create(CreateCheckout::new(
+                book_id,
+                None,
+                second,
+                second,
+                Utc::now(),
+            ))
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+        checkout_repo
+            .create(CreateCheckout::new(book_id, None, first, first, Utc::now()))
+            .await?;
+
+        // 借りた予約者の予約は完了として削除される
+        assert!(reservation_repo.find_by_user_id(first).await?.is_empty());
+        let queue = reservation_repo.find_by_book_id(book_id).await?;
+        assert_eq!(queue.len(), 1);
+        assert_eq!((queue[0].reserved_by.id, queue[0].position), (second, 1));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_checkout_limit(pool: sqlx::PgPool) -> AppResult<()> {
+        setup(&pool).await?;
+        let user_id = create_user(&pool, "test@example.com").await?;
+        let mut book_ids = Vec::new();
+        for title in ["Book 1", "Book 2", "Book 3"] {
+            book_ids.push(create_book(&pool, user_id, title).await?);
+        }
+        let user_repo = UserRepositoryImpl::new(ConnectionPool::new(pool.clone()), 3);
+        let checkout_repo = CheckoutRepositoryImpl::new(ConnectionPool::new(pool), config());
+
+        // 一般ユーザーはロールごとの上限（2 冊）まで借りられる
+        for book_id in &book_ids[..2] {
+            checkout_repo
+                .create(CreateCheckout::new(
+                    *book_id,
+                    None,
+                    user_id,
+                    user_id,
+                    Utc::now(),
+                ))
+                .await?;
+        }
+        let res = checkout_repo
+            .create(CreateCheckout::new(
+                book_ids[2],
+                None,
+                user_id,
+                user_id,
+                Utc::now(),
+            ))
+            .await;
+        assert!(matches!(
+            res,
+            Err(AppError::CheckoutLimitExceeded { limit: 2 })
+        ));
+
+        // ユーザーごとの上限が設定されている場合はそちらが優先される
+        user_repo
+            .update_checkout_limit(UpdateUserCheckoutLimit {
+                user_id,
+                checkout_limit: Some(3),
+            })
+            .await?;
+        checkout_repo
+            .create(CreateCheckout::new(
+                book_ids[2],
+                None,
+                user_id,
+                user_id,
+                Utc::now(),
+            ))
+            .await?;
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_concurrent_checkout_and_return(pool: sqlx::PgPool) -> AppResult<()> {
+        setup(&pool).await?;
+        let mut user_ids = Vec::new();
+        for i in 0..8 {
+            user_ids.push(create_user(&pool, &format!("test{i}@example.com")).await?);
+        }
+        let book_id = create_book(&pool, user_ids[0], "Test Book").await?;
+        let checkout_repo = std::sync::Arc::new(CheckoutRepositoryImpl::new(
+            ConnectionPool::new(pool),
+            config(),
+        ));
+
+        // 同じ蔵書を同時に借りようとしても、直列化の失敗は内部でやり直されるため
+        // 1 人だけが借りられ、残りは貸出中として扱われる
+        let mut tasks = tokio::task::JoinSet::new();
+        for user_id in user_ids.clone() {
+            let repo = checkout_repo.clone();
+            tasks.spawn(async move {
+                let res = repo
+                    .create(CreateCheckout::new(
+                        book_id,
+                        None,
+                        user_id,
+                        user_id,
+                        Utc::now(),
+                    ))
+                    .await;
+                (user_id, res)
+            });
+        }
+        let mut borrower = None;
+        while let Some(joined) = tasks.join_next().await {
+            match joined.expect("task panicked") {
+                (user_id, Ok(())) => {
+                    assert!(borrower.is_none());
+                    borrower = Some(user_id);
+                }
+                (_, Err(e)) => assert!(matches!(e, AppError::UnprocessableEntity(_)), "{e:?}"),
+            }
+        }
+        let borrower = borrower.expect("one checkout should succeed");
+
+        // 返却と他のユーザーによる貸出が同時に行われても、直列化の失敗で終わることはない
+        let checkout = checkout_repo
+            .find_unreturned_by_book_id(book_id)
+            .await?
+            .remove(0);
+        let mut tasks = tokio::task::JoinSet::new();
+        {
+            let repo = checkout_repo.clone();
+            tasks.spawn(async move {
+                repo.update_returned(UpdateReturned::new(
+                    checkout.id,
+                    book_id,
+                    borrower,
+                    false,
+                    Utc::now(),
+                ))
+                .await
+            });
+        }
+        for user_id in user_ids.into_iter().filter(|u| *u != borrower) {
+            let repo = checkout_repo.clone();
+            tasks.spawn(async move {
+                repo.create(CreateCheckout::new(
+                    book_id,
+                    None,
+                    user_id,
+                    user_id,
+                    Utc::now(),
+                ))
+                .await
+            });
+        }
+        let mut succeeded = 0;
+        while let Some(joined) = tasks.join_next().await {
+            match joined.expect("task panicked") {
+                Ok(()) => succeeded += 1,
+                Err(e) => assert!(matches!(e, AppError::UnprocessableEntity(_)), "{e:?}"),
+            }
+        }
+        // 返却は必ず成功し、貸出は高々 1 件だけ成功する
+        assert!((1..=2).contains(&succeeded));
+        let history = checkout_repo
+            .find_history_by_book_id(
+                book_id,
+                CursorListOptions {
+                    limit: 20,
+                    cursor: None,
+                },
+            )
+            .await?;
+        assert_eq!(history.items.len(), succeeded);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_concurrent_reservations(pool: sqlx::PgPool) -> AppResult<()> {
+        setup(&pool).await?;
+        let mut user_ids = Vec::new();
+        for i in 0..8 {
+            user_ids.push(create_user(&pool, &format!("test{i}@example.com")).await?);
+        }
+        let borrower = user_ids.remove(0);
+        let book_id = create_book(&pool, borrower, "Test Book").await?;
+        let checkout_repo =
+            CheckoutRepositoryImpl::new(ConnectionPool::new(pool.clone()), config());
+        let reservation_repo =
+            std::sync::Arc::new(ReservationRepositoryImpl::new(ConnectionPool::new(pool), 3));
+        checkout_repo
+            .create(CreateCheckout::new(
+                book_id,
+                None,
+                borrower,
+                borrower,
+                Utc::now(),
+            ))
+            .await?;
+
+        // 同じ蔵書を同時に予約しても、直列化の失敗は内部でやり直されるため全員が予約できる
+        let mut tasks = tokio::task::JoinSet::new();
+        for user_id in user_ids.clone() {
+            let repo = reservation_repo.clone();
+            tasks.spawn(async move {
+                repo.create(CreateReservation::new(book_id, user_id, Utc::now()))
+                    .await
+            });
+        }
+        while let Some(joined) = tasks.join_next().await {
+            joined.expect("task panicked")?;
+        }
+        let queue = reservation_repo.find_by_book_id(book_id).await?;
+        assert_eq!(queue.len(), user_ids.len());
+
+        // 同時に取り消しても、すべての取り消しが成功する
+        let mut tasks = tokio::task::JoinSet::new();
+        for reservation in queue {
+            let repo = reservation_repo.clone();
+            tasks.spawn(async move {
+                repo.delete(DeleteReservation::new(
+                    reservation.id,
+                    book_id,
+                    reservation.reserved_by.id,
+                    false,
+                    Utc::now(),
+                ))
+                .await
+            });
+        }
+        while let Some(joined) = tasks.join_next().await {
+            joined.expect("task panicked")?;
+        }
+        assert!(reservation_repo.find_by_book_id(book_id).await?.is_empty());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_checkout_history_with_cursor(pool: sqlx::PgPool) -> AppResult<()> {
+        setup(&pool).await?;
+        let user_id = create_user(&pool, "test@example.com").await?;
+        let book_id = create_book(&pool, user_id, "Test Book").await?;
+        let checkout_repo = CheckoutRepositoryImpl::new(ConnectionPool::new(pool), config());
+
+        // 3 回貸出・返却を繰り返した後、4 回目の貸出中の状態にする
+        let start = Utc::now().trunc_subsecs(3) - Duration::days(30);
+        let mut expected = Vec::new();
+        for i in 0..4 {
+            let checked_out_at = start + Duration::days(i);
+            checkout_repo
+                .create(CreateCheckout::new(
+                    book_id,
+                    None,
+                    user_id,
+                    user_id,
+                    checked_out_at,
+                ))
+                .await?;
+            let checkout = checkout_repo
+                .find_unreturned_by_book_id(book_id)
+                .await?
+                .remove(0);
+            expected.push(checkout.id);
+            if i < 3 {
+                checkout_repo
+                    .update_returned(UpdateReturned::new(
+                        checkout.id,
+                        book_id,
+                        user_id,
+                        false,
+                        checked_out_at + Duration::hours(1),
+                    ))
+                    .await?;
+            }
+        }
+        expected.reverse();
+
+        // 貸出中のものを先頭に、貸出日時の新しい順に 2 件ずつ取得できる
+        let mut ids = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = checkout_repo
+                .find_history_by_book_id(book_id, CursorListOptions { limit: 2, cursor })
+                .await?;
+            assert!(page.items.len() <= 2);
+            ids.extend(page.items.iter().map(|c| c.id));
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        assert_eq!(ids, expected);
+
+        let unreturned = checkout_repo
+            .find_unreturned_all(CursorListOptions {
+                limit: 1,
+                cursor: None,
+            })
+            .await?;
+        assert_eq!(unreturned.items.len(), 1);
+        assert!(unreturned.next_cursor.is_none());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_checkout_multiple_copies(pool: sqlx::PgPool) -> AppResult<()> {
+        setup(&pool).await?;
+        let owner = create_user(&pool, "owner@example.com").await?;
+        let first = create_user(&pool, "first@example.com").await?;
+        let second = create_user(&pool, "second@example.com").await?;
+        let third = create_user(&pool, "third@example.com").await?;
+        let book_id = create_book(&pool, owner, "Test Book").await?;
+        let book_repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let checkout_repo = CheckoutRepositoryImpl::new(ConnectionPool::new(pool), config());
+
+        book_repo
+            .create_copy(CreateBookCopy {
+                book_id,
+                barcode: Some("BC-SECOND".into()),
+                requested_user: owner,
+            })
+            .await?;
+        // 同じバーコードのコピーは登録できない
+        let res = book_repo
+            .create_copy(CreateBookCopy {
+                book_id,
+                barcode: Some("BC-SECOND".into()),
+                requested_user: owner,
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+
+        // コピーの数だけ別々のユーザーが借りられる
+        for user_id in [first, second] {
+            checkout_repo
+                .create(CreateCheckout::new(
+                    book_id,
+                    None,
+                    user_id,
+                    user_id,
+                    Utc::now(),
+                ))
+                .await?;
+        }
+        let res = checkout_repo
+            .create(CreateCheckout::new(book_id, None, third, third, Utc::now()))
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+
+        let book = book_repo.find_by_id(book_id).await?.unwrap();
+        assert_eq!(book.total_copies(), 2);
+        assert_eq!(book.available_copies(), 0);
+
+        // 貸出中のコピーは削除できない
+        let checkouts = checkout_repo.find_unreturned_by_book_id(book_id).await?;
+        assert_eq!(checkouts.len(), 2);
+        let checkout = checkouts
+            .into_iter()
+            .find(|c| c.checked_out_by.id == first)
+            .unwrap();
+        let res = book_repo
+            .delete_copy(DeleteBookCopy {
+                book_id,
+                copy_id: checkout.book.copy_id,
+                requested_user: owner,
+                is_admin: false,
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+
+        // 返却されたコピーは他のユーザーが借りられ、貸出履歴にはどちらのコピーの貸出も含まれる
+        checkout_repo
+            .update_returned(UpdateReturned::new(
+                checkout.id,
+                book_id,
+                first,
+                false,
+                Utc::now(),
+            ))
+            .await?;
+        let book = book_repo.find_by_id(book_id).await?.unwrap();
+        assert_eq!(book.available_copies(), 1);
+        checkout_repo
+            .create(CreateCheckout::new(
+                book_id,
+                Some(checkout.book.copy_id),
+                third,
+                third,
+                Utc::now(),
+            ))
+            .await?;
+        let history = checkout_repo
+            .find_history_by_book_id(
+                book_id,
+                CursorListOptions {
+                    limit: 10,
+                    cursor: None,
+                },
+            )
+            .await?;
+        assert_eq!(history.items.len(), 3);
+        assert!(
+            history
+                .items
+                .windows(2)
+                .all(|w| w[0].checked_out_at >= w[1].checked_out_at)
+        );
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_delete_last_copy(pool: sqlx::PgPool) -> AppResult<()> {
+        setup(&pool).await?;
+        let owner = create_user(&pool, "owner@example.com").await?;
+        let book_id = create_book(&pool, owner, "Test Book").await?;
+        let book_repo = BookRepositoryImpl::new(ConnectionPool::new(pool));
+
+        book_repo
+            .create_copy(CreateBookCopy {
+                book_id,
+                barcode: Some("BC-SECOND".into()),
+                requested_user: owner,
+            })
+            .await?;
+        let book = book_repo.find_by_id(book_id).await?.unwrap();
+        let delete = |copy_id| DeleteBookCopy {
+            book_id,
+            copy_id,
+            requested_user: owner,
+            is_admin: false,
+        };
+
+        // 2 冊のうち 1 冊は削除できるが、最後に残ったコピーは削除できない
+        book_repo.delete_copy(delete(book.copies[0].id)).await?;
+        let res = book_repo.delete_copy(delete(book.copies[1].id)).await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+
+        let book = book_repo.find_by_id(book_id).await?.unwrap();
+        assert_eq!(book.total_copies(), 1);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_archive_book(pool: sqlx::PgPool) -> AppResult<()> {
+        setup(&pool).await?;
+        let owner = create_user(&pool, "owner@example.com").await?;
+        let other = create_user(&pool, "other@example.com").await?;
+        let book_id = create_book(&pool, owner, "Test Book").await?;
+        let book_repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let checkout_repo =
+            CheckoutRepositoryImpl::new(ConnectionPool::new(pool.clone()), config());
+        let reservation_repo = ReservationRepositoryImpl::new(ConnectionPool::new(pool), 3);
+
+        let delete = |requested_user| DeleteBook {
+            book_id,
+            reason: Some("破損のため廃棄".into()),
+            requested_user,
+            is_admin: false,
+            expected_version: None,
+        };
+        let restore = |requested_user| RestoreBook {
+            book_id,
+            requested_user,
+            is_admin: false,
+        };
+        let list_options = |include_archived| BookListOptions {
+            limit: 20,
+            offset: 0,
+            query: None,
+            availability: None,
+            owner: None,
+            author: None,
+            isbn: None,
+            sort: BookSortKey::default(),
+            cursor: None,
+            tags: vec![],
+            include_archived,
+        };
+
+        checkout_repo
+            .create(CreateCheckout::new(book_id, None, owner, owner, Utc::now()))
+            .await?;
+        reservation_repo
+            .create(CreateReservation::new(book_id, other, Utc::now()))
+            .await?;
+
+        // 貸出中の蔵書はアーカイブできない
+        let res = book_repo.delete(delete(owner)).await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+
+        let checkout = checkout_repo
+            .find_unreturned_by_book_id(book_id)
+            .await?
+            .remove(0);
+        checkout_repo
+            .update_returned(UpdateReturned::new(
+                checkout.id,
+                book_id,
+                owner,
+                false,
+                Utc::now(),
+            ))
+            .await?;
+
+        // 所有者以外はアーカイブできない
+        let res = book_repo.delete(delete(other)).await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+
+        book_repo.delete(delete(owner)).await?;
+
+        // アーカイブした蔵書は一覧に含まれず、貸出履歴は残り、予約は取り消される
+        assert_eq!(book_repo.find_all(list_options(false)).await?.total, 0);
+        assert_eq!(book_repo.find_all(list_options(true)).await?.total, 1);
+        let book = book_repo.find_by_id(book_id).await?.unwrap();
+        let archive = book.archive.unwrap();
+        assert_eq!(archive.reason.as_deref(), Some("破損のため廃棄"));
+        let history = checkout_repo
+            .find_history_by_book_id(
+                book_id,
+                CursorListOptions {
+                    limit: 20,
+                    cursor: None,
+                },
+            )
+            .await?;
+        assert_eq!(history.items.len(), 1);
+        assert!(reservation_repo.find_by_book_id(book_id).await?.is_empty());
+
+        // アーカイブした蔵書は貸し出せず、予約もできない
+        let res = checkout_repo
+            .create(CreateCheckout::new(book_id, None, other, other, Utc::now()))
+            .await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+        let res = reservation_repo
+            .create(CreateReservation::new(book_id, other, Utc::now()))
+            .await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+
+        // 二重にはアーカイブできず、所有者以外は元に戻せない
+        let res = book_repo.delete(delete(owner)).await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+        let res = book_repo.restore(restore(other)).await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+
+        book_repo.restore(restore(owner)).await?;
+        assert_eq!(book_repo.find_all(list_options(false)).await?.total, 1);
+        assert!(
+            book_repo
+                .find_by_id(book_id)
+                .await?
+                .unwrap()
+                .archive
+                .is_none()
+        );
+        checkout_repo
+            .create(CreateCheckout::new(book_id, None, other, other, Utc::now()))
+            .await?;
+
+        let res = book_repo.restore(restore(owner)).await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_transfer_book_owner(pool: sqlx::PgPool) -> AppResult<()> {
+        setup(&pool).await?;
+        let owner = create_user(&pool, "owner@example.com").await?;
+        let other = create_user(&pool, "other@example.com").await?;
+        let successor = create_user(&pool, "successor@example.com").await?;
+        let book_id = create_book(&pool, owner, "Test Book").await?;
+        let book_repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let checkout_repo = CheckoutRepositoryImpl::new(ConnectionPool::new(pool), config());
+
+        book_repo
+            .create_copy(CreateBookCopy {
+                book_id,
+                barcode: None,
+                requested_user: other,
+            })
+            .await?;
+
+        let transfer = |new_owner, requested_user| TransferBookOwner {
+            book_id,
+            new_owner,
+            requested_user,
+            is_admin: false,
+        };
+
+        checkout_repo
+            .create(CreateCheckout::new(book_id, None, other, other, Utc::now()))
+            .await?;
+
+        // 貸出中の蔵書は所有者を変更できない
+        let res = book_repo.transfer_owner(transfer(successor, owner)).await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+
+        let checkout = checkout_repo
+            .find_unreturned_by_book_id(book_id)
+            .await?
+            .remove(0);
+        checkout_repo
+            .update_returned(UpdateReturned::new(
+                checkout.id,
+                book_id,
+                other,
+                false,
+                Utc::now(),
+            ))
+            .await?;
+
+        // 所有者以外は変更できず、存在しないユーザーや現在の所有者には変更できない
+        let res = book_repo.transfer_owner(transfer(successor, other)).await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+        let res = book_repo
+            .transfer_owner(transfer(UserId::new(), owner))
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+        let res = book_repo.transfer_owner(transfer(owner, owner)).await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+
+        book_repo.transfer_owner(transfer(successor, owner)).await?;
+
+        // 元の所有者のコピーだけが引き継がれ、他のユーザーが追加したコピーはそのまま
+        let book = book_repo.find_by_id(book_id).await?.unwrap();
+        assert_eq!(book.owner.id, successor);
+        assert_eq!(book.copies.len(), 2);
+        assert!(book.copies.iter().any(|copy| copy.owner.id == successor));
+        assert!(book.copies.iter().any(|copy| copy.owner.id == other));
+
+        // 変更は履歴に記録される
+        let transfers = book_repo.find_owner_transfers(book_id).await?;
+        assert_eq!(transfers.len(), 1);
+        assert_eq!(transfers[0].from.id, owner);
+        assert_eq!(transfers[0].to.id, successor);
+        assert_eq!(transfers[0].transferred_by.id, owner);
+
+        Ok(())
     }
 }
@@ -1,5 +1,9 @@
 pub mod auth;
+pub mod blob_storage;
 pub mod book;
+pub mod book_metadata;
 pub mod checkout;
 pub mod health;
+pub mod reservation;
+pub mod tag;
 pub mod user;
@@ -0,0 +1,315 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use derive_new::new;
+use kernel::{
+    model::{
+        id::{BookId, ReservationId, UserId},
+        reservation::{
+            Reservation,
+            event::{CreateReservation, DeleteReservation},
+        },
+    },
+    repository::reservation::ReservationRepository,
+};
+use shared::error::{AppError, AppResult};
+
+use crate::database::{ConnectionPool, PgTransaction, model::reservation::ReservationRow};
+
+#[derive(new)]
+pub struct ReservationRepositoryImpl {
+    db: ConnectionPool,
+    // 予約者のために返却された蔵書を取り置いておく日数
+    hold_pickup_days: i64,
+}
+
+#[async_trait]
+impl ReservationRepository for ReservationRepositoryImpl {
+    // 予約操作
+    async fn create(&self, event: CreateReservation) -> AppResult<()> {
+        let hold_pickup_days = self.hold_pickup_days;
+        // 同じ蔵書への同時操作で直列化に失敗した場合はトランザクションごとやり直す
+        self.db
+            .transaction_serializable(|tx| {
+                Box::pin(Self::create_in_tx(tx, hold_pickup_days, event.clone()))
+            })
+            .await
+    }
+
+    // 予約取り消し操作
+    async fn delete(&self, event: DeleteReservation) -> AppResult<()> {
+        let hold_pickup_days = self.hold_pickup_days;
+        self.db
+            .transaction_serializable(|tx| {
+                Box::pin(Self::delete_in_tx(tx, hold_pickup_days, event.clone()))
+            })
+            .await
+    }
+
+    // 蔵書の予約待ち一覧
+    async fn find_by_book_id(&self, book_id: BookId) -> AppResult<Vec<Reservation>> {
+        sqlx::query_as!(
+            ReservationRow,
+            r#"
+                WITH queue AS (
+                    SELECT
+                        r.*,
+                        ROW_NUMBER() OVER (
+                            PARTITION BY r.book_id
+                            ORDER BY r.reserved_at ASC, r.reservation_id ASC
+                        ) AS position
+                    FROM reservations AS r
+                    WHERE r.hold_expires_at IS NULL
+                    OR r.hold_expires_at > CURRENT_TIMESTAMP(3)
+                )
+                SELECT
+                    q.reservation_id AS "reservation_id!: ReservationId",
+                    q.book_id AS "book_id!: BookId",
+                    q.user_id AS "user_id!: UserId",
+                    u.name AS user_name,
+                    q.reserved_at AS "reserved_at!",
+                    q.position AS "position!",
+                    q.hold_expires_at,
+                    b.title,
+                    b.author,
+                    b.isbn
+                FROM queue AS q
+                INNER JOIN books AS b ON b.book_id = q.book_id
+                INNER JOIN users AS u ON u.user_id = q.user_id
+                WHERE q.book_id = $1
+                ORDER BY q.position ASC
+                ;
+            "#,
+            book_id as _
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map(|rows| rows.into_iter().map(Reservation::from).collect())
+        .map_err(AppError::SpecificOperationError)
+    }
+
+    // ユーザー ID に紐づく予約一覧
+    async fn find_by_user_id(&self, user_id: UserId) -> AppResult<Vec<Reservation>> {
+        sqlx::query_as!(
+            ReservationRow,
+            r#"
+                WITH queue AS (
+                    SELECT
+                        r.*,
+                        ROW_NUMBER() OVER (
+                            PARTITION BY r.book_id
+                            ORDER BY r.reserved_at ASC, r.reservation_id ASC
+                        ) AS position
+                    FROM reservations AS r
+                    WHERE r.hold_expires_at IS NULL
+                    OR r.hold_expires_at > CURRENT_TIMESTAMP(3)
+                )
+                SELECT
+                    q.reservation_id AS "reservation_id!: ReservationId",
+                    q.book_id AS "book_id!: BookId",
+                    q.user_id AS "user_id!: UserId",
+                    u.name AS user_name,
+                    q.reserved_at AS "reserved_at!",
+                    q.position AS "position!",
+                    q.hold_expires_at,
+                    b.title,
+                    b.author,
+                    b.isbn
+                FROM queue AS q
+                INNER JOIN books AS b ON b.book_id = q.book_id
+                INNER JOIN users AS u ON u.user_id = q.user_id
+                WHERE q.user_id = $1
+                ORDER BY q.reserved_at ASC
+                ;
+            "#,
+            user_id as _
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map(|rows| rows.into_iter().map(Reservation::from).collect())
+        .map_err(AppError::SpecificOperationError)
+    }
+}
+
+impl ReservationRepositoryImpl {
+    // 予約操作のトランザクション内の処理
+    async fn create_in_tx(
+        tx: &mut PgTransaction,
+        hold_pickup_days: i64,
+        event: CreateReservation,
+    ) -> AppResult<()> {
+        // 蔵書が存在し、アーカイブ済みでないかを確認する
+        let exists = sqlx::query_scalar!(
+            r#"SELECT EXISTS(SELECT 1 FROM books WHERE book_id = $1 AND deleted_at IS NULL) AS "exists!""#,
+            event.book_id as _,
+        )
+        .fetch_one(&mut **tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        if !exists {
+            return Err(AppError::EntityNotFound(format!(
+                "書籍（{}）が見つかりませんでした。",
+                event.book_id
+            )));
+        }
+
+        refresh_holds(tx, event.book_id, event.reserved_at, hold_pickup_days).await?;
+
+        let state = sqlx::query!(
+            r#"
+                SELECT
+                    EXISTS(
+                        SELECT 1 FROM checkouts
+                        WHERE book_id = $1 AND user_id = $2
+                    ) AS "is_borrower!",
+                    (
+                        SELECT COUNT(*) FROM book_copies AS bc
+                        WHERE bc.book_id = $1
+                        AND NOT EXISTS(SELECT 1 FROM checkouts AS c WHERE c.copy_id = bc.copy_id)
+                    ) AS "available!",
+                    (
+                        SELECT COUNT(*) FROM reservations
+                        WHERE book_id = $1 AND hold_expires_at IS NOT NULL
+                    ) AS "held!"
+            "#,
+            event.book_id as _,
+            event.reserved_by as _,
+        )
+        .fetch_one(&mut **tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        // 自分が借りている蔵書は予約できない
+        if state.is_borrower {
+            return Err(AppError::UnprocessableEntity(format!(
+                "書籍（{}）はすでに借りています。",
+                event.book_id
+            )));
+        }
+
+        // 取り置かれていないコピーが残っている蔵書は、予約せずにそのまま借りられる
+        if state.available > state.held {
+            return Err(AppError::UnprocessableEntity(format!(
+                "書籍（{}）は貸出可能なため予約できません。",
+                event.book_id
+            )));
+        }
+
+        let res = sqlx::query!(
+            r#"
+                INSERT INTO reservations (reservation_id, book_id, user_id, reserved_at)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (book_id, user_id) DO NOTHING
+                ;
+            "#,
+            ReservationId::new() as _,
+            event.book_id as _,
+            event.reserved_by as _,
+            event.reserved_at,
+        )
+        .execute(&mut **tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        if res.rows_affected() < 1 {
+            return Err(AppError::UnprocessableEntity(format!(
+                "書籍（{}）はすでに予約しています。",
+                event.book_id
+            )));
+        }
+
+        Ok(())
+    }
+
+    // 予約取り消し操作のトランザクション内の処理
+    async fn delete_in_tx(
+        tx: &mut PgTransaction,
+        hold_pickup_days: i64,
+        event: DeleteReservation,
+    ) -> AppResult<()> {
+        // 予約したユーザー本人か管理者のみ取り消せる
+        let res = sqlx::query!(
+            r#"
+                DELETE FROM reservations
+                WHERE reservation_id = $1
+                AND book_id = $2
+                AND ($3 OR user_id = $4)
+            "#,
+            event.reservation_id as _,
+            event.book_id as _,
+            event.is_admin,
+            event.requested_user as _,
+        )
+        .execute(&mut **tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        if res.rows_affected() < 1 {
+            return Err(AppError::EntityNotFound(
+                "Specified reservation not found".into(),
+            ));
+        }
+
+        // 取り置き中の予約が取り消された場合は次の予約者に取り置きを回す
+        refresh_holds(tx, event.book_id, event.deleted_at, hold_pickup_days).await?;
+
+        Ok(())
+    }
+}
+
+// 蔵書の取り置き状態を最新にする
+// - 受け取り期限を過ぎた取り置きは予約ごと削除する
+// - 貸出中でも取り置き中でもないコピーが残っている場合は、その冊数だけ先頭の予約者から順に取り置きを設定する
+pub(crate) async fn refresh_holds(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    book_id: BookId,
+    now: DateTime<Utc>,
+    hold_pickup_days: i64,
+) -> AppResult<()> {
+    sqlx::query!(
+        r#"
+            DELETE FROM reservations
+            WHERE book_id = $1
+            AND hold_expires_at <= $2
+            ;
+        "#,
+        book_id as _,
+        now,
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+
+    sqlx::query!(
+        r#"
+            UPDATE reservations
+            SET hold_expires_at = $2::TIMESTAMPTZ + ($3::BIGINT * INTERVAL '1 day')
+            WHERE reservation_id IN (
+                SELECT reservation_id FROM reservations
+                WHERE book_id = $1
+                AND hold_expires_at IS NULL
+                ORDER BY reserved_at ASC, reservation_id ASC
+                LIMIT GREATEST(
+                    0,
+                    (
+                        SELECT COUNT(*) FROM book_copies AS bc
+                        WHERE bc.book_id = $1
+                        AND NOT EXISTS(SELECT 1 FROM checkouts AS c WHERE c.copy_id = bc.copy_id)
+                    ) - (
+                        SELECT COUNT(*) FROM reservations
+                        WHERE book_id = $1 AND hold_expires_at IS NOT NULL
+                    )
+                )
+            )
+            ;
+        "#,
+        book_id as _,
+        now,
+        hold_pickup_days,
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+
+    Ok(())
+}
@@ -0,0 +1,105 @@
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::{
+    model::{
+        id::TagId,
+        tag::{
+            Tag,
+            event::{CreateTag, DeleteTag, UpdateTag},
+        },
+    },
+    repository::tag::TagRepository,
+};
+use shared::error::{AppError, AppResult};
+
+use crate::database::{ConnectionPool, model::tag::TagRow};
+
+#[derive(new)]
+pub struct TagRepositoryImpl {
+    db: ConnectionPool,
+}
+
+#[async_trait]
+impl TagRepository for TagRepositoryImpl {
+    async fn find_all(&self) -> AppResult<Vec<Tag>> {
+        let rows = sqlx::query_as!(
+            TagRow,
+            r#"
+                SELECT tag_id AS "tag_id: TagId", name
+                FROM tags
+                ORDER BY LOWER(name) ASC, tag_id ASC
+            "#
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        Ok(rows.into_iter().map(Tag::from).collect())
+    }
+
+    async fn create(&self, event: CreateTag) -> AppResult<Tag> {
+        let row = sqlx::query_as!(
+            TagRow,
+            r#"
+                INSERT INTO tags (tag_id, name)
+                VALUES ($1, $2)
+                RETURNING tag_id AS "tag_id: TagId", name
+            "#,
+            TagId::new() as _,
+            event.name,
+        )
+        .fetch_one(self.db.inner_ref())
+        .await
+        .map_err(|e| duplicated_name_or(e, &event.name))?;
+
+        Ok(row.into())
+    }
+
+    async fn update(&self, event: UpdateTag) -> AppResult<()> {
+        let res = sqlx::query!(
+            r#"
+                UPDATE tags SET name = $1
+                WHERE tag_id = $2
+            "#,
+            event.name,
+            event.tag_id as _,
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(|e| duplicated_name_or(e, &event.name))?;
+
+        if res.rows_affected() < 1 {
+            return Err(AppError::EntityNotFound("Specified tag not found".into()));
+        }
+
+        Ok(())
+    }
+
+    async fn delete(&self, event: DeleteTag) -> AppResult<()> {
+        let res = sqlx::query!(
+            r#"
+                DELETE FROM tags WHERE tag_id = $1
+            "#,
+            event.tag_id as _,
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        if res.rows_affected() < 1 {
+            return Err(AppError::EntityNotFound("Specified tag not found".into()));
+        }
+
+        Ok(())
+    }
+}
+
+// タグ名は大文字・小文字を区別せずに一意なので、一意制約の違反はタグ名の重複として扱う
+fn duplicated_name_or(e: sqlx::Error, name: &str) -> AppError {
+    match e.as_database_error() {
+        Some(db_error) if db_error.is_unique_violation() => {
+            AppError::UnprocessableEntity(format!("タグ（{name}）はすでに登録されています。"))
+        }
+        _ => AppError::SpecificOperationError(e),
+    }
+}
@@ -1,13 +1,19 @@
-use crate::database::{ConnectionPool, model::user::UserRow};
+use crate::{
+    database::{ConnectionPool, model::user::UserRow},
+    repository::reservation::refresh_holds,
+};
 use async_trait::async_trait;
 use derive_new::new;
 use kernel::{
     model::{
-        id::UserId,
+        id::{BookId, UserId},
         role::Role,
         user::{
             User,
-            event::{CreateUser, DeleteUser, UpdateUserPassword, UpdateUserRole},
+            event::{
+                CreateUser, DeleteUser, DeleteUserBooks, UpdateUserCheckoutLimit,
+                UpdateUserPassword, UpdateUserProfile, UpdateUserRole,
+            },
         },
     },
     repository::user::UserRepository,
@@ -17,6 +23,8 @@ use shared::error::{AppError, AppResult};
 #[derive(new)]
 pub struct UserRepositoryImpl {
     db: ConnectionPool,
+    // 削除するユーザーの貸出を返却したときに、予約者のために蔵書を取り置いておく日数
+    hold_pickup_days: i64,
 }
 
 #[async_trait]
@@ -110,6 +118,43 @@ impl UserRepository for UserRepositoryImpl {
         })
     }
 
+    async fn update_profile(&self, event: UpdateUserProfile) -> AppResult<()> {
+        if event.name.is_none() && event.email.is_none() {
+            return Ok(());
+        }
+
+        // 指定された項目の SET 句だけを組み立てる
+        let mut query = sqlx::QueryBuilder::new("UPDATE users SET ");
+        let mut set = query.separated(", ");
+        if let Some(name) = event.name {
+            set.push("name = ").push_bind_unseparated(name);
+        }
+        if let Some(email) = &event.email {
+            set.push("email = ").push_bind_unseparated(email.clone());
+        }
+        query.push(" WHERE user_id = ").push_bind(event.user_id);
+
+        let res = query
+            .build()
+            .execute(self.db.inner_ref())
+            .await
+            .map_err(|e| match e.as_database_error() {
+                Some(db_error) if db_error.is_unique_violation() => {
+                    AppError::UnprocessableEntity(format!(
+                        "メールアドレス（{}）はすでに使われています。",
+                        event.email.as_deref().unwrap_or_default()
+                    ))
+                }
+                _ => AppError::SpecificOperationError(e),
+            })?;
+
+        if res.rows_affected() < 1 {
+            return Err(AppError::EntityNotFound("Specified user not found".into()));
+        }
+
+        Ok(())
+    }
+
     async fn update_password(&self, event: UpdateUserPassword) -> AppResult<()> {
         let mut tx = self.db.begin().await?;
 
@@ -169,13 +214,15 @@ impl UserRepository for UserRepositoryImpl {
         Ok(())
     }
 
-    async fn delete(&self, event: DeleteUser) -> AppResult<()> {
+    async fn update_checkout_limit(&self, event: UpdateUserCheckoutLimit) -> AppResult<()> {
         let res = sqlx::query!(
             r#"
-                DELETE FROM users
+                UPDATE users
+                SET checkout_limit = $2
                 WHERE user_id = $1
             "#,
-            event.user_id as _
+            event.user_id as _,
+            event.checkout_limit
         )
         .execute(self.db.inner_ref())
         .await
@@ -187,6 +234,309 @@ impl UserRepository for UserRepositoryImpl {
 
         Ok(())
     }
+
+    async fn delete(&self, event: DeleteUser) -> AppResult<()> {
+        let DeleteUser {
+            user_id,
+            books,
+            force_return,
+            requested_user,
+        } = event;
+
+        if user_id == requested_user {
+            return Err(AppError::UnprocessableEntity(
+                "自分自身は削除できません。".into(),
+            ));
+        }
+
+        let mut tx = self.db.begin().await?;
+
+        // 削除するユーザーの行をロックし、処理の途中で貸出や蔵書の登録がされないようにする
+        let exists = sqlx::query_scalar!(
+            r#"SELECT user_id AS "user_id: UserId" FROM users WHERE user_id = $1 FOR UPDATE"#,
+            user_id as _
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        if exists.is_none() {
+            return Err(AppError::EntityNotFound("Specified user not found".into()));
+        }
+
+        // ユーザーが借りている蔵書は、指定がある場合のみ削除を実行したユーザーの操作として返却する
+        let checked_out = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "count!" FROM checkouts WHERE user_id = $1"#,
+            user_id as _
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        let mut returned_books = Vec::new();
+        if checked_out > 0 {
+            if !force_return {
+                return Err(AppError::UnprocessableEntity(format!(
+                    "ユーザーが借りている蔵書が {checked_out} 冊返却されていないため削除できません。"
+                )));
+            }
+            returned_books = return_all_checkouts(&mut tx, user_id, requested_user).await?;
+        }
+
+        let owns_books = sqlx::query_scalar!(
+            r#"
+                SELECT
+                    EXISTS(SELECT 1 FROM books WHERE user_id = $1)
+                    OR EXISTS(SELECT 1 FROM book_copies WHERE user_id = $1)
+                    AS "owns_books!"
+            "#,
+            user_id as _
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        match books {
+            _ if !owns_books => {}
+            None => {
+                return Err(AppError::UnprocessableEntity(
+                    "ユーザーが所有する蔵書の引き継ぎ先を指定するか、アーカイブを指定してください。"
+                        .into(),
+                ));
+            }
+            Some(DeleteUserBooks::TransferTo(new_owner)) => {
+                transfer_books(&mut tx, user_id, new_owner, requested_user).await?;
+            }
+            Some(DeleteUserBooks::Archive) => {
+                archive_books(&mut tx, user_id, requested_user).await?;
+            }
+        }
+
+        // 返却済みの貸出履歴は、借りたユーザーと操作したユーザーを匿名化して残す
+        sqlx::query!(
+            r#"
+                UPDATE returned_checkouts
+                SET
+                    user_id = NULLIF(user_id, $1),
+                    checkout_operator_id = NULLIF(checkout_operator_id, $1),
+                    return_operator_id = NULLIF(return_operator_id, $1)
+                WHERE $1 IN (user_id, checkout_operator_id, return_operator_id)
+            "#,
+            user_id as _
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        sqlx::query!(
+            r#"
+                DELETE FROM users
+                WHERE user_id = $1
+            "#,
+            user_id as _
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        // 返却した蔵書を予約している次の予約者のために取り置く
+        // ユーザー自身の予約はユーザーの削除とともに削除されるため、削除した後に取り置きを更新する
+        let now = chrono::Utc::now();
+        for book_id in returned_books {
+            refresh_holds(&mut tx, book_id, now, self.hold_pickup_days).await?;
+        }
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
+        Ok(())
+    }
+}
+
+// ユーザーが借りているすべての蔵書を、returned_by が返却した扱いにする
+// 返却した蔵書の ID を重複なく返す
+async fn return_all_checkouts(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: UserId,
+    returned_by: UserId,
+) -> AppResult<Vec<BookId>> {
+    let book_ids = sqlx::query_scalar!(
+        r#"SELECT DISTINCT book_id AS "book_id!: BookId" FROM checkouts WHERE user_id = $1"#,
+        user_id as _
+    )
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+
+    sqlx::query!(
+        r#"
+            INSERT INTO returned_checkouts
+            (
+                checkout_id, book_id, copy_id, user_id, checkout_operator_id,
+                checked_out_at, due_at, renewal_count, returned_at, return_operator_id
+            )
+            SELECT
+                checkout_id, book_id, copy_id, user_id, checkout_operator_id,
+                checked_out_at, due_at, renewal_count, CURRENT_TIMESTAMP(3), $2
+            FROM checkouts
+            WHERE user_id = $1
+        "#,
+        user_id as _,
+        returned_by as _,
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+
+    sqlx::query!(r#"DELETE FROM checkouts WHERE user_id = $1"#, user_id as _)
+        .execute(&mut **tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+    Ok(book_ids)
+}
+
+// ユーザーが所有する蔵書とコピーの所有者を new_owner に変更する
+// 蔵書の所有者の変更は、蔵書ごとに変更履歴に記録する
+async fn transfer_books(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: UserId,
+    new_owner: UserId,
+    transferred_by: UserId,
+) -> AppResult<()> {
+    if new_owner == user_id {
+        return Err(AppError::UnprocessableEntity(
+            "削除するユーザーを蔵書の引き継ぎ先にはできません。".into(),
+        ));
+    }
+    let exists = sqlx::query_scalar!(
+        r#"SELECT EXISTS(SELECT 1 FROM users WHERE user_id = $1) AS "exists!""#,
+        new_owner as _
+    )
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+    if !exists {
+        return Err(AppError::UnprocessableEntity(format!(
+            "蔵書の引き継ぎ先のユーザー（{new_owner}）が見つかりませんでした。"
+        )));
+    }
+
+    // 貸出中の蔵書は、蔵書ごとの所有者の変更と同じく引き継げない
+    let checked_out = sqlx::query_scalar!(
+        r#"
+            SELECT COUNT(DISTINCT b.book_id) AS "count!"
+            FROM books AS b
+            INNER JOIN checkouts AS c ON c.book_id = b.book_id
+            WHERE b.user_id = $1
+        "#,
+        user_id as _
+    )
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+    if checked_out > 0 {
+        return Err(AppError::UnprocessableEntity(format!(
+            "ユーザーが所有する蔵書のうち {checked_out} 冊が貸出中のため所有者を変更できません。"
+        )));
+    }
+
+    sqlx::query!(
+        r#"
+            INSERT INTO book_owner_transfers (book_id, from_user_id, to_user_id, transferred_by)
+            SELECT book_id, $1, $2, $3 FROM books WHERE user_id = $1
+        "#,
+        user_id as _,
+        new_owner as _,
+        transferred_by as _
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+    sqlx::query!(
+        r#"UPDATE books SET user_id = $2 WHERE user_id = $1"#,
+        user_id as _,
+        new_owner as _
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+    sqlx::query!(
+        r#"UPDATE book_copies SET user_id = $2 WHERE user_id = $1"#,
+        user_id as _,
+        new_owner as _
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+
+    Ok(())
+}
+
+// ユーザーが所有する蔵書をアーカイブし、所有者を archived_by に変更する
+// 他のユーザーの蔵書に追加したコピーは、その蔵書の所有者に引き継ぐ
+async fn archive_books(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: UserId,
+    archived_by: UserId,
+) -> AppResult<()> {
+    // 貸出中の蔵書はアーカイブできない
+    let checked_out = sqlx::query_scalar!(
+        r#"
+            SELECT COUNT(DISTINCT b.book_id) AS "count!"
+            FROM books AS b
+            INNER JOIN checkouts AS c ON c.book_id = b.book_id
+            WHERE b.user_id = $1
+            AND b.deleted_at IS NULL
+        "#,
+        user_id as _
+    )
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+    if checked_out > 0 {
+        return Err(AppError::UnprocessableEntity(format!(
+            "ユーザーが所有する蔵書のうち {checked_out} 冊が貸出中のためアーカイブできません。"
+        )));
+    }
+
+    sqlx::query!(
+        r#"
+            DELETE FROM reservations
+            WHERE book_id IN (SELECT book_id FROM books WHERE user_id = $1 AND deleted_at IS NULL)
+        "#,
+        user_id as _
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+    sqlx::query!(
+        r#"
+            UPDATE books
+            SET
+                user_id = $2,
+                deleted_at = COALESCE(deleted_at, CURRENT_TIMESTAMP(3)),
+                archive_reason = COALESCE(archive_reason, $3)
+            WHERE user_id = $1
+        "#,
+        user_id as _,
+        archived_by as _,
+        "所有者のユーザーを削除したため",
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+    sqlx::query!(
+        r#"
+            UPDATE book_copies AS bc
+            SET user_id = b.user_id
+            FROM books AS b
+            WHERE b.book_id = bc.book_id
+            AND bc.user_id = $1
+        "#,
+        user_id as _
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+
+    Ok(())
 }
 
 fn hash_password(password: &str) -> AppResult<String> {
@@ -200,3 +550,359 @@ fn verify_password(password: &str, hash: &str) -> AppResult<()> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::{
+        book::BookRepositoryImpl, checkout::CheckoutRepositoryImpl,
+        reservation::ReservationRepositoryImpl,
+    };
+    use chrono::Utc;
+    use kernel::{
+        model::{
+            book::event::{CreateBook, CreateBookCopy},
+            checkout::event::{CreateCheckout, UpdateReturned},
+            list::CursorListOptions,
+            reservation::event::CreateReservation,
+        },
+        repository::{
+            book::BookRepository, checkout::CheckoutRepository, reservation::ReservationRepository,
+        },
+    };
+    use shared::config::CheckoutConfig;
+
+    async fn create_user(repo: &UserRepositoryImpl, email: &str) -> AppResult<UserId> {
+        let user = repo
+            .create(CreateUser {
+                name: email.into(),
+                email: email.into(),
+                password: "password".into(),
+            })
+            .await?;
+        Ok(user.id)
+    }
+
+    async fn create_book(pool: &sqlx::PgPool, owner: UserId, title: &str) -> AppResult<BookId> {
+        BookRepositoryImpl::new(ConnectionPool::new(pool.clone()))
+            .create(
+                CreateBook {
+                    title: title.into(),
+                    author: "Test Author".into(),
+                    isbn: "9781593278281".parse().unwrap(),
+                    description: "".into(),
+                    barcode: None,
+                    tags: vec![],
+                },
+                owner,
+            )
+            .await?;
+        sqlx::query_scalar!(
+            r#"SELECT book_id AS "book_id: BookId" FROM books WHERE title = $1"#,
+            title
+        )
+        .fetch_one(pool)
+        .await
+        .map_err(AppError::SpecificOperationError)
+    }
+
+    #[sqlx::test]
+    async fn test_delete_user(pool: sqlx::PgPool) -> AppResult<()> {
+        sqlx::query!(r#"INSERT INTO roles(name) VALUES ('Admin'), ('User');"#)
+            .execute(&pool)
+            .await
+            .map_err(AppError::SpecificOperationError)?;
+
+        let user_repo = UserRepositoryImpl::new(ConnectionPool::new(pool.clone()), 3);
+        let book_repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let checkout_repo = CheckoutRepositoryImpl::new(
+            ConnectionPool::new(pool.clone()),
+            CheckoutConfig {
+                loan_period_days: 14,
+                max_renewals: 2,
+                hold_pickup_days: 3,
+                checkout_limit_admin: 3,
+                checkout_limit_user: 3,
+            },
+        );
+
+        let admin = create_user(&user_repo, "admin@example.com").await?;
+        let leaving = create_user(&user_repo, "leaving@example.com").await?;
+        let successor = create_user(&user_repo, "successor@example.com").await?;
+        let owned = create_book(&pool, leaving, "Owned Book").await?;
+        let borrowed = create_book(&pool, successor, "Borrowed Book").await?;
+
+        // 返却済みの貸出と、貸出中の蔵書を 1 冊ずつ作る
+        checkout_repo
+            .create(CreateCheckout::new(
+                borrowed,
+                None,
+                leaving,
+                leaving,
+                Utc::now(),
+            ))
+            .await?;
+        let checkout = checkout_repo
+            .find_unreturned_by_user_id(leaving)
+            .await?
+            .remove(0);
+        checkout_repo
+            .update_returned(UpdateReturned::new(
+                checkout.id,
+                borrowed,
+                leaving,
+                false,
+                Utc::now(),
+            ))
+            .await?;
+        checkout_repo
+            .create(CreateCheckout::new(
+                borrowed,
+                None,
+                leaving,
+                leaving,
+                Utc::now(),
+            ))
+            .await?;
+
+        let delete = |books, force_return| DeleteUser {
+            user_id: leaving,
+            books,
+            force_return,
+            requested_user: admin,
+        };
+
+        // 借りている蔵書がある場合と、蔵書の扱いを指定しない場合は削除できない
+        let transfer = Some(DeleteUserBooks::TransferTo(successor));
+        let res = user_repo.delete(delete(transfer, false)).await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+        let res = user_repo.delete(delete(None, true)).await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+        let res = user_repo
+            .delete(delete(Some(DeleteUserBooks::TransferTo(leaving)), true))
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+        // 失敗した場合は何も変更しない
+        assert_eq!(
+            checkout_repo
+                .find_unreturned_by_user_id(leaving)
+                .await?
+                .len(),
+            1
+        );
+
+        user_repo.delete(delete(transfer, true)).await?;
+        assert!(user_repo.find_current_user(leaving).await?.is_none());
+
+        // 蔵書とコピーは引き継ぎ先のユーザーが所有する
+        let book = book_repo.find_by_id(owned).await?.unwrap();
+        assert_eq!(book.owner.id, successor);
+        assert!(book.copies.iter().all(|c| c.owner.id == successor));
+        assert!(book.archive.is_none());
+        // 所有者の変更履歴には、削除したユーザーが匿名化されて残る
+        let transfers = book_repo.find_owner_transfers(owned).await?;
+        assert_eq!(transfers.len(), 1);
+        assert_eq!(transfers[0].from.id, UserId::from(uuid::Uuid::nil()));
+        assert_eq!(transfers[0].to.id, successor);
+        assert_eq!(transfers[0].transferred_by.id, admin);
+
+        // 貸出中だった蔵書は返却され、貸出履歴は借りたユーザーを匿名化して残る
+        assert!(
+            checkout_repo
+                .find_unreturned_by_user_id(leaving)
+                .await?
+                .is_empty()
+        );
+        let history = checkout_repo
+            .find_history_by_book_id(
+                borrowed,
+                CursorListOptions {
+                    limit: 20,
+                    cursor: None,
+                },
+            )
+            .await?;
+        assert_eq!(history.items.len(), 2);
+        for checkout in history.items {
+            assert!(checkout.returned_at.is_some());
+            assert_eq!(checkout.checked_out_by.id.raw(), uuid::Uuid::nil());
+            assert_eq!(checkout.checked_out_by.name, "削除されたユーザー");
+        }
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_delete_user_with_reservations(pool: sqlx::PgPool) -> AppResult<()> {
+        sqlx::query!(r#"INSERT INTO roles(name) VALUES ('Admin'), ('User');"#)
+            .execute(&pool)
+            .await
+            .map_err(AppError::SpecificOperationError)?;
+
+        let user_repo = UserRepositoryImpl::new(ConnectionPool::new(pool.clone()), 3);
+        let checkout_repo = CheckoutRepositoryImpl::new(
+            ConnectionPool::new(pool.clone()),
+            CheckoutConfig {
+                loan_period_days: 14,
+                max_renewals: 2,
+                hold_pickup_days: 3,
+                checkout_limit_admin: 3,
+                checkout_limit_user: 3,
+            },
+        );
+        let reservation_repo = ReservationRepositoryImpl::new(ConnectionPool::new(pool.clone()), 3);
+
+        let admin = create_user(&user_repo, "admin@example.com").await?;
+        let leaving = create_user(&user_repo, "leaving@example.com").await?;
+        let successor = create_user(&user_repo, "successor@example.com").await?;
+        let reader = create_user(&user_repo, "reader@example.com").await?;
+        let owned = create_book(&pool, leaving, "Owned Book").await?;
+        let borrowed = create_book(&pool, successor, "Borrowed Book").await?;
+
+        // 削除するユーザーが借りている蔵書を、他のユーザーが予約している
+        checkout_repo
+            .create(CreateCheckout::new(
+                borrowed,
+                None,
+                leaving,
+                leaving,
+                Utc::now(),
+            ))
+            .await?;
+        reservation_repo
+            .create(CreateReservation::new(borrowed, reader, Utc::now()))
+            .await?;
+        // 削除するユーザーが所有する蔵書を、他のユーザーが借りている
+        checkout_repo
+            .create(CreateCheckout::new(owned, None, reader, reader, Utc::now()))
+            .await?;
+
+        let delete = || DeleteUser {
+            user_id: leaving,
+            books: Some(DeleteUserBooks::TransferTo(successor)),
+            force_return: true,
+            requested_user: admin,
+        };
+
+        // 貸出中の蔵書は引き継げず、何も変更しない
+        let res = user_repo.delete(delete()).await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+        assert!(user_repo.find_current_user(leaving).await?.is_some());
+        assert_eq!(
+            checkout_repo
+                .find_unreturned_by_user_id(leaving)
+                .await?
+                .len(),
+            1
+        );
+
+        let checkout = checkout_repo
+            .find_unreturned_by_user_id(reader)
+            .await?
+            .remove(0);
+        checkout_repo
+            .update_returned(UpdateReturned::new(
+                checkout.id,
+                owned,
+                reader,
+                false,
+                Utc::now(),
+            ))
+            .await?;
+
+        // 削除するユーザーが借りていた蔵書は返却され、予約者のために取り置かれる
+        user_repo.delete(delete()).await?;
+        let queue = reservation_repo.find_by_book_id(borrowed).await?;
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue[0].reserved_by.id, reader);
+        assert!(queue[0].hold_expires_at.is_some());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_delete_user_with_archive(pool: sqlx::PgPool) -> AppResult<()> {
+        sqlx::query!(r#"INSERT INTO roles(name) VALUES ('Admin'), ('User');"#)
+            .execute(&pool)
+            .await
+            .map_err(AppError::SpecificOperationError)?;
+
+        let user_repo = UserRepositoryImpl::new(ConnectionPool::new(pool.clone()), 3);
+        let book_repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+
+        let admin = create_user(&user_repo, "admin@example.com").await?;
+        let leaving = create_user(&user_repo, "leaving@example.com").await?;
+        let other = create_user(&user_repo, "other@example.com").await?;
+        let owned = create_book(&pool, leaving, "Owned Book").await?;
+        let others = create_book(&pool, other, "Other Book").await?;
+        // 他のユーザーの蔵書に追加したコピー
+        book_repo
+            .create_copy(CreateBookCopy {
+                book_id: others,
+                barcode: None,
+                requested_user: leaving,
+            })
+            .await?;
+
+        user_repo
+            .delete(DeleteUser {
+                user_id: leaving,
+                books: Some(DeleteUserBooks::Archive),
+                force_return: false,
+                requested_user: admin,
+            })
+            .await?;
+
+        // 所有していた蔵書はアーカイブされ、削除を実行したユーザーが所有する
+        let book = book_repo.find_by_id(owned).await?.unwrap();
+        assert_eq!(book.owner.id, admin);
+        assert!(book.archive.is_some());
+        assert!(book.copies.iter().all(|c| c.owner.id == admin));
+
+        // 他のユーザーの蔵書に追加したコピーは、その蔵書の所有者が引き継ぐ
+        let book = book_repo.find_by_id(others).await?.unwrap();
+        assert_eq!(book.total_copies(), 2);
+        assert!(book.copies.iter().all(|c| c.owner.id == other));
+        assert!(book.archive.is_none());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_update_profile(pool: sqlx::PgPool) -> AppResult<()> {
+        sqlx::query!(r#"INSERT INTO roles(name) VALUES ('Admin'), ('User');"#)
+            .execute(&pool)
+            .await
+            .map_err(AppError::SpecificOperationError)?;
+
+        let user_repo = UserRepositoryImpl::new(ConnectionPool::new(pool), 3);
+        let user = create_user(&user_repo, "user@example.com").await?;
+        create_user(&user_repo, "taken@example.com").await?;
+
+        // 指定した項目だけを更新する
+        user_repo
+            .update_profile(UpdateUserProfile {
+                user_id: user,
+                name: Some("New Name".into()),
+                email: None,
+            })
+            .await?;
+        let found = user_repo.find_current_user(user).await?.unwrap();
+        assert_eq!(found.name, "New Name");
+        assert_eq!(found.email, "user@example.com");
+
+        // ほかのユーザーが使っているメールアドレスには変更できない
+        let res = user_repo
+            .update_profile(UpdateUserProfile {
+                user_id: user,
+                name: None,
+                email: Some("taken@example.com".into()),
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+        let found = user_repo.find_current_user(user).await?.unwrap();
+        assert_eq!(found.email, "user@example.com");
+
+        Ok(())
+    }
+}
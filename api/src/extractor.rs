@@ -1,4 +1,13 @@
-use axum::{RequestPartsExt, extract::FromRequestParts, http::request::Parts};
+use std::{
+    convert::Infallible,
+    net::{IpAddr, SocketAddr},
+};
+
+use axum::{
+    RequestPartsExt,
+    extract::{ConnectInfo, FromRequestParts},
+    http::{HeaderMap, header, request::Parts},
+};
 use axum_extra::TypedHeader;
 use axum_extra::headers::Authorization;
 use axum_extra::headers::authorization::Bearer;
@@ -7,7 +16,7 @@ use kernel::model::id::UserId;
 use kernel::model::role::Role;
 use kernel::model::user::User;
 use registry::AppRegistry;
-use shared::error::AppError;
+use shared::{config::TrustedProxies, error::AppError};
 
 pub struct AuthorizedUser {
     pub access_token: AccessToken,
@@ -27,33 +36,129 @@ impl FromRequestParts<AppRegistry> for AuthorizedUser {
     type Rejection = AppError;
 
     // handler メソッドの引数に AuthorizedUser を追加したときはこのメソッドが呼ばれる
-    fn from_request_parts(
+    async fn from_request_parts(
         parts: &mut Parts,
         registry: &AppRegistry,
-    ) -> impl std::future::Future<Output = Result<Self, Self::Rejection>> + Send {
-        async move {
-            // HTTP ヘッダからアクセストークンを取り出す
-            let TypedHeader(Authorization(bearer)) = parts
-                .extract::<TypedHeader<Authorization<Bearer>>>()
-                .await
-                .map_err(|_| AppError::UnauthorizedError)?;
-            let access_token = AccessToken(bearer.token().to_string());
-
-            // アクセストークンが紐づくユーザー ID を抽出する
-            let user_id = registry
-                .auth_repository()
-                .fetch_user_id_from_token(&access_token)
-                .await?
-                .ok_or(AppError::UnauthenticatedError)?;
-
-            // ユーザー ID でデータベースからユーザーのレコードを引く
-            let user = registry
-                .user_repository()
-                .find_current_user(user_id)
-                .await?
-                .ok_or(AppError::UnauthenticatedError)?;
-
-            Ok(Self { access_token, user })
+    ) -> Result<Self, Self::Rejection> {
+        // HTTP ヘッダからアクセストークンを取り出す
+        let TypedHeader(Authorization(bearer)) = parts
+            .extract::<TypedHeader<Authorization<Bearer>>>()
+            .await
+            .map_err(|_| AppError::UnauthorizedError)?;
+        let access_token = AccessToken(bearer.token().to_string());
+
+        // アクセストークンが紐づくユーザー ID を抽出する
+        let user_id = registry
+            .auth_repository()
+            .fetch_user_id_from_token(&access_token)
+            .await?
+            .ok_or(AppError::UnauthenticatedError)?;
+
+        // ユーザー ID でデータベースからユーザーのレコードを引く
+        let user = registry
+            .user_repository()
+            .find_current_user(user_id)
+            .await?
+            .ok_or(AppError::UnauthenticatedError)?;
+
+        Ok(Self { access_token, user })
+    }
+}
+
+/// If-Match ヘッダで指定されたリソースのバージョン
+///
+/// ヘッダがない場合と `*` の場合は `None` になる。
+/// 弱い ETag や複数の ETag の指定には対応しない。
+pub struct IfMatch(pub Option<i64>);
+
+impl<S: Send + Sync> FromRequestParts<S> for IfMatch {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Some(value) = parts.headers.get(header::IF_MATCH) else {
+            return Ok(Self(None));
+        };
+        let invalid = || AppError::PreconditionFailed("If-Match ヘッダの値が不正です。".into());
+        let value = value.to_str().map_err(|_| invalid())?.trim();
+        if value == "*" {
+            return Ok(Self(None));
+        }
+        value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .and_then(|v| v.parse::<i64>().ok())
+            .map(|version| Self(Some(version)))
+            .ok_or_else(invalid)
+    }
+}
+
+/// 蔵書のバージョンを ETag ヘッダの値に変換する
+pub fn etag(version: i64) -> String {
+    format!("\"{version}\"")
+}
+
+/// リクエストを送ったクライアントの情報
+pub struct ClientInfo {
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+impl FromRequestParts<AppRegistry> for ClientInfo {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        registry: &AppRegistry,
+    ) -> Result<Self, Self::Rejection> {
+        let user_agent = parts
+            .headers
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(String::from);
+
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip());
+        let ip_address =
+            client_ip(peer, &parts.headers, &registry.trusted_proxies()).map(|ip| ip.to_string());
+
+        Ok(Self {
+            user_agent,
+            ip_address,
+        })
+    }
+}
+
+// 接続元が信頼するプロキシの場合に限り X-Forwarded-For を参照し、末尾から順に
+// 信頼するプロキシのアドレスを読み飛ばした最初のアドレスをクライアントのアドレスとみなす。
+// 接続元が信頼するプロキシでなければ、X-Forwarded-For はクライアントが自由に書き換えられるため無視する
+fn client_ip(
+    peer: Option<IpAddr>,
+    headers: &HeaderMap,
+    trusted_proxies: &TrustedProxies,
+) -> Option<IpAddr> {
+    let mut client = peer?;
+    if !trusted_proxies.contains(&client) {
+        return Some(client);
+    }
+    let forwarded_for = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .collect::<Vec<_>>();
+    for value in forwarded_for.into_iter().rev() {
+        // 解釈できない値があれば、それより前の値は信頼せずに直前のアドレスを使う
+        let Ok(ip) = value.parse::<IpAddr>() else {
+            break;
+        };
+        client = ip;
+        if !trusted_proxies.contains(&ip) {
+            break;
         }
     }
+    Some(client)
 }
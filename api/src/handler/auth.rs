@@ -1,11 +1,14 @@
 use axum::{Json, extract::State, http::StatusCode};
-use kernel::model::auth::event::CreateToken;
+use kernel::model::auth::{
+    RefreshToken,
+    event::{CreateToken, VerifyUser},
+};
 use registry::AppRegistry;
 use shared::error::AppResult;
 
 use crate::{
-    extractor::AuthorizedUser,
-    model::auth::{AccessTokenResponse, LoginRequest},
+    extractor::{AuthorizedUser, ClientInfo},
+    model::auth::{AccessTokenResponse, LoginRequest, RefreshTokenRequest},
 };
 
 #[utoipa::path(
@@ -13,33 +16,66 @@ use crate::{
     path = "/auth/login",
     tag = "認証",
     summary = "ログイン",
-    description = "メールアドレスとパスワードでログインし、アクセストークンを取得します",
+    description = "メールアドレスとパスワードでログインし、アクセストークンとリフレッシュトークンを取得します",
     operation_id = "login",
     request_body = LoginRequest,
     responses(
         (status = 200, description = "ログイン成功", body = AccessTokenResponse),
         (status = 401, description = "認証エラー"),
+        (status = 429, description = "ログインの失敗が続いたため、アカウントまたはIPアドレスが一時的にロックされている。Retry-After ヘッダにロックが解除されるまでの秒数を返す"),
         (status = 500, description = "サーバーエラー"),
     )
 )]
 pub async fn login(
+    client: ClientInfo,
     State(registry): State<AppRegistry>,
     Json(req): Json<LoginRequest>,
 ) -> AppResult<Json<AccessTokenResponse>> {
     let user_id = registry
         .auth_repository()
-        .verify_user(&req.email, &req.password)
+        .verify_user(VerifyUser {
+            email: req.email,
+            password: req.password,
+            ip_address: client.ip_address.clone(),
+        })
         .await?;
 
-    let access_token = registry
+    registry
         .auth_repository()
-        .create_token(CreateToken::new(user_id))
-        .await?;
+        .create_token(CreateToken::new(
+            user_id,
+            client.user_agent,
+            client.ip_address,
+        ))
+        .await
+        .map(AccessTokenResponse::from)
+        .map(Json)
+}
 
-    Ok(Json(AccessTokenResponse {
-        user_id,
-        access_token: access_token.0,
-    }))
+#[utoipa::path(
+    post,
+    path = "/auth/refresh",
+    tag = "認証",
+    summary = "アクセストークンの再発行",
+    description = "リフレッシュトークンを使って、新しいアクセストークンとリフレッシュトークンを発行します。使ったリフレッシュトークンと以前のアクセストークンは無効になります。リフレッシュで使用済みになったリフレッシュトークン（リフレッシュトークンの有効期間内に使用済みになった直近 32 個まで）が再び使われた場合は、漏洩したとみなしてそのログインで発行したトークンをすべて無効にします",
+    operation_id = "refreshToken",
+    request_body = RefreshTokenRequest,
+    responses(
+        (status = 200, description = "再発行成功", body = AccessTokenResponse),
+        (status = 401, description = "リフレッシュトークンが無効、期限切れ、または使用済み"),
+        (status = 500, description = "サーバーエラー"),
+    )
+)]
+pub async fn refresh(
+    State(registry): State<AppRegistry>,
+    Json(req): Json<RefreshTokenRequest>,
+) -> AppResult<Json<AccessTokenResponse>> {
+    registry
+        .auth_repository()
+        .refresh_token(RefreshToken(req.refresh_token))
+        .await
+        .map(AccessTokenResponse::from)
+        .map(Json)
 }
 
 #[utoipa::path(
@@ -47,7 +83,7 @@ pub async fn login(
     path = "/auth/logout",
     tag = "認証",
     summary = "ログアウト",
-    description = "現在のセッションからログアウトし、アクセストークンを無効化します",
+    description = "現在のセッションからログアウトし、アクセストークンとリフレッシュトークンを無効化します",
     operation_id = "logout",
     responses(
         (status = 204, description = "ログアウト成功"),
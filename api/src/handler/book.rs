@@ -1,18 +1,30 @@
 use axum::{
     Json,
+    body::Body,
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
 };
 use garde::Validate;
-use kernel::model::{book::DeleteBook, id::BookId};
+use kernel::model::{
+    book::{
+        BookListOptions, DeleteBook, RestoreBook,
+        event::{DeleteBookCopy, ImportBooks},
+    },
+    id::{BookCopyId, BookId},
+};
 use registry::AppRegistry;
 use shared::error::{AppError, AppResult};
+use tokio_stream::StreamExt;
 
 use crate::{
-    extractor::AuthorizedUser,
+    extractor::{AuthorizedUser, IfMatch, etag},
     model::book::{
-        BookListQuery, BookResponse, CreateBookRequest, PaginatedBookResponse, UpdateBookRequest,
-        UpdateBookRequestWithIds,
+        BookDeleteQuery, BookExportQuery, BookImportQuery, BookImportResponse, BookListQuery,
+        BookLookupQuery, BookOwnerTransfersResponse, BookResponse, CreateBookCopyRequest,
+        CreateBookCopyRequestWithIds, CreateBookRequest, PaginatedBookResponse, PatchBookRequest,
+        PatchBookRequestWithIds, UpdateBookOwnerRequest, UpdateBookOwnerRequestWithIds,
+        UpdateBookRequest, UpdateBookRequestWithIds,
     },
 };
 
@@ -21,14 +33,14 @@ use crate::{
     path = "/books",
     tag = "蔵書",
     summary = "蔵書登録",
-    description = "新しい蔵書を登録します。登録者が蔵書の所有者になります",
+    description = "新しい蔵書を登録します。登録者が蔵書の所有者になります。最初のコピーが 1 冊登録されます",
     operation_id = "registerBook",
     request_body = CreateBookRequest,
     responses(
         (status = 201, description = "蔵書の登録成功"),
         (status = 400, description = "リクエストパラメータ不正"),
         (status = 401, description = "認証エラー"),
-        (status = 422, description = "リクエストした蔵書の登録に失敗した場合"),
+        (status = 422, description = "ISBN のチェックディジットが正しくない場合やバーコードが使われている場合、存在しないタグを指定した場合など、リクエストした蔵書の登録に失敗した場合"),
     ),
     security(
         ("bearer_auth" = [])
@@ -39,6 +51,8 @@ pub async fn register_book(
     State(registry): State<AppRegistry>,
     Json(req): Json<CreateBookRequest>,
 ) -> AppResult<StatusCode> {
+    req.validate()?;
+
     registry
         .book_repository()
         .create(req.into(), user.id())
@@ -46,36 +60,186 @@ pub async fn register_book(
         .map(|_| StatusCode::CREATED)
 }
 
+// 一度に一括登録できる行数の上限
+const MAX_IMPORT_ROWS: usize = 1000;
+
+#[utoipa::path(
+    post,
+    path = "/books/import",
+    tag = "蔵書",
+    summary = "蔵書一括登録",
+    description = "CSV または JSON Lines で記述した蔵書をまとめて登録します。各行は蔵書登録と同じ規則で検証し、問題のない行だけを 1 つのトランザクションで登録します。ISBN が登録済みの蔵書や前の行と重複している行は登録せず、結果に含めて返します。dryRun を指定すると登録を確定せずに結果だけを返します",
+    operation_id = "importBooks",
+    params(
+        BookImportQuery
+    ),
+    request_body(
+        content(
+            (String = "text/csv"),
+            (String = "application/jsonl"),
+        ),
+        description = "format に指定した形式の蔵書データ（最大 1000 行）",
+    ),
+    responses(
+        (status = 200, description = "一括登録の結果", body = BookImportResponse),
+        (status = 400, description = "クエリパラメータ不正"),
+        (status = 401, description = "認証エラー"),
+        (status = 422, description = "行数が上限を超えている場合"),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn import_books(
+    user: AuthorizedUser,
+    Query(query): Query<BookImportQuery>,
+    State(registry): State<AppRegistry>,
+    body: String,
+) -> AppResult<Json<BookImportResponse>> {
+    let (rows, errors) = query.format.parse(&body);
+    if rows.len() + errors.len() > MAX_IMPORT_ROWS {
+        return Err(AppError::UnprocessableEntity(format!(
+            "一度に登録できる蔵書は {MAX_IMPORT_ROWS} 件までです。"
+        )));
+    }
+
+    let import_books = ImportBooks {
+        rows,
+        requested_user: user.id(),
+        dry_run: query.dry_run,
+    };
+
+    registry
+        .book_repository()
+        .import(import_books)
+        .await
+        .map(|report| BookImportResponse::new(query.dry_run, report, errors))
+        .map(Json)
+}
+
 #[utoipa::path(
     get,
     path = "/books",
     tag = "蔵書",
     summary = "蔵書一覧取得",
-    description = "登録されている蔵書の一覧をページネーション付きで取得します",
+    description = "登録されている蔵書の一覧をページネーション付きで取得します。検索語による全文検索・部分一致検索と、貸出状況・所有者・著者名・タグによる絞り込み、並び順の指定ができます。レスポンスには検索条件に当てはまる蔵書のタグごとの冊数も含まれます。アーカイブ済みの蔵書は含まれません。登録日時による並び順では、レスポンスの nextCursor を cursor に指定すると次のページを取得できます",
     operation_id = "listBooks",
     params(
         BookListQuery
     ),
     responses(
         (status = 200, description = "蔵書一覧の取得成功", body = PaginatedBookResponse),
+        (status = 400, description = "クエリパラメータ不正"),
+        (status = 403, description = "管理者以外が includeArchived を指定した場合"),
+        (status = 422, description = "登録日時以外の並び順でカーソルを指定した場合"),
     ),
     security(
         ("bearer_auth" = [])
     )
 )]
 pub async fn show_book_list(
-    _user: AuthorizedUser,
+    user: AuthorizedUser,
     Query(query): Query<BookListQuery>,
     State(registry): State<AppRegistry>,
 ) -> AppResult<Json<PaginatedBookResponse>> {
     query.validate()?;
 
-    registry
+    if query.include_archived && !user.is_admin() {
+        return Err(AppError::ForbiddenOperation);
+    }
+
+    let options = BookListOptions::from(query);
+    let tag_facets = registry.book_repository().find_tag_facets(&options).await?;
+    let list = registry.book_repository().find_all(options).await?;
+
+    Ok(Json(PaginatedBookResponse::new(list, tag_facets)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/books/export",
+    tag = "蔵書",
+    summary = "蔵書エクスポート",
+    description = "アーカイブ済みのものを除くすべての蔵書を、所有者とコピーごとの貸出状況を含めて CSV・JSON Lines・BibTeX のいずれかで出力します。蔵書は少しずつ取得しながらストリーミングで返します",
+    operation_id = "exportBooks",
+    params(
+        BookExportQuery
+    ),
+    responses(
+        (status = 200, description = "蔵書のエクスポート成功", content(
+            (String = "text/csv"),
+            (String = "application/jsonl"),
+            (String = "application/x-bibtex"),
+        )),
+        (status = 400, description = "クエリパラメータ不正"),
+        (status = 401, description = "認証エラー"),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn export_books(
+    _user: AuthorizedUser,
+    Query(query): Query<BookExportQuery>,
+    State(registry): State<AppRegistry>,
+) -> Response {
+    let format = query.format;
+    let books = registry
         .book_repository()
-        .find_all(query.into())
-        .await
-        .map(PaginatedBookResponse::from)
-        .map(Json)
+        .export_all()
+        .map(move |book| book.and_then(|book| format.encode(book)));
+    let body = tokio_stream::once(Ok(format.header())).chain(books);
+
+    (
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", format.file_name()),
+            ),
+        ],
+        Body::from_stream(body),
+    )
+        .into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/books/lookup",
+    tag = "蔵書",
+    summary = "書誌情報検索",
+    description = "ISBN から書誌情報を検索し、タイトル・著者名・説明を埋めた蔵書登録リクエストを返します",
+    operation_id = "lookupBook",
+    params(
+        BookLookupQuery
+    ),
+    responses(
+        (status = 200, description = "書誌情報の取得成功", body = CreateBookRequest),
+        (status = 400, description = "クエリパラメータ不正"),
+        (status = 401, description = "認証エラー"),
+        (status = 404, description = "書誌情報が見つからない"),
+        (status = 502, description = "書誌情報サービスとの通信に失敗した"),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn lookup_book(
+    _user: AuthorizedUser,
+    Query(query): Query<BookLookupQuery>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<CreateBookRequest>> {
+    registry
+        .book_metadata_provider()
+        .find_by_isbn(&query.isbn)
+        .await?
+        .map(|metadata| Json(metadata.into()))
+        .ok_or_else(|| {
+            AppError::EntityNotFound(format!(
+                "ISBN（{}）の書誌情報が見つかりません。",
+                query.isbn
+            ))
+        })
 }
 
 #[utoipa::path(
@@ -83,13 +247,14 @@ pub async fn show_book_list(
     path = "/books/{book_id}",
     tag = "蔵書",
     summary = "蔵書詳細取得",
-    description = "指定したIDの蔵書の詳細情報を取得します。貸出情報も含まれます",
+    description = "指定したIDの蔵書の詳細情報を取得します。コピーごとの貸出情報も含まれます。アーカイブ済みの蔵書は所有者と管理者のみ取得できます",
     operation_id = "getBook",
     params(
         ("book_id" = String, Path, description = "蔵書ID")
     ),
     responses(
-        (status = 200, description = "蔵書詳細の取得成功", body = BookResponse),
+        (status = 200, description = "蔵書詳細の取得成功", body = BookResponse,
+            headers(("ETag" = String, description = "蔵書のバージョン。更新・削除時に If-Match ヘッダに指定する"))),
         (status = 404, description = "蔵書が存在しない"),
     ),
     security(
@@ -97,22 +262,29 @@ pub async fn show_book_list(
     )
 )]
 #[tracing::instrument(
-    skip(_user, registry),
+    skip(user, registry),
     fields(
-        user_id = %_user.user.id.to_string()
+        user_id = %user.user.id.to_string()
     )
 )]
 pub async fn show_book(
-    _user: AuthorizedUser,
+    user: AuthorizedUser,
     Path(book_id): Path<BookId>,
     State(registry): State<AppRegistry>,
-) -> AppResult<Json<BookResponse>> {
+) -> AppResult<impl IntoResponse> {
     registry
         .book_repository()
         .find_by_id(book_id)
         .await
         .and_then(|bc| match bc {
-            Some(bc) => Ok(Json(bc.into())),
+            // アーカイブ済みの蔵書は、所有者と管理者以外には存在しないものとして扱う
+            Some(bc) if bc.archive.is_some() && bc.owner.id != user.id() && !user.is_admin() => {
+                Err(AppError::EntityNotFound("not found".into()))
+            }
+            Some(bc) => Ok((
+                [(header::ETAG, etag(bc.version))],
+                Json(BookResponse::from(bc)),
+            )),
             None => Err(AppError::EntityNotFound("not found".into())),
         })
 }
@@ -122,10 +294,11 @@ pub async fn show_book(
     path = "/books/{book_id}",
     tag = "蔵書",
     summary = "蔵書更新",
-    description = "指定したIDの蔵書情報を更新します",
+    description = "指定したIDの蔵書情報を更新します。tags を指定した場合は、蔵書に付いているタグを指定したタグに置き換えます",
     operation_id = "updateBook",
     params(
-        ("book_id" = String, Path, description = "蔵書ID")
+        ("book_id" = String, Path, description = "蔵書ID"),
+        ("If-Match" = Option<String>, Header, description = "蔵書取得時の ETag。指定した場合、蔵書が他のユーザーによって更新されていれば 412 を返す")
     ),
     request_body = UpdateBookRequest,
     responses(
@@ -133,6 +306,8 @@ pub async fn show_book(
         (status = 400, description = "リクエストパラメータ不正"),
         (status = 401, description = "認証エラー"),
         (status = 404, description = "蔵書が存在しない"),
+        (status = 412, description = "If-Match ヘッダの ETag が蔵書の現在のバージョンと一致しない場合"),
+        (status = 422, description = "ISBN のチェックディジットが正しくない場合や、存在しないタグを指定した場合"),
     ),
     security(
         ("bearer_auth" = [])
@@ -141,12 +316,14 @@ pub async fn show_book(
 pub async fn update_book(
     user: AuthorizedUser,
     Path(book_id): Path<BookId>,
+    IfMatch(expected_version): IfMatch,
     State(registry): State<AppRegistry>,
     Json(req): Json<UpdateBookRequest>,
 ) -> AppResult<StatusCode> {
     req.validate()?;
 
-    let update_book = UpdateBookRequestWithIds::new(book_id, user.id(), user.is_admin(), req);
+    let update_book =
+        UpdateBookRequestWithIds::new(book_id, user.id(), user.is_admin(), expected_version, req);
 
     registry
         .book_repository()
@@ -155,20 +332,69 @@ pub async fn update_book(
         .map(|_| StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    patch,
+    path = "/books/{book_id}",
+    tag = "蔵書",
+    summary = "蔵書の部分更新",
+    description = "指定したIDの蔵書情報のうち、リクエストに含めた項目だけを更新します（JSON Merge Patch）。省略した項目は変更しません",
+    operation_id = "patchBook",
+    params(
+        ("book_id" = String, Path, description = "蔵書ID"),
+        ("If-Match" = Option<String>, Header, description = "蔵書取得時の ETag。指定した場合、蔵書が他のユーザーによって更新されていれば 412 を返す")
+    ),
+    request_body(content = PatchBookRequest, content_type = "application/merge-patch+json"),
+    responses(
+        (status = 204, description = "蔵書の更新成功"),
+        (status = 400, description = "リクエストパラメータ不正"),
+        (status = 401, description = "認証エラー"),
+        (status = 404, description = "蔵書が存在しない"),
+        (status = 412, description = "If-Match ヘッダの ETag が蔵書の現在のバージョンと一致しない場合"),
+        (status = 422, description = "null を指定できない項目に null を指定した場合や、ISBN のチェックディジットが正しくない場合、存在しないタグを指定した場合"),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn patch_book(
+    user: AuthorizedUser,
+    Path(book_id): Path<BookId>,
+    IfMatch(expected_version): IfMatch,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<PatchBookRequest>,
+) -> AppResult<StatusCode> {
+    req.validate()?;
+
+    let patch_book =
+        PatchBookRequestWithIds::new(book_id, user.id(), user.is_admin(), expected_version, req)
+            .try_into()?;
+
+    registry
+        .book_repository()
+        .patch(patch_book)
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+}
+
 #[utoipa::path(
     delete,
     path = "/books/{book_id}",
     tag = "蔵書",
     summary = "蔵書削除",
-    description = "指定したIDの蔵書を削除します",
+    description = "指定したIDの蔵書をアーカイブします。蔵書は物理削除せず、貸出履歴は残ります。アーカイブした蔵書は一覧に表示されず、貸出や予約もできなくなります。蔵書への予約は取り消されます。コピーが貸出中の場合は削除できません",
     operation_id = "deleteBook",
     params(
-        ("book_id" = String, Path, description = "蔵書ID")
+        ("book_id" = String, Path, description = "蔵書ID"),
+        ("If-Match" = Option<String>, Header, description = "蔵書取得時の ETag。指定した場合、蔵書が他のユーザーによって更新されていれば 412 を返す"),
+        BookDeleteQuery
     ),
     responses(
         (status = 204, description = "蔵書の削除成功"),
+        (status = 400, description = "クエリパラメータ不正"),
         (status = 401, description = "認証エラー"),
-        (status = 404, description = "蔵書が存在しない"),
+        (status = 404, description = "蔵書が存在しないか、アーカイブ済みの場合"),
+        (status = 412, description = "If-Match ヘッダの ETag が蔵書の現在のバージョンと一致しない場合"),
+        (status = 422, description = "コピーが貸出中の場合"),
     ),
     security(
         ("bearer_auth" = [])
@@ -177,12 +403,18 @@ pub async fn update_book(
 pub async fn delete_book(
     user: AuthorizedUser,
     Path(book_id): Path<BookId>,
+    IfMatch(expected_version): IfMatch,
+    Query(query): Query<BookDeleteQuery>,
     State(registry): State<AppRegistry>,
 ) -> AppResult<StatusCode> {
+    query.validate()?;
+
     let delete_book = DeleteBook {
         book_id,
+        reason: query.reason,
         requested_user: user.id(),
         is_admin: user.is_admin(),
+        expected_version,
     };
 
     registry
@@ -191,3 +423,189 @@ pub async fn delete_book(
         .await
         .map(|_| StatusCode::NO_CONTENT)
 }
+
+#[utoipa::path(
+    post,
+    path = "/books/{book_id}/restore",
+    tag = "蔵書",
+    summary = "蔵書の復元",
+    description = "アーカイブした蔵書を元に戻します。蔵書の所有者と管理者のみ実行可能です",
+    operation_id = "restoreBook",
+    params(
+        ("book_id" = String, Path, description = "蔵書ID")
+    ),
+    responses(
+        (status = 204, description = "蔵書の復元成功"),
+        (status = 401, description = "認証エラー"),
+        (status = 404, description = "アーカイブ済みの蔵書が存在しない"),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn restore_book(
+    user: AuthorizedUser,
+    Path(book_id): Path<BookId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    let restore_book = RestoreBook {
+        book_id,
+        requested_user: user.id(),
+        is_admin: user.is_admin(),
+    };
+
+    registry
+        .book_repository()
+        .restore(restore_book)
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    put,
+    path = "/books/{book_id}/owner",
+    tag = "蔵書",
+    summary = "蔵書の所有者変更",
+    description = "指定したIDの蔵書の所有者を変更し、所有者の変更履歴に記録します。元の所有者が追加したコピーの所有者も変更します。蔵書の所有者か管理者のみ実行可能です。コピーが貸出中の場合は変更できません",
+    operation_id = "transferBookOwner",
+    params(
+        ("book_id" = String, Path, description = "蔵書ID")
+    ),
+    request_body = UpdateBookOwnerRequest,
+    responses(
+        (status = 204, description = "所有者の変更成功"),
+        (status = 400, description = "リクエストパラメータ不正"),
+        (status = 401, description = "認証エラー"),
+        (status = 404, description = "蔵書が存在しないか、アーカイブ済みの場合"),
+        (status = 422, description = "コピーが貸出中の場合や、新しい所有者が存在しないか、すでに所有者の場合"),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn transfer_book_owner(
+    user: AuthorizedUser,
+    Path(book_id): Path<BookId>,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<UpdateBookOwnerRequest>,
+) -> AppResult<StatusCode> {
+    req.validate()?;
+
+    let transfer = UpdateBookOwnerRequestWithIds::new(book_id, user.id(), user.is_admin(), req);
+
+    registry
+        .book_repository()
+        .transfer_owner(transfer.into())
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/books/{book_id}/owner-history",
+    tag = "蔵書",
+    summary = "蔵書の所有者の変更履歴取得",
+    description = "指定した蔵書の所有者の変更履歴を変更日時の古い順に取得します",
+    operation_id = "getBookOwnerHistory",
+    params(
+        ("book_id" = String, Path, description = "蔵書ID")
+    ),
+    responses(
+        (status = 200, description = "変更履歴の取得成功", body = BookOwnerTransfersResponse),
+        (status = 401, description = "認証エラー"),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn show_book_owner_history(
+    _user: AuthorizedUser,
+    Path(book_id): Path<BookId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<BookOwnerTransfersResponse>> {
+    registry
+        .book_repository()
+        .find_owner_transfers(book_id)
+        .await
+        .map(BookOwnerTransfersResponse::from)
+        .map(Json)
+}
+
+#[utoipa::path(
+    post,
+    path = "/books/{book_id}/copies",
+    tag = "蔵書",
+    summary = "蔵書のコピー登録",
+    description = "指定した蔵書にコピー（現物）を追加します。登録者がコピーの所有者になります",
+    operation_id = "registerBookCopy",
+    params(
+        ("book_id" = String, Path, description = "蔵書ID")
+    ),
+    request_body = CreateBookCopyRequest,
+    responses(
+        (status = 201, description = "コピーの登録成功"),
+        (status = 400, description = "リクエストパラメータ不正"),
+        (status = 401, description = "認証エラー"),
+        (status = 404, description = "蔵書が存在しない"),
+        (status = 422, description = "バーコードがすでに使われている"),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn register_book_copy(
+    user: AuthorizedUser,
+    Path(book_id): Path<BookId>,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<CreateBookCopyRequest>,
+) -> AppResult<StatusCode> {
+    req.validate()?;
+
+    let create_book_copy = CreateBookCopyRequestWithIds::new(book_id, user.id(), req);
+
+    registry
+        .book_repository()
+        .create_copy(create_book_copy.into())
+        .await
+        .map(|_| StatusCode::CREATED)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/books/{book_id}/copies/{copy_id}",
+    tag = "蔵書",
+    summary = "蔵書のコピー削除",
+    description = "指定した蔵書のコピーを削除します。削除できるのはコピーの所有者と管理者のみです。蔵書の最後のコピーは削除できないため、蔵書ごと不要な場合はアーカイブしてください",
+    operation_id = "deleteBookCopy",
+    params(
+        ("book_id" = String, Path, description = "蔵書ID"),
+        ("copy_id" = String, Path, description = "コピーID")
+    ),
+    responses(
+        (status = 204, description = "コピーの削除成功"),
+        (status = 401, description = "認証エラー"),
+        (status = 404, description = "コピーが存在しない"),
+        (status = 422, description = "コピーが貸出中か、蔵書の最後のコピーの場合"),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn delete_book_copy(
+    user: AuthorizedUser,
+    Path((book_id, copy_id)): Path<(BookId, BookCopyId)>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    let delete_book_copy = DeleteBookCopy {
+        book_id,
+        copy_id,
+        requested_user: user.id(),
+        is_admin: user.is_admin(),
+    };
+
+    registry
+        .book_repository()
+        .delete_copy(delete_book_copy)
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+}
@@ -1,32 +1,42 @@
 use axum::{
     Json,
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
 };
+use garde::Validate;
 use kernel::model::{
-    checkout::event::{CreateCheckout, UpdateReturned},
+    checkout::event::{CreateCheckout, RenewCheckout, UpdateReturned},
     id::{BookId, CheckoutId},
 };
 use registry::AppRegistry;
-use shared::error::AppResult;
+use shared::error::{AppError, AppResult};
 
-use crate::{extractor::AuthorizedUser, model::checkout::CheckoutsResponse};
+use crate::{
+    extractor::AuthorizedUser,
+    model::checkout::{
+        CheckoutBookRequest, CheckoutListQuery, CheckoutsResponse, OverdueCheckoutsResponse,
+    },
+};
 
 #[utoipa::path(
     post,
     path = "/books/{book_id}/checkouts",
     tag = "貸出・返却",
     summary = "蔵書貸出",
-    description = "指定した蔵書を借りる。既に貸出中の場合はエラーになります",
+    description = "指定した蔵書を借りる。コピーを指定しない場合は貸出中でないコピーから自動で選びます。すべてのコピーが貸出中の場合や、同時に借りられる冊数の上限に達している場合はエラーになります。管理者は他のユーザーの代理で貸し出せます",
     operation_id = "checkoutBook",
     params(
         ("book_id" = String, Path, description = "蔵書ID")
     ),
+    request_body = Option<CheckoutBookRequest>,
     responses(
         (status = 201, description = "貸出成功"),
         (status = 400, description = "リクエストパラメータ不正"),
         (status = 401, description = "認証エラー"),
+        (status = 403, description = "権限エラー（代理での貸出は管理者のみ）"),
         (status = 404, description = "蔵書が存在しない"),
+        (status = 409, description = "同時に借りられる冊数の上限に達している（code: CHECKOUT_LIMIT_EXCEEDED）"),
+        (status = 422, description = "すべてのコピーが貸出中、指定したコピーが貸出中、または他のユーザーのために取り置き中"),
     ),
     security(
         ("bearer_auth" = [])
@@ -36,8 +46,26 @@ pub async fn checkout_book(
     user: AuthorizedUser,
     Path(book_id): Path<BookId>,
     State(registry): State<AppRegistry>,
+    req: Option<Json<CheckoutBookRequest>>,
 ) -> AppResult<StatusCode> {
-    let create_checkout_history = CreateCheckout::new(book_id, user.id(), chrono::Utc::now());
+    let (user_id, copy_id) = req
+        .map(|Json(req)| (req.user_id, req.copy_id))
+        .unwrap_or_default();
+    let checked_out_by = match user_id {
+        // 他のユーザーの代理で貸し出せるのは管理者のみ
+        Some(user_id) if user_id != user.id() && !user.is_admin() => {
+            return Err(AppError::ForbiddenOperation);
+        }
+        Some(user_id) => user_id,
+        None => user.id(),
+    };
+    let create_checkout_history = CreateCheckout::new(
+        book_id,
+        copy_id,
+        checked_out_by,
+        user.id(),
+        chrono::Utc::now(),
+    );
 
     registry
         .checkout_repository()
@@ -51,7 +79,7 @@ pub async fn checkout_book(
     path = "/books/{book_id}/checkouts/{checkout_id}/returned",
     tag = "貸出・返却",
     summary = "蔵書返却",
-    description = "借りた蔵書を返却します",
+    description = "借りた蔵書を返却します。管理者は他のユーザーが借りた蔵書も返却できます",
     operation_id = "returnBook",
     params(
         ("book_id" = String, Path, description = "蔵書ID"),
@@ -72,7 +100,13 @@ pub async fn return_book(
     Path((book_id, checkout_id)): Path<(BookId, CheckoutId)>,
     State(registry): State<AppRegistry>,
 ) -> AppResult<StatusCode> {
-    let update_returned = UpdateReturned::new(checkout_id, book_id, user.id(), chrono::Utc::now());
+    let update_returned = UpdateReturned::new(
+        checkout_id,
+        book_id,
+        user.id(),
+        user.is_admin(),
+        chrono::Utc::now(),
+    );
 
     registry
         .checkout_repository()
@@ -81,15 +115,55 @@ pub async fn return_book(
         .map(|_| StatusCode::CREATED)
 }
 
+#[utoipa::path(
+    put,
+    path = "/books/{book_id}/checkouts/{checkout_id}/renewal",
+    tag = "貸出・返却",
+    summary = "貸出延長",
+    description = "借りている蔵書の返却期限を貸出期間の分だけ延長します。延長回数には上限があります",
+    operation_id = "renewCheckout",
+    params(
+        ("book_id" = String, Path, description = "蔵書ID"),
+        ("checkout_id" = String, Path, description = "貸出ID")
+    ),
+    responses(
+        (status = 204, description = "延長成功"),
+        (status = 400, description = "リクエストパラメータ不正"),
+        (status = 401, description = "認証エラー"),
+        (status = 404, description = "蔵書が存在しない"),
+        (status = 422, description = "延長できない貸出、または延長回数の上限に達している"),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn renew_checkout(
+    user: AuthorizedUser,
+    Path((book_id, checkout_id)): Path<(BookId, CheckoutId)>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    let renew_checkout = RenewCheckout::new(checkout_id, book_id, user.id());
+
+    registry
+        .checkout_repository()
+        .renew(renew_checkout)
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+}
+
 #[utoipa::path(
     get,
     path = "/books/checkouts",
     tag = "貸出・返却",
     summary = "貸出中蔵書一覧取得",
-    description = "現在貸出中のすべての蔵書を取得します",
+    description = "現在貸出中の蔵書を貸出日時の古い順に取得します。レスポンスの nextCursor を cursor に指定すると次のページを取得できます",
     operation_id = "listCheckedOutBooks",
+    params(
+        CheckoutListQuery
+    ),
     responses(
         (status = 200, description = "貸出中蔵書一覧の取得成功", body = CheckoutsResponse),
+        (status = 400, description = "クエリパラメータ不正"),
         (status = 401, description = "認証エラー"),
     ),
     security(
@@ -98,28 +172,60 @@ pub async fn return_book(
 )]
 pub async fn show_checked_out_list(
     _user: AuthorizedUser,
+    Query(query): Query<CheckoutListQuery>,
     State(registry): State<AppRegistry>,
 ) -> AppResult<Json<CheckoutsResponse>> {
+    query.validate()?;
+
     registry
         .checkout_repository()
-        .find_unreturned_all()
+        .find_unreturned_all(query.into())
         .await
         .map(CheckoutsResponse::from)
         .map(Json)
 }
 
+#[utoipa::path(
+    get,
+    path = "/books/checkouts/overdue",
+    tag = "貸出・返却",
+    summary = "延滞中蔵書一覧取得",
+    description = "返却期限を過ぎても返却されていない貸出を、返却期限の古い順に取得します",
+    operation_id = "listOverdueCheckouts",
+    responses(
+        (status = 200, description = "延滞中蔵書一覧の取得成功", body = OverdueCheckoutsResponse),
+        (status = 401, description = "認証エラー"),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn show_overdue_list(
+    _user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<OverdueCheckoutsResponse>> {
+    registry
+        .checkout_repository()
+        .find_overdue_all()
+        .await
+        .map(OverdueCheckoutsResponse::from)
+        .map(Json)
+}
+
 #[utoipa::path(
     get,
     path = "/books/{book_id}/checkout-history",
     tag = "貸出・返却",
     summary = "蔵書貸出履歴取得",
-    description = "指定した蔵書の貸出履歴（返却済みを含む）を取得します",
+    description = "指定した蔵書の貸出履歴（返却済みを含む）を貸出日時の新しい順に取得します。レスポンスの nextCursor を cursor に指定すると次のページを取得できます",
     operation_id = "getBookCheckoutHistory",
     params(
-        ("book_id" = String, Path, description = "蔵書ID")
+        ("book_id" = String, Path, description = "蔵書ID"),
+        CheckoutListQuery
     ),
     responses(
         (status = 200, description = "貸出履歴の取得成功", body = CheckoutsResponse),
+        (status = 400, description = "クエリパラメータ不正"),
         (status = 401, description = "認証エラー"),
         (status = 404, description = "蔵書が存在しない"),
     ),
@@ -130,11 +236,14 @@ pub async fn show_checked_out_list(
 pub async fn checkout_history(
     _user: AuthorizedUser,
     Path(book_id): Path<BookId>,
+    Query(query): Query<CheckoutListQuery>,
     State(registry): State<AppRegistry>,
 ) -> AppResult<Json<CheckoutsResponse>> {
+    query.validate()?;
+
     registry
         .checkout_repository()
-        .find_history_by_book_id(book_id)
+        .find_history_by_book_id(book_id, query.into())
         .await
         .map(CheckoutsResponse::from)
         .map(Json)
@@ -0,0 +1,231 @@
+use axum::{
+    Json,
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{HeaderMap, header},
+    response::IntoResponse,
+};
+use kernel::model::{
+    book::{BookCover, event::UpdateBookCover},
+    id::{BookId, CoverId},
+};
+use registry::AppRegistry;
+use shared::error::{AppError, AppResult};
+
+use crate::{
+    extractor::AuthorizedUser,
+    model::cover::{BookCoverResponse, CoverImage, CoverQuery},
+};
+
+// 書影の ID を含む URL は、書影を置き換えると URL も変わるため、長期間キャッシュさせる
+const COVER_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+// 書影の ID を含まない URL は、書影を置き換えても URL が変わらないため、短い期間だけキャッシュさせる
+const UNVERSIONED_COVER_CACHE_CONTROL: &str = "public, max-age=300";
+
+#[utoipa::path(
+    put,
+    path = "/books/{book_id}/cover",
+    tag = "蔵書",
+    summary = "書影登録",
+    description = "指定したIDの蔵書の書影を登録します。すでに書影がある場合は置き換えます。JPEG・PNG・WebP の画像（5 MiB まで、幅・高さ 4096 ピクセルまで）をリクエストボディにそのまま指定します。幅・高さ 256 ピクセルまでの PNG のサムネイルもあわせて生成します。蔵書の所有者か管理者のみ実行可能です。ファイルサイズが上限を超える場合は、画像を読み込む前に 413 を返します",
+    operation_id = "uploadBookCover",
+    params(
+        ("book_id" = String, Path, description = "蔵書ID")
+    ),
+    request_body(
+        content(
+            (Vec<u8> = "image/jpeg"),
+            (Vec<u8> = "image/png"),
+            (Vec<u8> = "image/webp"),
+        ),
+        description = "書影の画像",
+    ),
+    responses(
+        (status = 200, description = "書影の登録成功", body = BookCoverResponse),
+        (status = 401, description = "認証エラー"),
+        (status = 404, description = "蔵書が存在しないか、アーカイブ済みの場合や、蔵書の所有者でも管理者でもない場合"),
+        (status = 413, description = "画像のファイルサイズが上限（5 MiB）を超えている場合"),
+        (status = 415, description = "Content-Type が JPEG・PNG・WebP のいずれでもない場合"),
+        (status = 422, description = "画像の内容が Content-Type と一致しない場合や、画像として読み込めない場合"),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn upload_book_cover(
+    user: AuthorizedUser,
+    Path(book_id): Path<BookId>,
+    State(registry): State<AppRegistry>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> AppResult<Json<BookCoverResponse>> {
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+
+    // 画像の読み込みや保存をする前に、書影を登録できる蔵書かを確認する
+    // 所有者でも管理者でもない場合は、書影の登録時と同じく蔵書が見つからない扱いにする
+    registry
+        .book_repository()
+        .find_by_id(book_id)
+        .await?
+        .filter(|book| book.archive.is_none() && (book.owner.id == user.id() || user.is_admin()))
+        .ok_or_else(|| AppError::EntityNotFound("Specified book not found".into()))?;
+
+    // 画像の読み込みとサムネイルの生成は重いので、ブロッキング用のスレッドで行う
+    let image =
+        tokio::task::spawn_blocking(move || CoverImage::process(&content_type, body.to_vec()))
+            .await
+            .map_err(|e| AppError::ConversionEntityError(e.to_string()))??;
+
+    let cover = BookCover {
+        id: CoverId::new(),
+        content_type: image.content_type,
+    };
+    let storage = registry.blob_storage();
+    storage.put(&cover.key(), image.original).await?;
+    storage.put(&cover.thumbnail_key(), image.thumbnail).await?;
+
+    let update_cover = UpdateBookCover {
+        book_id,
+        cover: cover.clone(),
+        requested_user: user.id(),
+        is_admin: user.is_admin(),
+    };
+    let previous = match registry.book_repository().update_cover(update_cover).await {
+        Ok(previous) => previous,
+        Err(e) => {
+            // 書影を登録できなかった場合は、保存した画像を削除する
+            delete_cover_files(&registry, &cover).await;
+            return Err(e);
+        }
+    };
+    if let Some(previous) = previous {
+        delete_cover_files(&registry, &previous).await;
+    }
+
+    Ok(Json(BookCoverResponse::new(book_id.raw(), &cover)))
+}
+
+// 書影の画像とサムネイルを削除する
+// 削除に失敗しても書影の登録には影響しないため、ログに残すだけにする
+async fn delete_cover_files(registry: &AppRegistry, cover: &BookCover) {
+    let storage = registry.blob_storage();
+    for key in [cover.key(), cover.thumbnail_key()] {
+        if let Err(e) = storage.delete(&key).await {
+            tracing::warn!(
+                error.message = %e,
+                key,
+                "Failed to delete cover file"
+            );
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/books/{book_id}/cover",
+    tag = "蔵書",
+    summary = "書影取得",
+    description = "指定したIDの蔵書の書影の画像を返します。img 要素から参照できるよう、認証は不要です。書影の登録時に返した URL（書影のIDを v に含む）は長期間キャッシュできます",
+    operation_id = "getBookCover",
+    params(
+        ("book_id" = String, Path, description = "蔵書ID"),
+        CoverQuery
+    ),
+    responses(
+        (status = 200, description = "書影の画像", content(
+            (Vec<u8> = "image/jpeg"),
+            (Vec<u8> = "image/png"),
+            (Vec<u8> = "image/webp"),
+        )),
+        (status = 404, description = "蔵書が存在しないか、アーカイブ済みの場合や、書影が登録されていない場合、v が現在の書影のIDと一致しない場合"),
+    )
+)]
+pub async fn show_book_cover(
+    Path(book_id): Path<BookId>,
+    Query(query): Query<CoverQuery>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<impl IntoResponse> {
+    let cover = find_cover(&registry, book_id).await?;
+    let cache_control = cover_cache_control(&cover, query.v)?;
+    let data = find_cover_file(&registry, &cover.key()).await?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, cover.content_type),
+            (header::CACHE_CONTROL, cache_control.into()),
+        ],
+        data,
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/books/{book_id}/cover/thumbnail",
+    tag = "蔵書",
+    summary = "書影のサムネイル取得",
+    description = "指定したIDの蔵書の書影のサムネイル（PNG）を返します。img 要素から参照できるよう、認証は不要です。書影の登録時に返した URL（書影のIDを v に含む）は長期間キャッシュできます",
+    operation_id = "getBookCoverThumbnail",
+    params(
+        ("book_id" = String, Path, description = "蔵書ID"),
+        CoverQuery
+    ),
+    responses(
+        (status = 200, description = "書影のサムネイル", content(
+            (Vec<u8> = "image/png"),
+        )),
+        (status = 404, description = "蔵書が存在しないか、アーカイブ済みの場合や、書影が登録されていない場合、v が現在の書影のIDと一致しない場合"),
+    )
+)]
+pub async fn show_book_cover_thumbnail(
+    Path(book_id): Path<BookId>,
+    Query(query): Query<CoverQuery>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<impl IntoResponse> {
+    let cover = find_cover(&registry, book_id).await?;
+    let cache_control = cover_cache_control(&cover, query.v)?;
+    let data = find_cover_file(&registry, &cover.thumbnail_key()).await?;
+
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                BookCover::THUMBNAIL_CONTENT_TYPE.to_string(),
+            ),
+            (header::CACHE_CONTROL, cache_control.into()),
+        ],
+        data,
+    ))
+}
+
+// 認証なしで参照できるため、アーカイブ済みの蔵書の書影は返さない
+async fn find_cover(registry: &AppRegistry, book_id: BookId) -> AppResult<BookCover> {
+    registry
+        .book_repository()
+        .find_by_id(book_id)
+        .await?
+        .filter(|book| book.archive.is_none())
+        .and_then(|book| book.cover)
+        .ok_or_else(|| AppError::EntityNotFound("Specified cover not found".into()))
+}
+
+// URL に含まれる書影の ID から、レスポンスをキャッシュさせる期間を決める
+// 置き換える前の書影の URL で、現在の書影が長期間キャッシュされないよう、ID が一致しない場合は 404 にする
+fn cover_cache_control(cover: &BookCover, version: Option<CoverId>) -> AppResult<&'static str> {
+    match version {
+        None => Ok(UNVERSIONED_COVER_CACHE_CONTROL),
+        Some(version) if version == cover.id => Ok(COVER_CACHE_CONTROL),
+        Some(_) => Err(AppError::EntityNotFound("Specified cover not found".into())),
+    }
+}
+
+async fn find_cover_file(registry: &AppRegistry, key: &str) -> AppResult<Vec<u8>> {
+    registry
+        .blob_storage()
+        .get(key)
+        .await?
+        .ok_or_else(|| AppError::EntityNotFound("Specified cover not found".into()))
+}
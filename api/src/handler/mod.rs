@@ -1,5 +1,9 @@
 pub mod auth;
 pub mod book;
 pub mod checkout;
+pub mod cover;
 pub mod health;
+pub mod reservation;
+pub mod session;
+pub mod tag;
 pub mod user;
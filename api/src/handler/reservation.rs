@@ -0,0 +1,119 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use kernel::model::{
+    id::{BookId, ReservationId},
+    reservation::event::{CreateReservation, DeleteReservation},
+};
+use registry::AppRegistry;
+use shared::error::AppResult;
+
+use crate::{extractor::AuthorizedUser, model::reservation::ReservationsResponse};
+
+#[utoipa::path(
+    post,
+    path = "/books/{book_id}/reservations",
+    tag = "予約",
+    summary = "蔵書予約",
+    description = "貸出中の蔵書を予約します。返却されると予約順に取り置かれ、受け取り期限までは予約者本人のみが借りられます",
+    operation_id = "reserveBook",
+    params(
+        ("book_id" = String, Path, description = "蔵書ID")
+    ),
+    responses(
+        (status = 201, description = "予約成功"),
+        (status = 400, description = "リクエストパラメータ不正"),
+        (status = 401, description = "認証エラー"),
+        (status = 404, description = "蔵書が存在しない"),
+        (status = 422, description = "貸出可能な蔵書、自分が借りている蔵書、または予約済みの蔵書"),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn reserve_book(
+    user: AuthorizedUser,
+    Path(book_id): Path<BookId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    let create_reservation = CreateReservation::new(book_id, user.id(), chrono::Utc::now());
+
+    registry
+        .reservation_repository()
+        .create(create_reservation)
+        .await
+        .map(|_| StatusCode::CREATED)
+}
+
+#[utoipa::path(
+    get,
+    path = "/books/{book_id}/reservations",
+    tag = "予約",
+    summary = "蔵書予約一覧取得",
+    description = "指定した蔵書の予約を予約順に取得します",
+    operation_id = "listBookReservations",
+    params(
+        ("book_id" = String, Path, description = "蔵書ID")
+    ),
+    responses(
+        (status = 200, description = "予約一覧の取得成功", body = ReservationsResponse),
+        (status = 401, description = "認証エラー"),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn show_reservation_list(
+    _user: AuthorizedUser,
+    Path(book_id): Path<BookId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<ReservationsResponse>> {
+    registry
+        .reservation_repository()
+        .find_by_book_id(book_id)
+        .await
+        .map(ReservationsResponse::from)
+        .map(Json)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/books/{book_id}/reservations/{reservation_id}",
+    tag = "予約",
+    summary = "蔵書予約取り消し",
+    description = "予約を取り消します。予約したユーザー本人または管理者のみ実行可能です",
+    operation_id = "cancelReservation",
+    params(
+        ("book_id" = String, Path, description = "蔵書ID"),
+        ("reservation_id" = String, Path, description = "予約ID")
+    ),
+    responses(
+        (status = 204, description = "予約の取り消し成功"),
+        (status = 401, description = "認証エラー"),
+        (status = 404, description = "予約が存在しない"),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn cancel_reservation(
+    user: AuthorizedUser,
+    Path((book_id, reservation_id)): Path<(BookId, ReservationId)>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    let delete_reservation = DeleteReservation::new(
+        reservation_id,
+        book_id,
+        user.id(),
+        user.is_admin(),
+        chrono::Utc::now(),
+    );
+
+    registry
+        .reservation_repository()
+        .delete(delete_reservation)
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,205 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use kernel::model::id::{SessionId, UserId};
+use registry::AppRegistry;
+use shared::error::{AppError, AppResult};
+
+use crate::{extractor::AuthorizedUser, model::session::SessionsResponse};
+
+async fn find_sessions(
+    user: &AuthorizedUser,
+    user_id: UserId,
+    registry: &AppRegistry,
+) -> AppResult<Json<SessionsResponse>> {
+    let auth_repository = registry.auth_repository();
+    let sessions = auth_repository.find_sessions(user_id).await?;
+    let current = auth_repository.find_session_id(&user.access_token).await?;
+
+    Ok(Json(SessionsResponse::new(sessions, current)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/users/me/sessions",
+    tag = "ユーザー",
+    summary = "自分のセッション一覧取得",
+    description = "ログイン中のユーザーのセッション（ログインした端末）の一覧を取得します",
+    operation_id = "getMySessions",
+    responses(
+        (status = 200, description = "セッション一覧の取得成功", body = SessionsResponse),
+        (status = 401, description = "認証エラー"),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn get_my_sessions(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<SessionsResponse>> {
+    find_sessions(&user, user.id(), &registry).await
+}
+
+#[utoipa::path(
+    delete,
+    path = "/users/me/sessions/{session_id}",
+    tag = "ユーザー",
+    summary = "自分のセッションの終了",
+    description = "ログイン中のユーザーの指定したセッションを終了し、そのセッションのアクセストークンとリフレッシュトークンを無効化します",
+    operation_id = "deleteMySession",
+    params(
+        ("session_id" = String, Path, description = "セッションID")
+    ),
+    responses(
+        (status = 204, description = "セッションの終了成功"),
+        (status = 401, description = "認証エラー"),
+        (status = 404, description = "セッションが存在しない"),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn delete_my_session(
+    user: AuthorizedUser,
+    Path(session_id): Path<SessionId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    registry
+        .auth_repository()
+        .delete_session(user.id(), session_id)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/users/me/sessions",
+    tag = "ユーザー",
+    summary = "すべての端末からのログアウト",
+    description = "ログイン中のユーザーのすべてのセッション（リクエストに使ったセッションを含む）を終了します",
+    operation_id = "deleteMySessions",
+    responses(
+        (status = 204, description = "セッションの終了成功"),
+        (status = 401, description = "認証エラー"),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn delete_my_sessions(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    registry
+        .auth_repository()
+        .delete_sessions(user.id())
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/users/{user_id}/sessions",
+    tag = "ユーザー",
+    summary = "ユーザーのセッション一覧取得",
+    description = "指定したユーザーのセッションの一覧を取得します。管理者のみ実行可能です",
+    operation_id = "getUserSessions",
+    params(
+        ("user_id" = String, Path, description = "ユーザーID")
+    ),
+    responses(
+        (status = 200, description = "セッション一覧の取得成功", body = SessionsResponse),
+        (status = 401, description = "認証エラー"),
+        (status = 403, description = "権限エラー（管理者のみ）"),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn get_user_sessions(
+    user: AuthorizedUser,
+    Path(user_id): Path<UserId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<SessionsResponse>> {
+    if !user.is_admin() {
+        return Err(AppError::ForbiddenOperation);
+    }
+
+    find_sessions(&user, user_id, &registry).await
+}
+
+#[utoipa::path(
+    delete,
+    path = "/users/{user_id}/sessions/{session_id}",
+    tag = "ユーザー",
+    summary = "ユーザーのセッションの終了",
+    description = "指定したユーザーのセッションを終了します。管理者のみ実行可能です",
+    operation_id = "deleteUserSession",
+    params(
+        ("user_id" = String, Path, description = "ユーザーID"),
+        ("session_id" = String, Path, description = "セッションID")
+    ),
+    responses(
+        (status = 204, description = "セッションの終了成功"),
+        (status = 401, description = "認証エラー"),
+        (status = 403, description = "権限エラー（管理者のみ）"),
+        (status = 404, description = "セッションが存在しない"),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn delete_user_session(
+    user: AuthorizedUser,
+    Path((user_id, session_id)): Path<(UserId, SessionId)>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    if !user.is_admin() {
+        return Err(AppError::ForbiddenOperation);
+    }
+
+    registry
+        .auth_repository()
+        .delete_session(user_id, session_id)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/users/{user_id}/sessions",
+    tag = "ユーザー",
+    summary = "ユーザーの強制ログアウト",
+    description = "指定したユーザーのすべてのセッションを終了し、すべての端末からログアウトさせます。管理者のみ実行可能です",
+    operation_id = "deleteUserSessions",
+    params(
+        ("user_id" = String, Path, description = "ユーザーID")
+    ),
+    responses(
+        (status = 204, description = "セッションの終了成功"),
+        (status = 401, description = "認証エラー"),
+        (status = 403, description = "権限エラー（管理者のみ）"),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn delete_user_sessions(
+    user: AuthorizedUser,
+    Path(user_id): Path<UserId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    if !user.is_admin() {
+        return Err(AppError::ForbiddenOperation);
+    }
+
+    registry.auth_repository().delete_sessions(user_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,158 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use garde::Validate;
+use kernel::model::{id::TagId, tag::event::DeleteTag};
+use registry::AppRegistry;
+use shared::error::{AppError, AppResult};
+
+use crate::{
+    extractor::AuthorizedUser,
+    model::tag::{
+        CreateTagRequest, TagResponse, TagsResponse, UpdateTagRequest, UpdateTagRequestWithId,
+    },
+};
+
+#[utoipa::path(
+    get,
+    path = "/tags",
+    tag = "タグ",
+    summary = "タグ一覧取得",
+    description = "登録されているすべてのタグをタグ名の昇順で取得します",
+    operation_id = "listTags",
+    responses(
+        (status = 200, description = "タグ一覧の取得成功", body = TagsResponse),
+        (status = 401, description = "認証エラー"),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn list_tags(
+    _user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<TagsResponse>> {
+    let items = registry
+        .tag_repository()
+        .find_all()
+        .await?
+        .into_iter()
+        .map(TagResponse::from)
+        .collect();
+
+    Ok(Json(TagsResponse { items }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/tags",
+    tag = "タグ",
+    summary = "タグ登録",
+    description = "新しいタグを登録します。管理者のみ実行可能です",
+    operation_id = "registerTag",
+    request_body = CreateTagRequest,
+    responses(
+        (status = 201, description = "タグの登録成功", body = TagResponse),
+        (status = 400, description = "リクエストパラメータ不正"),
+        (status = 401, description = "認証エラー"),
+        (status = 403, description = "権限エラー（管理者のみ）"),
+        (status = 422, description = "同じ名前のタグが登録済みの場合"),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn register_tag(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<CreateTagRequest>,
+) -> AppResult<(StatusCode, Json<TagResponse>)> {
+    if !user.is_admin() {
+        return Err(AppError::ForbiddenOperation);
+    }
+
+    req.validate()?;
+
+    let tag = registry.tag_repository().create(req.into()).await?;
+    Ok((StatusCode::CREATED, Json(tag.into())))
+}
+
+#[utoipa::path(
+    put,
+    path = "/tags/{tag_id}",
+    tag = "タグ",
+    summary = "タグ更新",
+    description = "指定したタグの名前を変更します。管理者のみ実行可能です",
+    operation_id = "updateTag",
+    params(
+        ("tag_id" = String, Path, description = "タグID")
+    ),
+    request_body = UpdateTagRequest,
+    responses(
+        (status = 204, description = "タグの更新成功"),
+        (status = 400, description = "リクエストパラメータ不正"),
+        (status = 401, description = "認証エラー"),
+        (status = 403, description = "権限エラー（管理者のみ）"),
+        (status = 404, description = "タグが存在しない"),
+        (status = 422, description = "同じ名前のタグが登録済みの場合"),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn update_tag(
+    user: AuthorizedUser,
+    Path(tag_id): Path<TagId>,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<UpdateTagRequest>,
+) -> AppResult<StatusCode> {
+    if !user.is_admin() {
+        return Err(AppError::ForbiddenOperation);
+    }
+
+    req.validate()?;
+
+    registry
+        .tag_repository()
+        .update(UpdateTagRequestWithId::new(tag_id, req).into())
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/tags/{tag_id}",
+    tag = "タグ",
+    summary = "タグ削除",
+    description = "指定したタグを削除します。蔵書に付いているタグも外れます。管理者のみ実行可能です",
+    operation_id = "deleteTag",
+    params(
+        ("tag_id" = String, Path, description = "タグID")
+    ),
+    responses(
+        (status = 204, description = "タグの削除成功"),
+        (status = 401, description = "認証エラー"),
+        (status = 403, description = "権限エラー（管理者のみ）"),
+        (status = 404, description = "タグが存在しない"),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn delete_tag(
+    user: AuthorizedUser,
+    Path(tag_id): Path<TagId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    if !user.is_admin() {
+        return Err(AppError::ForbiddenOperation);
+    }
+
+    registry
+        .tag_repository()
+        .delete(DeleteTag { tag_id })
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+}
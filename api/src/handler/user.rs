@@ -1,6 +1,6 @@
 use axum::{
     Json,
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
 };
 use garde::Validate;
@@ -12,9 +12,13 @@ use crate::{
     extractor::AuthorizedUser,
     model::{
         checkout::CheckoutsResponse,
+        reservation::ReservationsResponse,
         user::{
-            CreateUserRequest, UpdateUserPasswordRequest, UpdateUserPasswordRequestWithUserId,
-            UpdateUserRoleRequest, UpdateUserRoleRequestWithUserId, UserResponse, UsersResponse,
+            CreateUserRequest, DeleteUserQuery, DeleteUserQueryWithIds, PatchUserRequest,
+            PatchUserRequestWithUserId, UpdateUserCheckoutLimitRequest,
+            UpdateUserCheckoutLimitRequestWithUserId, UpdateUserPasswordRequest,
+            UpdateUserPasswordRequestWithUserId, UpdateUserRoleRequest,
+            UpdateUserRoleRequestWithUserId, UserResponse, UsersResponse,
         },
     },
 };
@@ -87,16 +91,19 @@ pub async fn list_users(
     path = "/users/{user_id}",
     tag = "ユーザー",
     summary = "ユーザー削除",
-    description = "指定したユーザーを削除します。管理者のみ実行可能です",
+    description = "指定したユーザーを削除します。管理者のみ実行可能です。ユーザーが蔵書やコピーを所有している場合は、引き継ぎ先のユーザー（transferBooksTo）かアーカイブ（archiveBooks）のどちらかを指定する必要があります。ユーザーが借りている蔵書がある場合は、forceReturn を指定して返却しない限り削除できません。返却済みの貸出履歴は、借りたユーザーを匿名化して残します。削除したユーザーのトークンはすべて無効になります",
     operation_id = "deleteUser",
     params(
-        ("user_id" = String, Path, description = "ユーザーID")
+        ("user_id" = String, Path, description = "ユーザーID"),
+        DeleteUserQuery
     ),
     responses(
         (status = 200, description = "ユーザー削除成功"),
+        (status = 400, description = "クエリパラメータ不正"),
         (status = 401, description = "認証エラー"),
         (status = 403, description = "権限エラー（管理者のみ）"),
         (status = 404, description = "ユーザーが存在しない"),
+        (status = 422, description = "蔵書の引き継ぎ方法が指定されていない場合や、借りている蔵書がある場合、所有する蔵書が貸出中でアーカイブできない場合など"),
     ),
     security(
         ("bearer_auth" = [])
@@ -105,16 +112,18 @@ pub async fn list_users(
 pub async fn delete_user(
     user: AuthorizedUser,
     Path(user_id): Path<UserId>,
+    Query(query): Query<DeleteUserQuery>,
     State(registry): State<AppRegistry>,
 ) -> AppResult<StatusCode> {
     if !user.is_admin() {
         return Err(AppError::ForbiddenOperation);
     }
 
-    registry
-        .user_repository()
-        .delete(DeleteUser { user_id })
-        .await?;
+    let delete_user = DeleteUser::try_from(DeleteUserQueryWithIds::new(user_id, user.id(), query))?;
+    registry.user_repository().delete(delete_user).await?;
+
+    // 削除したユーザーのトークンをすべて無効にする
+    registry.auth_repository().delete_sessions(user_id).await?;
 
     Ok(StatusCode::OK)
 }
@@ -124,7 +133,7 @@ pub async fn delete_user(
     path = "/users/{user_id}/role",
     tag = "ユーザー",
     summary = "ユーザーロール変更",
-    description = "指定したユーザーのロール（権限）を変更します。管理者のみ実行可能です",
+    description = "指定したユーザーのロール（権限）を変更します。管理者のみ実行可能です。変更したユーザーはすべての端末からログアウトされます",
     operation_id = "changeUserRole",
     params(
         ("user_id" = String, Path, description = "ユーザーID")
@@ -155,6 +164,51 @@ pub async fn change_role(
         .update_role(UpdateUserRoleRequestWithUserId::new(user_id, req).into())
         .await?;
 
+    // 変更前のロールで発行したトークンを使い続けられないよう、すべて無効にする
+    registry.auth_repository().delete_sessions(user_id).await?;
+
+    Ok(StatusCode::OK)
+}
+
+#[utoipa::path(
+    put,
+    path = "/users/{user_id}/checkout-limit",
+    tag = "ユーザー",
+    summary = "貸出数上限変更",
+    description = "指定したユーザーが同時に借りられる冊数の上限を変更します。nullを指定するとロールごとの上限に戻ります。管理者のみ実行可能です",
+    operation_id = "changeUserCheckoutLimit",
+    params(
+        ("user_id" = String, Path, description = "ユーザーID")
+    ),
+    request_body = UpdateUserCheckoutLimitRequest,
+    responses(
+        (status = 200, description = "貸出数上限の更新成功"),
+        (status = 400, description = "リクエストパラメータ不正"),
+        (status = 401, description = "認証エラー"),
+        (status = 403, description = "権限エラー（管理者のみ）"),
+        (status = 404, description = "ユーザーが存在しない"),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn change_checkout_limit(
+    user: AuthorizedUser,
+    Path(user_id): Path<UserId>,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<UpdateUserCheckoutLimitRequest>,
+) -> AppResult<StatusCode> {
+    if !user.is_admin() {
+        return Err(AppError::ForbiddenOperation);
+    }
+
+    req.validate()?;
+
+    registry
+        .user_repository()
+        .update_checkout_limit(UpdateUserCheckoutLimitRequestWithUserId::new(user_id, req).into())
+        .await?;
+
     Ok(StatusCode::OK)
 }
 
@@ -177,12 +231,49 @@ pub async fn get_current_user(user: AuthorizedUser) -> Json<UserResponse> {
     Json(UserResponse::from(user.user))
 }
 
+#[utoipa::path(
+    patch,
+    path = "/users/me",
+    tag = "ユーザー",
+    summary = "自身のユーザー情報の部分更新",
+    description = "ログイン中のユーザー自身の名前とメールアドレスのうち、リクエストに含めた項目だけを更新します（JSON Merge Patch）",
+    operation_id = "patchCurrentUser",
+    request_body(content = PatchUserRequest, content_type = "application/merge-patch+json"),
+    responses(
+        (status = 200, description = "更新後の自身の情報", body = UserResponse),
+        (status = 400, description = "リクエストパラメータ不正"),
+        (status = 401, description = "認証エラー"),
+        (status = 422, description = "null を指定できない項目に null を指定した場合や、メールアドレスがすでに使われている場合"),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn patch_current_user(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<PatchUserRequest>,
+) -> AppResult<Json<UserResponse>> {
+    req.validate()?;
+
+    let update_profile = PatchUserRequestWithUserId::new(user.id(), req).try_into()?;
+
+    let user_repository = registry.user_repository();
+    user_repository.update_profile(update_profile).await?;
+
+    user_repository
+        .find_current_user(user.id())
+        .await?
+        .map(|user| Json(UserResponse::from(user)))
+        .ok_or_else(|| AppError::EntityNotFound("Specified user not found".into()))
+}
+
 #[utoipa::path(
     put,
     path = "/users/me/password",
     tag = "ユーザー",
     summary = "パスワード変更",
-    description = "ログイン中のユーザー自身のパスワードを変更します",
+    description = "ログイン中のユーザー自身のパスワードを変更します。リクエストに使ったセッション以外の端末からはログアウトされます",
     operation_id = "changePassword",
     request_body = UpdateUserPasswordRequest,
     responses(
@@ -206,6 +297,17 @@ pub async fn change_password(
         .update_password(UpdateUserPasswordRequestWithUserId::new(user.id(), req).into())
         .await?;
 
+    // 漏洩したパスワードでログインされていた場合に備えて、リクエストに使ったセッション以外を終了する
+    let auth_repository = registry.auth_repository();
+    match auth_repository.find_session_id(&user.access_token).await? {
+        Some(session_id) => {
+            auth_repository
+                .delete_other_sessions(user.id(), session_id)
+                .await?
+        }
+        None => auth_repository.delete_sessions(user.id()).await?,
+    }
+
     Ok(StatusCode::OK)
 }
 
@@ -235,3 +337,64 @@ pub async fn get_checkouts(
         .map(CheckoutsResponse::from)
         .map(Json)
 }
+
+#[utoipa::path(
+    get,
+    path = "/users/me/reservations",
+    tag = "ユーザー",
+    summary = "自分の予約一覧取得",
+    description = "ログイン中のユーザーの予約の一覧を取得します",
+    operation_id = "getMyReservations",
+    responses(
+        (status = 200, description = "自身の予約一覧の取得成功", body = ReservationsResponse),
+        (status = 401, description = "認証エラー"),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn get_reservations(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<ReservationsResponse>> {
+    registry
+        .reservation_repository()
+        .find_by_user_id(user.id())
+        .await
+        .map(ReservationsResponse::from)
+        .map(Json)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/users/{user_id}/login-lock",
+    tag = "ユーザー",
+    summary = "アカウントのロック解除",
+    description = "ログインの失敗が続いたことによる指定したユーザーのアカウントのロックを解除し、失敗回数をリセットします。管理者のみ実行可能です",
+    operation_id = "unlockUser",
+    params(
+        ("user_id" = String, Path, description = "ユーザーID")
+    ),
+    responses(
+        (status = 200, description = "ロック解除成功"),
+        (status = 401, description = "認証エラー"),
+        (status = 403, description = "権限エラー（管理者のみ）"),
+        (status = 404, description = "ユーザーが存在しない"),
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn unlock_user(
+    user: AuthorizedUser,
+    Path(user_id): Path<UserId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    if !user.is_admin() {
+        return Err(AppError::ForbiddenOperation);
+    }
+
+    registry.auth_repository().unlock_account(user_id).await?;
+
+    Ok(StatusCode::OK)
+}
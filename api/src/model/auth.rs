@@ -1,4 +1,4 @@
-use kernel::model::id::UserId;
+use kernel::model::{auth::AuthTokens, id::UserId};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
@@ -15,7 +15,7 @@ pub struct LoginRequest {
 }
 
 /// ログイン成功時のレスポンス
-#[derive(Serialize, ToSchema)]
+#[derive(Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct AccessTokenResponse {
     /// ログインしたユーザーのID
@@ -24,4 +24,31 @@ pub struct AccessTokenResponse {
     /// APIアクセスに使用するトークン。Authorization ヘッダーに Bearer {access_token} 形式で指定
     #[schema(example = "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9...")]
     pub access_token: String,
+    /// アクセストークンの再発行に使用するトークン。一度使うと無効になる
+    #[schema(example = "3f11016015344d64956c234d50589092.f128d8bbaa1d4753ab70f9cafd487038")]
+    pub refresh_token: String,
+}
+
+impl From<AuthTokens> for AccessTokenResponse {
+    fn from(value: AuthTokens) -> Self {
+        let AuthTokens {
+            user_id,
+            access_token,
+            refresh_token,
+        } = value;
+        Self {
+            user_id,
+            access_token: access_token.0,
+            refresh_token: refresh_token.0,
+        }
+    }
+}
+
+/// アクセストークンの再発行リクエスト
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshTokenRequest {
+    /// ログイン時または前回の再発行時に取得したリフレッシュトークン
+    #[schema(example = "3f11016015344d64956c234d50589092.f128d8bbaa1d4753ab70f9cafd487038")]
+    pub refresh_token: String,
 }
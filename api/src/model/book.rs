@@ -2,39 +2,64 @@ use chrono::{DateTime, Utc};
 use derive_new::new;
 use garde::Validate;
 use kernel::model::{
-    book::{Book, BookListOptions, Checkout, UpdateBook, event::CreateBook},
-    id::{BookId, CheckoutId, UserId},
-    list::PaginatedList,
+    book::{
+        Book, BookArchive, BookAvailability, BookCopy, BookImportDuplicate, BookImportError,
+        BookImportReport, BookListOptions, BookMetadata, BookOwnerTransfer, BookSortKey, Checkout,
+        ImportedBook, PatchBook, UpdateBook,
+        event::{CreateBook, CreateBookCopy, ImportBookRow, TransferBookOwner},
+    },
+    id::{BookCopyId, BookId, CheckoutId, TagId, UserId},
+    isbn::Isbn,
+    list::{Cursor, PaginatedList},
+    tag::TagFacet,
 };
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use shared::error::{AppError, AppResult};
 use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
-use crate::model::user::{BookOwner, CheckoutUser};
+use crate::model::{
+    cover::{cover_thumbnail_url, cover_url},
+    deserialize_patch, required_patch,
+    tag::{TagFacetResponse, TagResponse},
+    user::{BookOwner, CheckoutUser},
+};
 
 /// 蔵書登録リクエスト
-#[derive(Debug, Deserialize, Validate, ToSchema)]
+#[derive(Debug, Deserialize, Serialize, Validate, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateBookRequest {
     /// 書籍のタイトル
-    #[garde(length(min = 1))]
+    #[garde(length(chars, min = 1, max = 255))]
     #[schema(example = "The Rust Programming Language")]
     pub title: String,
 
     /// 著者名
-    #[garde(length(min = 1))]
+    #[garde(length(chars, min = 1, max = 255))]
     #[schema(example = "Steve Klabnik and Carol Nichols")]
     pub author: String,
 
-    /// ISBN（国際標準図書番号）
-    #[garde(length(min = 1))]
-    #[schema(example = "978-1593278281")]
-    pub isbn: String,
+    /// ISBN（国際標準図書番号）。ハイフンの有無を問わず ISBN-10 / ISBN-13 を受け付け、ISBN-13 に正規化して登録する
+    #[garde(skip)]
+    #[schema(value_type = String, example = "978-1593278281")]
+    pub isbn: Isbn,
 
     /// 書籍の説明・概要
-    #[garde(skip)]
+    #[garde(length(chars, max = 1024))]
     #[schema(example = "The official book on the Rust programming language")]
     pub description: String,
+
+    /// 蔵書と同時に登録する最初のコピーのバーコード（省略時は採番する）。採番するバーコードと同じ形式（BC と 8 桁の数字）は指定できない
+    #[garde(inner(length(chars, min = 1, max = 255), custom(validate_barcode)))]
+    #[serde(default)]
+    #[schema(example = "LIB-0001")]
+    pub barcode: Option<String>,
+
+    /// 蔵書に付けるタグのID
+    #[garde(skip)]
+    #[serde(default)]
+    #[schema(value_type = Vec<String>, example = json!(["550e8400-e29b-41d4-a716-446655440000"]))]
+    pub tags: Vec<TagId>,
 }
 
 impl From<CreateBookRequest> for CreateBook {
@@ -44,6 +69,8 @@ impl From<CreateBookRequest> for CreateBook {
             author,
             isbn,
             description,
+            barcode,
+            tags,
         } = value;
 
         Self {
@@ -51,6 +78,120 @@ impl From<CreateBookRequest> for CreateBook {
             author,
             isbn,
             description,
+            barcode,
+            tags,
+        }
+    }
+}
+
+impl From<BookMetadata> for CreateBookRequest {
+    fn from(value: BookMetadata) -> Self {
+        let BookMetadata {
+            isbn,
+            title,
+            author,
+            description,
+        } = value;
+
+        Self {
+            title,
+            author,
+            isbn,
+            description,
+            barcode: None,
+            tags: Vec::new(),
+        }
+    }
+}
+
+/// 書誌情報検索のクエリパラメータ
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(rename_all = "camelCase")]
+pub struct BookLookupQuery {
+    /// ISBN（ISBN-10 / ISBN-13 のどちらでも、ハイフンの有無を問わず指定できる）
+    #[param(value_type = String, example = "978-1593278281")]
+    pub isbn: Isbn,
+}
+
+/// 蔵書削除（アーカイブ）のクエリパラメータ
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(rename_all = "camelCase")]
+pub struct BookDeleteQuery {
+    /// アーカイブする理由
+    #[garde(inner(length(chars, min = 1, max = 255)))]
+    #[param(example = "破損のため廃棄")]
+    pub reason: Option<String>,
+}
+
+/// 蔵書のコピー登録リクエスト
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateBookCopyRequest {
+    /// コピーのバーコード（省略時は採番する）。採番するバーコードと同じ形式（BC と 8 桁の数字）は指定できない
+    #[garde(inner(length(chars, min = 1, max = 255), custom(validate_barcode)))]
+    #[schema(example = "LIB-0002")]
+    pub barcode: Option<String>,
+}
+
+// 採番するバーコード（BC と 8 桁の数字）と同じ形式のバーコードを指定すると、
+// 後で採番したバーコードと重複して登録できなくなるため受け付けない
+fn validate_barcode(barcode: &str, _: &()) -> garde::Result {
+    let generated = barcode
+        .strip_prefix("BC")
+        .is_some_and(|x| x.len() == 8 && x.bytes().all(|b| b.is_ascii_digit()));
+    if generated {
+        return Err(garde::Error::new(
+            "採番するバーコード（BC と 8 桁の数字）と同じ形式のバーコードは指定できません",
+        ));
+    }
+    Ok(())
+}
+
+#[derive(new)]
+pub struct CreateBookCopyRequestWithIds(BookId, UserId, CreateBookCopyRequest);
+
+impl From<CreateBookCopyRequestWithIds> for CreateBookCopy {
+    fn from(value: CreateBookCopyRequestWithIds) -> Self {
+        let CreateBookCopyRequestWithIds(book_id, user_id, CreateBookCopyRequest { barcode }) =
+            value;
+
+        Self {
+            book_id,
+            barcode,
+            requested_user: user_id,
+        }
+    }
+}
+
+/// 蔵書の所有者変更リクエスト
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateBookOwnerRequest {
+    /// 新しい所有者のユーザーID
+    #[garde(skip)]
+    #[schema(value_type = String, example = "550e8400-e29b-41d4-a716-446655440000")]
+    pub owner_id: UserId,
+}
+
+#[derive(new)]
+pub struct UpdateBookOwnerRequestWithIds(BookId, UserId, bool, UpdateBookOwnerRequest);
+
+impl From<UpdateBookOwnerRequestWithIds> for TransferBookOwner {
+    fn from(value: UpdateBookOwnerRequestWithIds) -> Self {
+        let UpdateBookOwnerRequestWithIds(
+            book_id,
+            user_id,
+            is_admin,
+            UpdateBookOwnerRequest { owner_id },
+        ) = value;
+
+        Self {
+            book_id,
+            new_owner: owner_id,
+            requested_user: user_id,
+            is_admin,
         }
     }
 }
@@ -60,30 +201,35 @@ impl From<CreateBookRequest> for CreateBook {
 #[serde(rename_all = "camelCase")]
 pub struct UpdateBookRequest {
     /// 書籍のタイトル
-    #[garde(length(min = 1))]
+    #[garde(length(chars, min = 1, max = 255))]
     #[schema(example = "The Rust Programming Language 2nd Edition")]
     pub title: String,
 
     /// 著者名
-    #[garde(length(min = 1))]
+    #[garde(length(chars, min = 1, max = 255))]
     #[schema(example = "Steve Klabnik and Carol Nichols")]
     pub author: String,
 
-    /// ISBN（国際標準図書番号）
-    #[garde(length(min = 1))]
-    #[schema(example = "978-1718503106")]
-    pub isbn: String,
+    /// ISBN（国際標準図書番号）。ハイフンの有無を問わず ISBN-10 / ISBN-13 を受け付け、ISBN-13 に正規化して登録する
+    #[garde(skip)]
+    #[schema(value_type = String, example = "978-1718503106")]
+    pub isbn: Isbn,
 
     /// 書籍の説明・概要
-    #[garde(skip)]
+    #[garde(length(chars, max = 1024))]
     #[schema(
         example = "The official book on the Rust programming language, updated for Rust 2024"
     )]
     pub description: String,
+
+    /// 蔵書に付けるタグのID（指定した場合は付いているタグをすべて置き換え、省略した場合は変更しない）
+    #[garde(skip)]
+    #[schema(value_type = Option<Vec<String>>, example = json!(["550e8400-e29b-41d4-a716-446655440000"]))]
+    pub tags: Option<Vec<TagId>>,
 }
 
 #[derive(new)]
-pub struct UpdateBookRequestWithIds(BookId, UserId, bool, UpdateBookRequest);
+pub struct UpdateBookRequestWithIds(BookId, UserId, bool, Option<i64>, UpdateBookRequest);
 
 impl From<UpdateBookRequestWithIds> for UpdateBook {
     fn from(value: UpdateBookRequestWithIds) -> Self {
@@ -91,11 +237,13 @@ impl From<UpdateBookRequestWithIds> for UpdateBook {
             book_id,
             user_id,
             is_admin,
+            expected_version,
             UpdateBookRequest {
                 title,
                 author,
                 isbn,
                 description,
+                tags,
             },
         ) = value;
 
@@ -105,17 +253,93 @@ impl From<UpdateBookRequestWithIds> for UpdateBook {
             author,
             isbn,
             description,
+            tags,
             requested_user: user_id,
             is_admin,
+            expected_version,
         }
     }
 }
 
+/// 蔵書の部分更新リクエスト（JSON Merge Patch）
+///
+/// 指定した項目だけを更新し、省略した項目は変更しない
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchBookRequest {
+    /// 書籍のタイトル（null は指定できない）
+    #[serde(default, deserialize_with = "deserialize_patch")]
+    #[garde(length(chars, min = 1, max = 255))]
+    #[schema(value_type = Option<String>, example = "The Rust Programming Language 2nd Edition")]
+    pub title: Option<Option<String>>,
+
+    /// 著者名（null は指定できない）
+    #[serde(default, deserialize_with = "deserialize_patch")]
+    #[garde(length(chars, min = 1, max = 255))]
+    #[schema(value_type = Option<String>, example = "Steve Klabnik and Carol Nichols")]
+    pub author: Option<Option<String>>,
+
+    /// ISBN（null は指定できない）。ハイフンの有無を問わず ISBN-10 / ISBN-13 を受け付け、ISBN-13 に正規化して登録する
+    #[serde(default, deserialize_with = "deserialize_patch")]
+    #[garde(skip)]
+    #[schema(value_type = Option<String>, example = "978-1718503106")]
+    pub isbn: Option<Option<Isbn>>,
+
+    /// 書籍の説明・概要（null を指定した場合は空にする）
+    #[serde(default, deserialize_with = "deserialize_patch")]
+    #[garde(length(chars, max = 1024))]
+    #[schema(value_type = Option<String>, example = "Updated for Rust 2024")]
+    pub description: Option<Option<String>>,
+
+    /// 蔵書に付けるタグのID（指定した場合は付いているタグをすべて置き換え、null を指定した場合はすべて外す）
+    #[serde(default, deserialize_with = "deserialize_patch")]
+    #[garde(skip)]
+    #[schema(value_type = Option<Vec<String>>, example = json!(["550e8400-e29b-41d4-a716-446655440000"]))]
+    pub tags: Option<Option<Vec<TagId>>>,
+}
+
+#[derive(new)]
+pub struct PatchBookRequestWithIds(BookId, UserId, bool, Option<i64>, PatchBookRequest);
+
+impl TryFrom<PatchBookRequestWithIds> for PatchBook {
+    type Error = AppError;
+
+    fn try_from(value: PatchBookRequestWithIds) -> AppResult<Self> {
+        let PatchBookRequestWithIds(
+            book_id,
+            user_id,
+            is_admin,
+            expected_version,
+            PatchBookRequest {
+                title,
+                author,
+                isbn,
+                description,
+                tags,
+            },
+        ) = value;
+
+        Ok(Self {
+            book_id,
+            title: required_patch("title", title)?,
+            author: required_patch("author", author)?,
+            isbn: required_patch("isbn", isbn)?,
+            description: description.map(Option::unwrap_or_default),
+            tags: tags.map(Option::unwrap_or_default),
+            expected_version,
+            requested_user: user_id,
+            is_admin,
+        })
+    }
+}
+
 /// 蔵書一覧取得のクエリパラメータ
 #[derive(Debug, Deserialize, Validate, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(rename_all = "camelCase")]
 pub struct BookListQuery {
-    /// 取得件数の上限（デフォルト: 20）
-    #[garde(range(min = 0))]
+    /// 取得件数の上限（デフォルト: 20、最大: 100）
+    #[garde(range(min = 0, max = MAX_LIMIT))]
     #[serde(default = "default_limit")]
     #[param(example = 20)]
     pub limit: i64,
@@ -125,17 +349,155 @@ pub struct BookListQuery {
     #[serde(default)]
     #[param(example = 0)]
     pub offset: i64,
+
+    /// 検索語（タイトル・著者名・説明・ISBN を対象に、全文検索と部分一致・あいまい検索を行う）
+    #[garde(inner(length(min = 1, max = 255)))]
+    #[param(example = "Rust")]
+    pub q: Option<String>,
+
+    /// 貸出状況で絞り込む
+    #[garde(skip)]
+    pub availability: Option<BookAvailabilityQuery>,
+
+    /// 所有者（登録者）のユーザーIDで絞り込む
+    #[garde(skip)]
+    #[param(value_type = Option<String>, example = "550e8400-e29b-41d4-a716-446655440000")]
+    pub owner_id: Option<UserId>,
+
+    /// 著者名（部分一致）で絞り込む
+    #[garde(inner(length(min = 1, max = 255)))]
+    #[param(example = "Steve Klabnik")]
+    pub author: Option<String>,
+
+    /// ISBN で絞り込む（ISBN-10 / ISBN-13 のどちらでも、ハイフンの有無を問わず指定できる）
+    #[garde(skip)]
+    #[param(value_type = Option<String>, example = "1-59327-828-4")]
+    pub isbn: Option<Isbn>,
+
+    /// 並び順（デフォルト: createdAtDesc）
+    #[garde(skip)]
+    #[serde(default)]
+    pub sort: BookSortQuery,
+
+    /// 前のページのレスポンスに含まれる nextCursor。指定した場合は offset の代わりにカーソルの位置から取得する（並び順が createdAtDesc / createdAtAsc の場合のみ）
+    #[garde(skip)]
+    #[param(value_type = Option<String>)]
+    pub cursor: Option<Cursor>,
+
+    /// タグのIDをカンマ区切りで指定し、指定したすべてのタグが付いている蔵書に絞り込む
+    #[garde(skip)]
+    #[serde(default, deserialize_with = "deserialize_tag_ids")]
+    #[param(value_type = Option<String>, example = "550e8400-e29b-41d4-a716-446655440000,6ba7b810-9dad-11d1-80b4-00c04fd430c8")]
+    pub tags: Vec<TagId>,
+
+    /// true の場合はアーカイブ済みの蔵書も含める（管理者のみ指定可能、デフォルト: false）
+    #[garde(skip)]
+    #[serde(default)]
+    #[param(example = false)]
+    pub include_archived: bool,
+}
+
+// クエリパラメータのカンマ区切りのタグ ID を読み込む
+fn deserialize_tag_ids<'de, D>(deserializer: D) -> Result<Vec<TagId>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    s.split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| id.parse().map_err(serde::de::Error::custom))
+        .collect()
 }
 
 const DEFAULT_LIMIT: i64 = 20;
+const MAX_LIMIT: i64 = 100;
 const fn default_limit() -> i64 {
     DEFAULT_LIMIT
 }
 
+/// 蔵書の貸出状況
+#[derive(Debug, Clone, Copy, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum BookAvailabilityQuery {
+    /// 貸出可能
+    Available,
+    /// 貸出中
+    CheckedOut,
+}
+
+impl From<BookAvailabilityQuery> for BookAvailability {
+    fn from(value: BookAvailabilityQuery) -> Self {
+        match value {
+            BookAvailabilityQuery::Available => Self::Available,
+            BookAvailabilityQuery::CheckedOut => Self::CheckedOut,
+        }
+    }
+}
+
+/// 蔵書一覧の並び順
+#[derive(Debug, Default, Clone, Copy, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum BookSortQuery {
+    /// 登録日時の新しい順
+    #[default]
+    CreatedAtDesc,
+    /// 登録日時の古い順
+    CreatedAtAsc,
+    /// タイトルの昇順
+    TitleAsc,
+    /// タイトルの降順
+    TitleDesc,
+    /// 著者名の昇順
+    AuthorAsc,
+    /// 著者名の降順
+    AuthorDesc,
+    /// 検索語との関連度の高い順（検索語がない場合は登録日時の新しい順）
+    Relevance,
+}
+
+impl From<BookSortQuery> for BookSortKey {
+    fn from(value: BookSortQuery) -> Self {
+        match value {
+            BookSortQuery::CreatedAtDesc => Self::CreatedAtDesc,
+            BookSortQuery::CreatedAtAsc => Self::CreatedAtAsc,
+            BookSortQuery::TitleAsc => Self::TitleAsc,
+            BookSortQuery::TitleDesc => Self::TitleDesc,
+            BookSortQuery::AuthorAsc => Self::AuthorAsc,
+            BookSortQuery::AuthorDesc => Self::AuthorDesc,
+            BookSortQuery::Relevance => Self::Relevance,
+        }
+    }
+}
+
 impl From<BookListQuery> for BookListOptions {
     fn from(value: BookListQuery) -> Self {
-        let BookListQuery { limit, offset } = value;
-        Self { limit, offset }
+        let BookListQuery {
+            limit,
+            offset,
+            q,
+            availability,
+            owner_id,
+            author,
+            isbn,
+            sort,
+            cursor,
+            tags,
+            include_archived,
+        } = value;
+        Self {
+            limit,
+            offset,
+            query: q,
+            availability: availability.map(BookAvailability::from),
+            owner: owner_id,
+            author,
+            isbn,
+            sort: sort.into(),
+            cursor,
+            tags,
+            include_archived,
+        }
     }
 }
 
@@ -151,6 +513,9 @@ pub struct BookCheckoutResponse {
     /// 貸出日時
     #[schema(example = "2024-01-15T10:30:00Z")]
     pub checked_out_at: DateTime<Utc>,
+    /// 返却期限
+    #[schema(example = "2024-01-29T10:30:00Z")]
+    pub due_at: DateTime<Utc>,
 }
 
 impl From<Checkout> for BookCheckoutResponse {
@@ -159,11 +524,13 @@ impl From<Checkout> for BookCheckoutResponse {
             checkout_id,
             checked_out_by,
             checked_out_at,
+            due_at,
         } = value;
         Self {
             id: checkout_id,
             checked_out_by: checked_out_by.into(),
             checked_out_at,
+            due_at,
         }
     }
 }
@@ -181,20 +548,49 @@ pub struct BookResponse {
     /// 著者名
     #[schema(example = "Steve Klabnik and Carol Nichols")]
     pub author: String,
-    /// ISBN（国際標準図書番号）
-    #[schema(example = "978-1593278281")]
-    pub isbn: String,
+    /// ISBN（国際標準図書番号、ハイフンなしの ISBN-13）
+    #[schema(value_type = String, example = "9781593278281")]
+    pub isbn: Isbn,
     /// 書籍の説明・概要
     #[schema(example = "The official book on the Rust programming language")]
     pub description: String,
     /// 蔵書の所有者（登録者）
     pub owner: BookOwner,
-    /// 現在の貸出情報（貸出中でなければnull）
-    pub checkout: Option<BookCheckoutResponse>,
+    /// コピーの総数
+    #[schema(example = 2)]
+    pub total_copies: usize,
+    /// 貸出中でないコピーの冊数
+    #[schema(example = 1)]
+    pub available_copies: usize,
+    /// コピーの一覧
+    pub copies: Vec<BookCopyResponse>,
+    /// 蔵書に付いているタグ（タグ名の昇順）
+    pub tags: Vec<TagResponse>,
+    /// アーカイブした日時（アーカイブ済みでなければnull）
+    #[schema(example = "2024-03-01T09:00:00Z")]
+    pub archived_at: Option<DateTime<Utc>>,
+    /// アーカイブした理由
+    #[schema(example = "破損のため廃棄")]
+    pub archive_reason: Option<String>,
+    /// 蔵書のバージョン。更新のたびに増え、ETag ヘッダの値と一致する
+    #[schema(example = 1)]
+    pub version: i64,
+    /// 書影の画像の URL（書影が登録されていなければnull）
+    #[schema(
+        example = "/api/v1/books/550e8400-e29b-41d4-a716-446655440000/cover?v=0b5f1f3c2d8e4a7b9c6d5e4f3a2b1c0d"
+    )]
+    pub cover_url: Option<String>,
+    /// 書影のサムネイル（PNG）の URL（書影が登録されていなければnull）
+    #[schema(
+        example = "/api/v1/books/550e8400-e29b-41d4-a716-446655440000/cover/thumbnail?v=0b5f1f3c2d8e4a7b9c6d5e4f3a2b1c0d"
+    )]
+    pub cover_thumbnail_url: Option<String>,
 }
 
 impl From<Book> for BookResponse {
     fn from(value: Book) -> Self {
+        let total_copies = value.total_copies();
+        let available_copies = value.available_copies();
         let Book {
             id,
             title,
@@ -202,8 +598,19 @@ impl From<Book> for BookResponse {
             isbn,
             description,
             owner,
-            checkout,
+            copies,
+            tags,
+            archive,
+            version,
+            cover,
         } = value;
+        let (archived_at, archive_reason) = match archive {
+            Some(BookArchive {
+                archived_at,
+                reason,
+            }) => (Some(archived_at), reason),
+            None => (None, None),
+        };
 
         Self {
             id,
@@ -212,6 +619,100 @@ impl From<Book> for BookResponse {
             isbn,
             description,
             owner: owner.into(),
+            total_copies,
+            available_copies,
+            copies: copies.into_iter().map(BookCopyResponse::from).collect(),
+            tags: tags.into_iter().map(TagResponse::from).collect(),
+            archived_at,
+            archive_reason,
+            version,
+            cover_url: cover.as_ref().map(|cover| cover_url(id, cover)),
+            cover_thumbnail_url: cover.as_ref().map(|cover| cover_thumbnail_url(id, cover)),
+        }
+    }
+}
+
+/// 蔵書の所有者の変更履歴
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BookOwnerTransferResponse {
+    /// 変更前の所有者（削除されたユーザーは nil UUID と「削除されたユーザー」になる）
+    pub from: BookOwner,
+    /// 変更後の所有者
+    pub to: BookOwner,
+    /// 変更を実行したユーザー（所有者本人か管理者）
+    pub transferred_by: BookOwner,
+    /// 変更日時
+    #[schema(example = "2024-03-01T09:00:00Z")]
+    pub transferred_at: DateTime<Utc>,
+}
+
+impl From<BookOwnerTransfer> for BookOwnerTransferResponse {
+    fn from(value: BookOwnerTransfer) -> Self {
+        let BookOwnerTransfer {
+            from,
+            to,
+            transferred_by,
+            transferred_at,
+        } = value;
+
+        Self {
+            from: from.into(),
+            to: to.into(),
+            transferred_by: transferred_by.into(),
+            transferred_at,
+        }
+    }
+}
+
+/// 蔵書の所有者の変更履歴一覧レスポンス
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BookOwnerTransfersResponse {
+    /// 変更履歴（変更日時の古い順）
+    pub items: Vec<BookOwnerTransferResponse>,
+}
+
+impl From<Vec<BookOwnerTransfer>> for BookOwnerTransfersResponse {
+    fn from(value: Vec<BookOwnerTransfer>) -> Self {
+        Self {
+            items: value
+                .into_iter()
+                .map(BookOwnerTransferResponse::from)
+                .collect(),
+        }
+    }
+}
+
+/// 蔵書のコピー（現物）
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BookCopyResponse {
+    /// コピーID
+    #[schema(value_type = String, example = "550e8400-e29b-41d4-a716-446655440000")]
+    pub id: BookCopyId,
+    /// バーコード
+    #[schema(example = "BC00000001")]
+    pub barcode: String,
+    /// コピーの所有者
+    pub owner: BookOwner,
+    /// 現在の貸出情報（貸出中でなければnull）
+    pub checkout: Option<BookCheckoutResponse>,
+}
+
+impl From<BookCopy> for BookCopyResponse {
+    fn from(value: BookCopy) -> Self {
+        let BookCopy {
+            id,
+            barcode,
+            owner,
+            checkout,
+        } = value;
+
+        Self {
+            id,
+            barcode,
+            owner: owner.into(),
             checkout: checkout.map(BookCheckoutResponse::from),
         }
     }
@@ -232,22 +733,382 @@ pub struct PaginatedBookResponse {
     pub offset: i64,
     /// 蔵書一覧
     pub items: Vec<BookResponse>,
+    /// 次のページを取得するためのカーソル（次のページがない場合や、登録日時以外の並び順の場合はnull）
+    #[schema(value_type = Option<String>, example = "00063e7c1b9a4c00550e8400e29b41d4a716446655440000")]
+    pub next_cursor: Option<Cursor>,
+    /// 検索条件に当てはまる蔵書に付いているタグと、タグごとの冊数（冊数の多い順）
+    pub tag_facets: Vec<TagFacetResponse>,
 }
 
-impl From<PaginatedList<Book>> for PaginatedBookResponse {
-    fn from(value: PaginatedList<Book>) -> Self {
+impl PaginatedBookResponse {
+    pub fn new(list: PaginatedList<Book>, tag_facets: Vec<TagFacet>) -> Self {
         let PaginatedList {
             items,
             total,
             limit,
             offset,
-        } = value;
+            next_cursor,
+        } = list;
 
         Self {
             total,
             limit,
             offset,
             items: items.into_iter().map(BookResponse::from).collect(),
+            next_cursor,
+            tag_facets: tag_facets.into_iter().map(TagFacetResponse::from).collect(),
+        }
+    }
+}
+
+/// 蔵書一括登録のクエリパラメータ
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(rename_all = "camelCase")]
+pub struct BookImportQuery {
+    /// 取り込むデータの形式
+    pub format: BookImportFormat,
+
+    /// true の場合は登録を確定せず、登録した場合の結果だけを返す（デフォルト: false）
+    #[serde(default)]
+    #[param(example = false)]
+    pub dry_run: bool,
+}
+
+/// 蔵書一括登録で取り込むデータの形式
+#[derive(Debug, Clone, Copy, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum BookImportFormat {
+    /// ヘッダー行付きの CSV（列: title, author, isbn, description, barcode。barcode は省略可能）
+    Csv,
+    /// 1 行に 1 件の蔵書登録リクエストを JSON で記述した JSON Lines
+    Jsonl,
+}
+
+impl BookImportFormat {
+    // 各行を蔵書登録リクエストとして読み込み、蔵書登録と同じ規則で検証する
+    // 読み込めなかった行と検証に失敗した行は、行番号付きのエラーとして返す
+    pub fn parse(self, body: &str) -> (Vec<ImportBookRow>, Vec<BookImportError>) {
+        let results = match self {
+            Self::Csv => parse_csv(body),
+            Self::Jsonl => body
+                .lines()
+                .enumerate()
+                .filter(|(_, line)| !line.trim().is_empty())
+                .map(|(i, line)| (i + 1, serde_json::from_str(line).map_err(|e| e.to_string())))
+                .collect(),
+        };
+
+        let mut rows = Vec::new();
+        let mut errors = Vec::new();
+        for (line, res) in results {
+            match res.and_then(|req| req.validate().map(|_| req).map_err(|e| e.to_string())) {
+                Ok(req) => rows.push(ImportBookRow {
+                    line,
+                    book: req.into(),
+                }),
+                Err(message) => errors.push(BookImportError { line, message }),
+            }
+        }
+        (rows, errors)
+    }
+}
+
+fn parse_csv(body: &str) -> Vec<(usize, Result<CreateBookRequest, String>)> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(body.as_bytes());
+    let headers = match reader.headers() {
+        Ok(headers) => headers.clone(),
+        Err(e) => return vec![(1, Err(e.to_string()))],
+    };
+    reader
+        .records()
+        .map(|res| {
+            let record = res.map_err(|e| {
+                let line = e.position().map(|p| p.line()).unwrap_or_default();
+                (line as usize, e.to_string())
+            })?;
+            let line = record.position().map(|p| p.line()).unwrap_or_default() as usize;
+            record
+                .deserialize(Some(&headers))
+                .map(|req| (line, Ok(req)))
+                .map_err(|e| match e.kind() {
+                    csv::ErrorKind::Deserialize { err, .. } => (line, err.to_string()),
+                    _ => (line, e.to_string()),
+                })
+        })
+        .map(|res| res.unwrap_or_else(|(line, message)| (line, Err(message))))
+        .collect()
+}
+
+/// 蔵書一括登録の結果
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BookImportResponse {
+    /// ドライランかどうか
+    pub dry_run: bool,
+    /// 登録した行（ドライランの場合は登録できる行）
+    pub imported: Vec<ImportedBookResponse>,
+    /// ISBN が重複しているため登録しなかった行
+    pub duplicates: Vec<BookImportDuplicateResponse>,
+    /// 読み込みや検証に失敗したため登録しなかった行
+    pub errors: Vec<BookImportErrorResponse>,
+}
+
+impl BookImportResponse {
+    // 取り込み前に失敗した行のエラーと、登録時の結果をあわせて行番号順に並べる
+    pub fn new(dry_run: bool, report: BookImportReport, errors: Vec<BookImportError>) -> Self {
+        let BookImportReport {
+            imported,
+            duplicates,
+            errors: import_errors,
+        } = report;
+        let mut errors: Vec<BookImportErrorResponse> = errors
+            .into_iter()
+            .chain(import_errors)
+            .map(BookImportErrorResponse::from)
+            .collect();
+        errors.sort_by_key(|e| e.line);
+
+        Self {
+            dry_run,
+            imported: imported
+                .into_iter()
+                .map(ImportedBookResponse::from)
+                .collect(),
+            duplicates: duplicates
+                .into_iter()
+                .map(BookImportDuplicateResponse::from)
+                .collect(),
+            errors,
         }
     }
 }
+
+/// 一括登録で登録した行
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedBookResponse {
+    /// 行番号（1始まり）
+    #[schema(example = 2)]
+    pub line: usize,
+    /// 登録した蔵書のID（ドライランの場合はnull）
+    #[schema(value_type = Option<String>, example = "550e8400-e29b-41d4-a716-446655440000")]
+    pub book_id: Option<BookId>,
+}
+
+impl From<ImportedBook> for ImportedBookResponse {
+    fn from(value: ImportedBook) -> Self {
+        let ImportedBook { line, book_id } = value;
+        Self { line, book_id }
+    }
+}
+
+/// 一括登録で ISBN が重複していた行
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BookImportDuplicateResponse {
+    /// 行番号（1始まり）
+    #[schema(example = 3)]
+    pub line: usize,
+    /// 重複していた ISBN（ISBN-13 に正規化したもの）
+    #[schema(value_type = String, example = "9781593278281")]
+    pub isbn: Isbn,
+    /// 同じ ISBN で登録済みの蔵書のID
+    #[schema(value_type = Option<String>, example = "550e8400-e29b-41d4-a716-446655440000")]
+    pub existing_book_id: Option<BookId>,
+    /// 同じ ISBN が前に現れた行の行番号
+    #[schema(example = 2)]
+    pub duplicate_of_line: Option<usize>,
+}
+
+impl From<BookImportDuplicate> for BookImportDuplicateResponse {
+    fn from(value: BookImportDuplicate) -> Self {
+        let BookImportDuplicate {
+            line,
+            isbn,
+            existing_book_id,
+            duplicate_of_line,
+        } = value;
+        Self {
+            line,
+            isbn,
+            existing_book_id,
+            duplicate_of_line,
+        }
+    }
+}
+
+/// 一括登録で失敗した行
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BookImportErrorResponse {
+    /// 行番号（1始まり）
+    #[schema(example = 4)]
+    pub line: usize,
+    /// 失敗した理由
+    #[schema(example = "ISBN（978-0000000000）が不正です。")]
+    pub message: String,
+}
+
+impl From<BookImportError> for BookImportErrorResponse {
+    fn from(value: BookImportError) -> Self {
+        let BookImportError { line, message } = value;
+        Self { line, message }
+    }
+}
+
+/// 蔵書エクスポートのクエリパラメータ
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(rename_all = "camelCase")]
+pub struct BookExportQuery {
+    /// 出力する形式
+    pub format: BookExportFormat,
+}
+
+/// 蔵書エクスポートの出力形式
+#[derive(Debug, Clone, Copy, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum BookExportFormat {
+    /// ヘッダー行付きの CSV。蔵書一括登録にそのまま取り込める
+    Csv,
+    /// 1 行に 1 件の蔵書詳細を JSON で記述した JSON Lines
+    Jsonl,
+    /// BibTeX（@book エントリ）
+    Bibtex,
+}
+
+impl BookExportFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Jsonl => "application/jsonl",
+            Self::Bibtex => "application/x-bibtex; charset=utf-8",
+        }
+    }
+
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Self::Csv => "books.csv",
+            Self::Jsonl => "books.jsonl",
+            Self::Bibtex => "books.bib",
+        }
+    }
+
+    // 蔵書より前に出力する内容（CSV のヘッダー行）
+    pub fn header(self) -> String {
+        match self {
+            Self::Csv => BOOK_EXPORT_CSV_HEADER.join(",") + "\n",
+            Self::Jsonl | Self::Bibtex => String::new(),
+        }
+    }
+
+    // 蔵書 1 件分を出力する内容に変換する
+    pub fn encode(self, book: Book) -> AppResult<String> {
+        match self {
+            Self::Csv => encode_csv(book),
+            Self::Jsonl => serde_json::to_string(&BookResponse::from(book))
+                .map(|json| json + "\n")
+                .map_err(|e| AppError::ConversionEntityError(e.to_string())),
+            Self::Bibtex => Ok(encode_bibtex(book)),
+        }
+    }
+}
+
+const BOOK_EXPORT_CSV_HEADER: [&str; 11] = [
+    "id",
+    "title",
+    "author",
+    "isbn",
+    "description",
+    "ownerId",
+    "ownerName",
+    "totalCopies",
+    "availableCopies",
+    "checkedOutBy",
+    "nextDueAt",
+];
+
+fn encode_csv(book: Book) -> AppResult<String> {
+    let total_copies = book.total_copies();
+    let available_copies = book.available_copies();
+    let checkouts = book
+        .copies
+        .iter()
+        .filter_map(|c| c.checkout.as_ref())
+        .collect::<Vec<_>>();
+    // 貸出中のコピーを借りているユーザー名（複数の場合は ; 区切り）と、最も近い返却期限
+    let checked_out_by = checkouts
+        .iter()
+        .map(|c| c.checked_out_by.name.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+    let next_due_at = checkouts
+        .iter()
+        .map(|c| c.due_at)
+        .min()
+        .map(|d| d.to_rfc3339())
+        .unwrap_or_default();
+
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(false)
+        .from_writer(Vec::new());
+    writer
+        .write_record([
+            book.id.to_string(),
+            book.title,
+            book.author,
+            book.isbn.to_string(),
+            book.description,
+            book.owner.id.to_string(),
+            book.owner.name,
+            total_copies.to_string(),
+            available_copies.to_string(),
+            checked_out_by,
+            next_due_at,
+        ])
+        .map_err(|e| AppError::ConversionEntityError(e.to_string()))?;
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| AppError::ConversionEntityError(e.to_string()))?;
+    String::from_utf8(bytes).map_err(|e| AppError::ConversionEntityError(e.to_string()))
+}
+
+fn encode_bibtex(book: Book) -> String {
+    let fields = [
+        ("title", book.title.clone()),
+        ("author", book.author.clone()),
+        ("isbn", book.isbn.to_string()),
+        ("abstract", book.description.clone()),
+        ("owner", book.owner.name.clone()),
+        ("copies", book.total_copies().to_string()),
+        ("available", book.available_copies().to_string()),
+    ];
+    let body = fields
+        .iter()
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(name, value)| format!("  {name} = {{{}}}", escape_bibtex(value)))
+        .collect::<Vec<_>>()
+        .join(",\n");
+    format!("@book{{{},\n{body}\n}}\n\n", book.id.simple())
+}
+
+// BibTeX の値として特別な意味を持つ文字をエスケープする
+fn escape_bibtex(s: &str) -> String {
+    s.chars()
+        .fold(String::with_capacity(s.len()), |mut out, c| {
+            match c {
+                '\\' => out.push_str("\\textbackslash{}"),
+                '{' | '}' | '%' | '&' | '$' | '#' | '_' => {
+                    out.push('\\');
+                    out.push(c);
+                }
+                '~' => out.push_str("\\textasciitilde{}"),
+                '^' => out.push_str("\\textasciicircum{}"),
+                c => out.push(c),
+            }
+            out
+        })
+}
@@ -1,25 +1,85 @@
 use chrono::{DateTime, Utc};
+use garde::Validate;
 use kernel::model::{
-    checkout::{Checkout, CheckoutBook},
-    id::{BookId, CheckoutId},
+    checkout::{Checkout, CheckoutBook, OverdueCheckout},
+    id::{BookCopyId, BookId, CheckoutId, UserId},
+    list::{Cursor, CursorListOptions, CursorPaginatedList},
 };
-use serde::Serialize;
-use utoipa::ToSchema;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
 use crate::model::user::CheckoutUser;
 
+/// 蔵書貸出リクエスト（本文は省略可能）
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckoutBookRequest {
+    /// 代理で貸し出す場合の借りるユーザーのID。管理者のみ指定でき、省略時はログイン中のユーザーが借りる
+    #[schema(value_type = Option<String>, example = "550e8400-e29b-41d4-a716-446655440000")]
+    pub user_id: Option<UserId>,
+    /// 借りるコピーのID。省略時は貸出中でないコピーから自動で選ぶ
+    #[schema(value_type = Option<String>, example = "550e8400-e29b-41d4-a716-446655440000")]
+    pub copy_id: Option<BookCopyId>,
+}
+
+/// 貸出一覧取得のクエリパラメータ
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(rename_all = "camelCase")]
+pub struct CheckoutListQuery {
+    /// 取得件数の上限（デフォルト: 20、最大: 100）
+    #[garde(range(min = 0, max = MAX_LIMIT))]
+    #[serde(default = "default_limit")]
+    #[param(example = 20)]
+    pub limit: i64,
+
+    /// 前のページのレスポンスに含まれる nextCursor（省略時は先頭から取得）
+    #[garde(skip)]
+    #[param(value_type = Option<String>)]
+    pub cursor: Option<Cursor>,
+}
+
+const DEFAULT_LIMIT: i64 = 20;
+const MAX_LIMIT: i64 = 100;
+const fn default_limit() -> i64 {
+    DEFAULT_LIMIT
+}
+
+impl From<CheckoutListQuery> for CursorListOptions {
+    fn from(value: CheckoutListQuery) -> Self {
+        let CheckoutListQuery { limit, cursor } = value;
+        Self { limit, cursor }
+    }
+}
+
 /// 貸出一覧レスポンス
 #[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CheckoutsResponse {
     /// 貸出情報一覧
     pub items: Vec<CheckoutResponse>,
+    /// 次のページを取得するためのカーソル（次のページがない場合はnull）
+    #[schema(value_type = Option<String>, example = "00063e7c1b9a4c00550e8400e29b41d4a716446655440000")]
+    pub next_cursor: Option<Cursor>,
 }
 
 impl From<Vec<Checkout>> for CheckoutsResponse {
     fn from(value: Vec<Checkout>) -> Self {
         Self {
             items: value.into_iter().map(CheckoutResponse::from).collect(),
+            next_cursor: None,
+        }
+    }
+}
+
+impl From<CursorPaginatedList<Checkout>> for CheckoutsResponse {
+    fn from(value: CursorPaginatedList<Checkout>) -> Self {
+        let CursorPaginatedList {
+            items, next_cursor, ..
+        } = value;
+        Self {
+            items: items.into_iter().map(CheckoutResponse::from).collect(),
+            next_cursor,
         }
     }
 }
@@ -36,9 +96,18 @@ pub struct CheckoutResponse {
     /// 貸出日時
     #[schema(example = "2024-01-15T10:30:00Z")]
     pub checked_out_at: DateTime<Utc>,
+    /// 返却期限
+    #[schema(example = "2024-01-29T10:30:00Z")]
+    pub due_at: DateTime<Utc>,
     /// 返却日時（未返却の場合はnull）
     #[schema(example = "2024-01-22T14:00:00Z")]
     pub returned_at: Option<DateTime<Utc>>,
+    /// 返却期限を延長した回数
+    #[schema(example = 0)]
+    pub renewal_count: i32,
+    /// 未返却のまま返却期限を過ぎているかどうか
+    #[schema(example = false)]
+    pub is_overdue: bool,
     /// 貸出中の蔵書情報
     pub book: CheckoutBookResponse,
 }
@@ -49,19 +118,69 @@ impl From<Checkout> for CheckoutResponse {
             id,
             checked_out_by,
             checked_out_at,
+            due_at,
             returned_at,
+            renewal_count,
+            is_overdue,
             book,
         } = value;
         Self {
             id,
             checked_out_by: checked_out_by.into(),
             checked_out_at,
+            due_at,
             returned_at,
+            renewal_count,
+            is_overdue,
             book: book.into(),
         }
     }
 }
 
+/// 延滞中の貸出一覧レスポンス
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OverdueCheckoutsResponse {
+    /// 延滞中の貸出情報一覧（返却期限の古い順）
+    pub items: Vec<OverdueCheckoutResponse>,
+}
+
+impl From<Vec<OverdueCheckout>> for OverdueCheckoutsResponse {
+    fn from(value: Vec<OverdueCheckout>) -> Self {
+        Self {
+            items: value
+                .into_iter()
+                .map(OverdueCheckoutResponse::from)
+                .collect(),
+        }
+    }
+}
+
+/// 延滞中の貸出情報レスポンス
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OverdueCheckoutResponse {
+    /// 貸出情報
+    #[serde(flatten)]
+    pub checkout: CheckoutResponse,
+    /// 返却期限からの経過日数
+    #[schema(example = 3)]
+    pub days_overdue: i64,
+}
+
+impl From<OverdueCheckout> for OverdueCheckoutResponse {
+    fn from(value: OverdueCheckout) -> Self {
+        let OverdueCheckout {
+            checkout,
+            days_overdue,
+        } = value;
+        Self {
+            checkout: checkout.into(),
+            days_overdue,
+        }
+    }
+}
+
 /// 貸出蔵書の簡易情報
 #[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
@@ -69,6 +188,9 @@ pub struct CheckoutBookResponse {
     /// 蔵書ID
     #[schema(value_type = String, example = "550e8400-e29b-41d4-a716-446655440000")]
     pub id: BookId,
+    /// 貸し出したコピーのID
+    #[schema(value_type = String, example = "550e8400-e29b-41d4-a716-446655440000")]
+    pub copy_id: BookCopyId,
     /// 書籍のタイトル
     #[schema(example = "The Rust Programming Language")]
     pub title: String,
@@ -84,12 +206,14 @@ impl From<CheckoutBook> for CheckoutBookResponse {
     fn from(value: CheckoutBook) -> Self {
         let CheckoutBook {
             book_id,
+            copy_id,
             title,
             author,
             isbn,
         } = value;
         Self {
             id: book_id,
+            copy_id,
             title,
             author,
             isbn,
@@ -0,0 +1,120 @@
+use std::io::Cursor;
+
+use image::{ImageFormat, ImageReader, Limits};
+use kernel::model::{book::BookCover, id::CoverId};
+use serde::{Deserialize, Serialize};
+use shared::error::{AppError, AppResult};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+// 書影として登録できる画像のファイルサイズの上限（5 MiB）
+// ルーティングでリクエストボディの上限に設定し、超えた場合は画像を読み込む前に 413 を返す
+pub const MAX_COVER_SIZE: usize = 5 * 1024 * 1024;
+// 書影として登録できる画像の幅・高さの上限
+const MAX_COVER_DIMENSION: u32 = 4096;
+// サムネイルの幅・高さの上限（縦横比は保つ）
+const THUMBNAIL_SIZE: u32 = 256;
+
+// 書影として登録できる画像の形式
+const SUPPORTED_FORMATS: [(&str, ImageFormat); 3] = [
+    ("image/jpeg", ImageFormat::Jpeg),
+    ("image/png", ImageFormat::Png),
+    ("image/webp", ImageFormat::WebP),
+];
+
+// 検証済みの書影の画像と、そこから生成したサムネイル
+pub struct CoverImage {
+    pub content_type: String,
+    pub original: Vec<u8>,
+    pub thumbnail: Vec<u8>,
+}
+
+impl CoverImage {
+    // アップロードされた画像を検証し、サムネイルを生成する
+    // Content-Type と実際の画像の形式が一致し、画像として読み込めるものだけを受け付ける
+    pub fn process(content_type: &str, data: Vec<u8>) -> AppResult<Self> {
+        let media_type = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        let (content_type, format) = SUPPORTED_FORMATS
+            .into_iter()
+            .find(|(supported, _)| *supported == media_type)
+            .ok_or_else(|| {
+                AppError::UnsupportedMediaType(format!(
+                    "書影の形式（{content_type}）には対応していません。JPEG・PNG・WebP のいずれかを指定してください。"
+                ))
+            })?;
+        if image::guess_format(&data).ok() != Some(format) {
+            return Err(AppError::UnprocessableEntity(format!(
+                "書影の内容が Content-Type（{content_type}）と一致しません。"
+            )));
+        }
+
+        let mut limits = Limits::default();
+        limits.max_image_width = Some(MAX_COVER_DIMENSION);
+        limits.max_image_height = Some(MAX_COVER_DIMENSION);
+        let mut reader = ImageReader::with_format(Cursor::new(&data), format);
+        reader.limits(limits);
+        let image = reader.decode().map_err(|e| {
+            AppError::UnprocessableEntity(format!("書影の画像を読み込めませんでした（{e}）。"))
+        })?;
+
+        let mut thumbnail = Vec::new();
+        image
+            .thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+            .write_to(&mut Cursor::new(&mut thumbnail), ImageFormat::Png)
+            .map_err(|e| AppError::ConversionEntityError(e.to_string()))?;
+
+        Ok(Self {
+            content_type: content_type.into(),
+            original: data,
+            thumbnail,
+        })
+    }
+}
+
+// 書影の画像を返すエンドポイントの URL
+// 書影を置き換えると URL も変わるよう、書影の ID をクエリに含める
+pub fn cover_url(book_id: Uuid, cover: &BookCover) -> String {
+    format!("/api/v1/books/{book_id}/cover?v={}", cover.id)
+}
+
+pub fn cover_thumbnail_url(book_id: Uuid, cover: &BookCover) -> String {
+    format!("/api/v1/books/{book_id}/cover/thumbnail?v={}", cover.id)
+}
+
+/// 書影取得のクエリパラメータ
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct CoverQuery {
+    /// 書影のID（書影の URL に含まれる値）。現在の書影と一致しない場合は 404 を返す
+    #[param(value_type = Option<String>, example = "0b5f1f3c2d8e4a7b9c6d5e4f3a2b1c0d")]
+    pub v: Option<CoverId>,
+}
+
+/// 書影登録レスポンス
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BookCoverResponse {
+    /// 書影の画像の URL
+    #[schema(
+        example = "/api/v1/books/550e8400-e29b-41d4-a716-446655440000/cover?v=0b5f1f3c2d8e4a7b9c6d5e4f3a2b1c0d"
+    )]
+    pub cover_url: String,
+    /// 書影のサムネイル（PNG）の URL
+    #[schema(
+        example = "/api/v1/books/550e8400-e29b-41d4-a716-446655440000/cover/thumbnail?v=0b5f1f3c2d8e4a7b9c6d5e4f3a2b1c0d"
+    )]
+    pub cover_thumbnail_url: String,
+}
+
+impl BookCoverResponse {
+    pub fn new(book_id: Uuid, cover: &BookCover) -> Self {
+        Self {
+            cover_url: cover_url(book_id, cover),
+            cover_thumbnail_url: cover_thumbnail_url(book_id, cover),
+        }
+    }
+}
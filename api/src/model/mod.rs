@@ -1,4 +1,33 @@
 pub mod auth;
 pub mod book;
 pub mod checkout;
+pub mod cover;
+pub mod reservation;
+pub mod session;
+pub mod tag;
 pub mod user;
+
+use serde::{Deserialize, Deserializer};
+use shared::error::{AppError, AppResult};
+
+// JSON Merge Patch（RFC 7396）の項目を読み込む
+// 項目の省略は None、null の指定は Some(None) として区別する
+// （`#[serde(default, deserialize_with = "deserialize_patch")]` と組み合わせて使う）
+fn deserialize_patch<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+// null を指定できない項目を取り出す
+fn required_patch<T>(name: &str, value: Option<Option<T>>) -> AppResult<Option<T>> {
+    match value {
+        Some(None) => Err(AppError::UnprocessableEntity(format!(
+            "{name} に null は指定できません。"
+        ))),
+        Some(Some(value)) => Ok(Some(value)),
+        None => Ok(None),
+    }
+}
@@ -0,0 +1,103 @@
+use chrono::{DateTime, Utc};
+use kernel::model::{
+    id::{BookId, ReservationId},
+    reservation::{Reservation, ReservationBook},
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::model::user::ReservationUser;
+
+/// 予約一覧レスポンス
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReservationsResponse {
+    /// 予約情報一覧
+    pub items: Vec<ReservationResponse>,
+}
+
+impl From<Vec<Reservation>> for ReservationsResponse {
+    fn from(value: Vec<Reservation>) -> Self {
+        Self {
+            items: value.into_iter().map(ReservationResponse::from).collect(),
+        }
+    }
+}
+
+/// 予約情報レスポンス
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReservationResponse {
+    /// 予約ID
+    #[schema(value_type = String, example = "550e8400-e29b-41d4-a716-446655440000")]
+    pub id: ReservationId,
+    /// 予約したユーザー
+    pub reserved_by: ReservationUser,
+    /// 予約日時
+    #[schema(example = "2024-01-15T10:30:00Z")]
+    pub reserved_at: DateTime<Utc>,
+    /// 予約の順番（1始まり）
+    #[schema(example = 1)]
+    pub position: i64,
+    /// 取り置きの受け取り期限（順番待ちの場合はnull）
+    #[schema(example = "2024-01-18T10:30:00Z")]
+    pub hold_expires_at: Option<DateTime<Utc>>,
+    /// 予約した蔵書情報
+    pub book: ReservationBookResponse,
+}
+
+impl From<Reservation> for ReservationResponse {
+    fn from(value: Reservation) -> Self {
+        let Reservation {
+            id,
+            reserved_by,
+            reserved_at,
+            position,
+            hold_expires_at,
+            book,
+        } = value;
+        Self {
+            id,
+            reserved_by: reserved_by.into(),
+            reserved_at,
+            position,
+            hold_expires_at,
+            book: book.into(),
+        }
+    }
+}
+
+/// 予約蔵書の簡易情報
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReservationBookResponse {
+    /// 蔵書ID
+    #[schema(value_type = String, example = "550e8400-e29b-41d4-a716-446655440000")]
+    pub id: BookId,
+    /// 書籍のタイトル
+    #[schema(example = "The Rust Programming Language")]
+    pub title: String,
+    /// 著者名
+    #[schema(example = "Steve Klabnik and Carol Nichols")]
+    pub author: String,
+    /// ISBN（国際標準図書番号）
+    #[schema(example = "978-1593278281")]
+    pub isbn: String,
+}
+
+impl From<ReservationBook> for ReservationBookResponse {
+    fn from(value: ReservationBook) -> Self {
+        let ReservationBook {
+            book_id,
+            title,
+            author,
+            isbn,
+        } = value;
+        Self {
+            id: book_id,
+            title,
+            author,
+            isbn,
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use chrono::{DateTime, Utc};
+use kernel::model::{auth::Session, id::SessionId};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// セッション一覧レスポンス
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionsResponse {
+    /// ログイン中のセッション一覧（最後に使われた日時の新しい順）
+    pub items: Vec<SessionResponse>,
+}
+
+impl SessionsResponse {
+    pub fn new(sessions: Vec<Session>, current: Option<SessionId>) -> Self {
+        Self {
+            items: sessions
+                .into_iter()
+                .map(|session| {
+                    let current = Some(session.id) == current;
+                    SessionResponse::new(session, current)
+                })
+                .collect(),
+        }
+    }
+}
+
+/// セッション情報
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionResponse {
+    /// セッションID
+    #[schema(value_type = String, example = "550e8400-e29b-41d4-a716-446655440000")]
+    pub id: SessionId,
+    /// ログインしたクライアントの User-Agent
+    #[schema(example = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7)")]
+    pub user_agent: Option<String>,
+    /// ログインしたクライアントのIPアドレス
+    #[schema(example = "203.0.113.1")]
+    pub ip_address: Option<String>,
+    /// ログイン日時
+    pub created_at: DateTime<Utc>,
+    /// 最後にAPIへアクセスした日時
+    pub last_used_at: DateTime<Utc>,
+    /// リクエストに使ったアクセストークンのセッションかどうか
+    pub current: bool,
+}
+
+impl SessionResponse {
+    fn new(session: Session, current: bool) -> Self {
+        let Session {
+            id,
+            user_agent,
+            ip_address,
+            created_at,
+            last_used_at,
+        } = session;
+        Self {
+            id,
+            user_agent,
+            ip_address,
+            created_at,
+            last_used_at,
+            current,
+        }
+    }
+}
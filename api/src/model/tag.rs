@@ -0,0 +1,96 @@
+use derive_new::new;
+use garde::Validate;
+use kernel::model::{
+    id::TagId,
+    tag::{
+        Tag, TagFacet,
+        event::{CreateTag, UpdateTag},
+    },
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// タグ登録リクエスト
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateTagRequest {
+    /// タグ名（大文字・小文字を区別せず一意）
+    #[garde(length(chars, min = 1, max = 64))]
+    #[schema(example = "プログラミング")]
+    pub name: String,
+}
+
+impl From<CreateTagRequest> for CreateTag {
+    fn from(value: CreateTagRequest) -> Self {
+        let CreateTagRequest { name } = value;
+        Self { name }
+    }
+}
+
+/// タグ更新リクエスト
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateTagRequest {
+    /// タグ名（大文字・小文字を区別せず一意）
+    #[garde(length(chars, min = 1, max = 64))]
+    #[schema(example = "Rust")]
+    pub name: String,
+}
+
+#[derive(new)]
+pub struct UpdateTagRequestWithId(TagId, UpdateTagRequest);
+
+impl From<UpdateTagRequestWithId> for UpdateTag {
+    fn from(value: UpdateTagRequestWithId) -> Self {
+        let UpdateTagRequestWithId(tag_id, UpdateTagRequest { name }) = value;
+        Self { tag_id, name }
+    }
+}
+
+/// タグ
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TagResponse {
+    /// タグID
+    #[schema(value_type = String, example = "550e8400-e29b-41d4-a716-446655440000")]
+    pub id: TagId,
+    /// タグ名
+    #[schema(example = "プログラミング")]
+    pub name: String,
+}
+
+impl From<Tag> for TagResponse {
+    fn from(value: Tag) -> Self {
+        let Tag { id, name } = value;
+        Self { id, name }
+    }
+}
+
+/// タグ一覧レスポンス
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TagsResponse {
+    /// タグ一覧（タグ名の昇順）
+    pub items: Vec<TagResponse>,
+}
+
+/// タグごとの蔵書の冊数
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TagFacetResponse {
+    /// タグ
+    pub tag: TagResponse,
+    /// 検索条件に当てはまる蔵書のうち、このタグが付いている冊数
+    #[schema(example = 3)]
+    pub count: i64,
+}
+
+impl From<TagFacet> for TagFacetResponse {
+    fn from(value: TagFacet) -> Self {
+        let TagFacet { tag, count } = value;
+        Self {
+            tag: tag.into(),
+            count,
+        }
+    }
+}
@@ -5,12 +5,18 @@ use kernel::model::{
     role::Role,
     user::{
         User,
-        event::{CreateUser, UpdateUserPassword, UpdateUserRole},
+        event::{
+            CreateUser, DeleteUser, DeleteUserBooks, UpdateUserCheckoutLimit, UpdateUserPassword,
+            UpdateUserProfile, UpdateUserRole,
+        },
     },
 };
 use serde::{Deserialize, Serialize};
+use shared::error::{AppError, AppResult};
 use strum::VariantNames;
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::model::{deserialize_patch, required_patch};
 
 /// ユーザーの権限ロール
 #[derive(Serialize, Deserialize, VariantNames, ToSchema)]
@@ -83,6 +89,41 @@ impl From<User> for UserResponse {
     }
 }
 
+/// 自身のユーザー情報の部分更新リクエスト（JSON Merge Patch）
+///
+/// 指定した項目だけを更新し、省略した項目は変更しない
+#[derive(Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchUserRequest {
+    /// ユーザー名（null は指定できない）
+    #[serde(default, deserialize_with = "deserialize_patch")]
+    #[garde(length(min = 1))]
+    #[schema(value_type = Option<String>, example = "山田太郎")]
+    pub name: Option<Option<String>>,
+    /// メールアドレス（null は指定できない）
+    #[serde(default, deserialize_with = "deserialize_patch")]
+    #[garde(email)]
+    #[schema(value_type = Option<String>, example = "yamada@example.com")]
+    pub email: Option<Option<String>>,
+}
+
+#[derive(new)]
+pub struct PatchUserRequestWithUserId(UserId, PatchUserRequest);
+
+impl TryFrom<PatchUserRequestWithUserId> for UpdateUserProfile {
+    type Error = AppError;
+
+    fn try_from(value: PatchUserRequestWithUserId) -> AppResult<Self> {
+        let PatchUserRequestWithUserId(user_id, PatchUserRequest { name, email }) = value;
+
+        Ok(Self {
+            user_id,
+            name: required_patch("name", name)?,
+            email: required_patch("email", email)?,
+        })
+    }
+}
+
 /// パスワード変更リクエスト
 #[derive(Deserialize, Validate, ToSchema)]
 #[serde(rename_all = "camelCase")]
@@ -174,6 +215,90 @@ impl From<UpdateUserRoleRequestWithUserId> for UpdateUserRole {
     }
 }
 
+/// 貸出数上限変更リクエスト
+#[derive(Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateUserCheckoutLimitRequest {
+    /// 同時に借りられる冊数の上限（nullの場合はロールごとの上限に戻す）
+    #[garde(inner(range(min = 0)))]
+    #[schema(example = 10)]
+    pub checkout_limit: Option<i32>,
+}
+
+#[derive(new)]
+pub struct UpdateUserCheckoutLimitRequestWithUserId(UserId, UpdateUserCheckoutLimitRequest);
+
+impl From<UpdateUserCheckoutLimitRequestWithUserId> for UpdateUserCheckoutLimit {
+    fn from(value: UpdateUserCheckoutLimitRequestWithUserId) -> Self {
+        let UpdateUserCheckoutLimitRequestWithUserId(
+            user_id,
+            UpdateUserCheckoutLimitRequest { checkout_limit },
+        ) = value;
+
+        Self {
+            user_id,
+            checkout_limit,
+        }
+    }
+}
+
+/// ユーザー削除のクエリパラメータ
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(rename_all = "camelCase")]
+pub struct DeleteUserQuery {
+    /// ユーザーが所有する蔵書とコピーを引き継ぐユーザーのID（archiveBooks とは同時に指定できない）
+    #[param(value_type = Option<String>, example = "550e8400-e29b-41d4-a716-446655440000")]
+    pub transfer_books_to: Option<UserId>,
+
+    /// true の場合は、ユーザーが所有する蔵書をアーカイブし、所有者を削除を実行した管理者に変更する（デフォルト: false）
+    #[serde(default)]
+    #[param(example = false)]
+    pub archive_books: bool,
+
+    /// true の場合は、ユーザーが借りている蔵書を返却してから削除する（デフォルト: false）
+    #[serde(default)]
+    #[param(example = false)]
+    pub force_return: bool,
+}
+
+#[derive(new)]
+pub struct DeleteUserQueryWithIds(UserId, UserId, DeleteUserQuery);
+
+impl TryFrom<DeleteUserQueryWithIds> for DeleteUser {
+    type Error = AppError;
+
+    fn try_from(value: DeleteUserQueryWithIds) -> AppResult<Self> {
+        let DeleteUserQueryWithIds(
+            user_id,
+            requested_user,
+            DeleteUserQuery {
+                transfer_books_to,
+                archive_books,
+                force_return,
+            },
+        ) = value;
+
+        let books = match (transfer_books_to, archive_books) {
+            (Some(_), true) => {
+                return Err(AppError::UnprocessableEntity(
+                    "transferBooksTo と archiveBooks は同時に指定できません。".into(),
+                ));
+            }
+            (Some(new_owner), false) => Some(DeleteUserBooks::TransferTo(new_owner)),
+            (None, true) => Some(DeleteUserBooks::Archive),
+            (None, false) => None,
+        };
+
+        Ok(Self {
+            user_id,
+            books,
+            force_return,
+            requested_user,
+        })
+    }
+}
+
 /// 蔵書の所有者情報
 #[derive(Debug, Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
@@ -211,3 +336,22 @@ impl From<kernel::model::user::CheckoutUser> for CheckoutUser {
         Self { id, name }
     }
 }
+
+/// 予約ユーザー情報
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReservationUser {
+    /// ユーザーID
+    #[schema(value_type = String, example = "550e8400-e29b-41d4-a716-446655440000")]
+    pub id: UserId,
+    /// ユーザー名
+    #[schema(example = "山田太郎")]
+    pub name: String,
+}
+
+impl From<kernel::model::user::ReservationUser> for ReservationUser {
+    fn from(value: kernel::model::user::ReservationUser) -> Self {
+        let kernel::model::user::ReservationUser { id, name } = value;
+        Self { id, name }
+    }
+}
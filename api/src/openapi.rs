@@ -21,42 +21,106 @@ use utoipa::{
         crate::handler::health::health_check_db,
         crate::handler::auth::login,
         crate::handler::auth::logout,
+        crate::handler::auth::refresh,
         crate::handler::book::show_book_list,
+        crate::handler::book::import_books,
+        crate::handler::book::export_books,
+        crate::handler::book::lookup_book,
         crate::handler::book::show_book,
         crate::handler::book::register_book,
         crate::handler::book::update_book,
+        crate::handler::book::patch_book,
         crate::handler::book::delete_book,
+        crate::handler::book::restore_book,
+        crate::handler::book::transfer_book_owner,
+        crate::handler::book::show_book_owner_history,
+        crate::handler::cover::upload_book_cover,
+        crate::handler::cover::show_book_cover,
+        crate::handler::cover::show_book_cover_thumbnail,
+        crate::handler::book::register_book_copy,
+        crate::handler::book::delete_book_copy,
+        crate::handler::tag::list_tags,
+        crate::handler::tag::register_tag,
+        crate::handler::tag::update_tag,
+        crate::handler::tag::delete_tag,
         crate::handler::checkout::checkout_book,
         crate::handler::checkout::return_book,
+        crate::handler::checkout::renew_checkout,
         crate::handler::checkout::checkout_history,
+        crate::handler::reservation::reserve_book,
+        crate::handler::reservation::show_reservation_list,
+        crate::handler::reservation::cancel_reservation,
         crate::handler::checkout::show_checked_out_list,
+        crate::handler::checkout::show_overdue_list,
         crate::handler::user::register_user,
         crate::handler::user::list_users,
         crate::handler::user::delete_user,
         crate::handler::user::change_role,
+        crate::handler::user::change_checkout_limit,
+        crate::handler::user::unlock_user,
+        crate::handler::user::patch_current_user,
         crate::handler::user::change_password,
         crate::handler::user::get_current_user,
         crate::handler::user::get_checkouts,
+        crate::handler::user::get_reservations,
+        crate::handler::session::get_my_sessions,
+        crate::handler::session::delete_my_session,
+        crate::handler::session::delete_my_sessions,
+        crate::handler::session::get_user_sessions,
+        crate::handler::session::delete_user_session,
+        crate::handler::session::delete_user_sessions,
     ),
     components(schemas(
         crate::model::auth::LoginRequest,
         crate::model::auth::AccessTokenResponse,
+        crate::model::auth::RefreshTokenRequest,
         crate::model::book::CreateBookRequest,
         crate::model::book::UpdateBookRequest,
+        crate::model::book::PatchBookRequest,
+        crate::model::book::UpdateBookOwnerRequest,
+        crate::model::book::BookOwnerTransferResponse,
+        crate::model::book::BookOwnerTransfersResponse,
+        crate::model::cover::BookCoverResponse,
+        crate::model::book::CreateBookCopyRequest,
         crate::model::book::BookResponse,
         crate::model::book::PaginatedBookResponse,
+        crate::model::book::BookCopyResponse,
         crate::model::book::BookCheckoutResponse,
+        crate::model::book::BookAvailabilityQuery,
+        crate::model::book::BookSortQuery,
+        crate::model::book::BookImportFormat,
+        crate::model::book::BookImportResponse,
+        crate::model::book::ImportedBookResponse,
+        crate::model::book::BookImportDuplicateResponse,
+        crate::model::book::BookImportErrorResponse,
+        crate::model::book::BookExportFormat,
+        crate::model::tag::CreateTagRequest,
+        crate::model::tag::UpdateTagRequest,
+        crate::model::tag::TagResponse,
+        crate::model::tag::TagsResponse,
+        crate::model::tag::TagFacetResponse,
+        crate::model::checkout::CheckoutBookRequest,
         crate::model::checkout::CheckoutsResponse,
         crate::model::checkout::CheckoutResponse,
         crate::model::checkout::CheckoutBookResponse,
+        crate::model::checkout::OverdueCheckoutsResponse,
+        crate::model::checkout::OverdueCheckoutResponse,
         crate::model::user::CreateUserRequest,
+        crate::model::reservation::ReservationsResponse,
+        crate::model::reservation::ReservationResponse,
+        crate::model::reservation::ReservationBookResponse,
+        crate::model::user::PatchUserRequest,
         crate::model::user::UpdateUserPasswordRequest,
         crate::model::user::UpdateUserRoleRequest,
+        crate::model::user::UpdateUserCheckoutLimitRequest,
         crate::model::user::UserResponse,
         crate::model::user::UsersResponse,
+        crate::model::session::SessionsResponse,
+        crate::model::session::SessionResponse,
         crate::model::user::RoleName,
         crate::model::user::BookOwner,
         crate::model::user::CheckoutUser,
+        crate::model::user::ReservationUser,
     )),
     modifiers(&SecurityAddon),
 )]
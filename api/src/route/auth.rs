@@ -1,12 +1,13 @@
 use axum::{Router, routing::post};
 use registry::AppRegistry;
 
-use crate::handler::auth::{login, logout};
+use crate::handler::auth::{login, logout, refresh};
 
 pub fn routes() -> Router<AppRegistry> {
     let routers = Router::new()
         .route("/login", post(login))
-        .route("/logout", post(logout));
+        .route("/logout", post(logout))
+        .route("/refresh", post(refresh));
 
     Router::new().nest("/auth", routers)
 }
@@ -1,30 +1,74 @@
 use axum::{
     Router,
-    routing::{delete, get, post, put},
+    extract::DefaultBodyLimit,
+    routing::{delete, get, patch, post, put},
 };
 use registry::AppRegistry;
 
-use crate::handler::book::{delete_book, register_book, show_book, show_book_list, update_book};
+use crate::handler::book::{
+    delete_book, delete_book_copy, export_books, import_books, lookup_book, patch_book,
+    register_book, register_book_copy, restore_book, show_book, show_book_list,
+    show_book_owner_history, transfer_book_owner, update_book,
+};
 use crate::handler::checkout::{
-    checkout_book, checkout_history, return_book, show_checked_out_list,
+    checkout_book, checkout_history, renew_checkout, return_book, show_checked_out_list,
+    show_overdue_list,
 };
+use crate::handler::cover::{show_book_cover, show_book_cover_thumbnail, upload_book_cover};
+use crate::handler::reservation::{cancel_reservation, reserve_book, show_reservation_list};
+use crate::model::cover::MAX_COVER_SIZE;
 
 pub fn build_book_routes() -> Router<AppRegistry> {
     let book_routers = Router::new()
         .route("/", post(register_book))
         .route("/", get(show_book_list))
+        .route("/import", post(import_books))
+        .route("/export", get(export_books))
+        .route("/lookup", get(lookup_book))
         .route("/{book_id}", get(show_book))
         .route("/{book_id}", put(update_book))
-        .route("/{book_id}", delete(delete_book));
+        .route("/{book_id}", patch(patch_book))
+        .route("/{book_id}", delete(delete_book))
+        .route("/{book_id}/restore", post(restore_book))
+        .route("/{book_id}/owner", put(transfer_book_owner))
+        .route("/{book_id}/owner-history", get(show_book_owner_history))
+        .route("/{book_id}/cover", get(show_book_cover))
+        .route(
+            "/{book_id}/cover",
+            put(upload_book_cover).layer(DefaultBodyLimit::max(MAX_COVER_SIZE)),
+        )
+        .route("/{book_id}/cover/thumbnail", get(show_book_cover_thumbnail))
+        .route("/{book_id}/copies", post(register_book_copy))
+        .route("/{book_id}/copies/{copy_id}", delete(delete_book_copy));
 
     let checkout_routers = Router::new()
         .route("/checkouts", get(show_checked_out_list))
+        .route("/checkouts/overdue", get(show_overdue_list))
         .route("/{book_id}/checkouts", post(checkout_book))
         .route(
             "/{book_id}/checkouts/{checkout_id}/returned",
             put(return_book),
         )
+        .route(
+            "/{book_id}/checkouts/{checkout_id}/renewal",
+            put(renew_checkout),
+        )
         .route("/{book_id}/checkout-history", get(checkout_history));
 
-    Router::new().nest("/books", book_routers.merge(checkout_routers))
+    let reservation_routers = Router::new()
+        .route(
+            "/{book_id}/reservations",
+            get(show_reservation_list).post(reserve_book),
+        )
+        .route(
+            "/{book_id}/reservations/{reservation_id}",
+            delete(cancel_reservation),
+        );
+
+    Router::new().nest(
+        "/books",
+        book_routers
+            .merge(checkout_routers)
+            .merge(reservation_routers),
+    )
 }
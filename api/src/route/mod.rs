@@ -1,5 +1,6 @@
 pub mod auth;
 pub mod book;
 pub mod health;
+pub mod tag;
 pub mod user;
 pub mod v1;
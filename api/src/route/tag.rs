@@ -0,0 +1,15 @@
+use axum::{
+    Router,
+    routing::{get, put},
+};
+use registry::AppRegistry;
+
+use crate::handler::tag::{delete_tag, list_tags, register_tag, update_tag};
+
+pub fn build_tag_routes() -> Router<AppRegistry> {
+    let tag_routers = Router::new()
+        .route("/", get(list_tags).post(register_tag))
+        .route("/{tag_id}", put(update_tag).delete(delete_tag));
+
+    Router::new().nest("/tags", tag_routers)
+}
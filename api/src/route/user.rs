@@ -4,17 +4,43 @@ use axum::{
 };
 use registry::AppRegistry;
 
-use crate::handler::user::{
-    change_password, change_role, delete_user, get_checkouts, get_current_user, list_users,
-    register_user,
+use crate::handler::{
+    session::{
+        delete_my_session, delete_my_sessions, delete_user_session, delete_user_sessions,
+        get_my_sessions, get_user_sessions,
+    },
+    user::{
+        change_checkout_limit, change_password, change_role, delete_user, get_checkouts,
+        get_current_user, get_reservations, list_users, patch_current_user, register_user,
+        unlock_user,
+    },
 };
 
 pub fn build_user_router() -> Router<AppRegistry> {
     Router::new()
-        .route("/users/me", get(get_current_user))
+        .route("/users/me", get(get_current_user).patch(patch_current_user))
         .route("/users/me/checkouts", get(get_checkouts))
+        .route("/users/me/reservations", get(get_reservations))
         .route("/users/me/password", put(change_password))
+        .route(
+            "/users/me/sessions",
+            get(get_my_sessions).delete(delete_my_sessions),
+        )
+        .route("/users/me/sessions/{session_id}", delete(delete_my_session))
         .route("/users", get(list_users).post(register_user))
         .route("/users/{user_id}", delete(delete_user))
         .route("/users/{user_id}/role", put(change_role))
+        .route(
+            "/users/{user_id}/checkout-limit",
+            put(change_checkout_limit),
+        )
+        .route("/users/{user_id}/login-lock", delete(unlock_user))
+        .route(
+            "/users/{user_id}/sessions",
+            get(get_user_sessions).delete(delete_user_sessions),
+        )
+        .route(
+            "/users/{user_id}/sessions/{session_id}",
+            delete(delete_user_session),
+        )
 }
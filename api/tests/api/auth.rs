@@ -0,0 +1,200 @@
+use std::{net::SocketAddr, sync::Arc};
+
+use axum::{body::Body, extract::ConnectInfo, http::Request};
+use kernel::{
+    model::{
+        auth::{AccessToken, AuthTokens, RefreshToken},
+        id::UserId,
+    },
+    repository::auth::MockAuthRepository,
+};
+use rstest::rstest;
+use shared::{config::TrustedProxies, error::AppError};
+use tower::ServiceExt;
+
+use crate::{
+    deserialize_json,
+    helper::{fixture_registry, make_router},
+};
+use api::model::auth::AccessTokenResponse;
+
+#[rstest]
+#[tokio::test]
+async fn login_200(mut fixture_registry: registry::MockAppRegistryExt) -> anyhow::Result<()> {
+    let user_id = UserId::new();
+    fixture_registry
+        .expect_trusted_proxies()
+        .returning(|| "10.0.0.0/8".parse().unwrap());
+    fixture_registry
+        .expect_auth_repository()
+        .returning(move || {
+            let mut mock = MockAuthRepository::new();
+            mock.expect_verify_user()
+                .withf(|event| {
+                    event.email == "test@example.com"
+                        && event.password == "password"
+                        && event.ip_address.as_deref() == Some("203.0.113.1")
+                })
+                .returning(move |_| Ok(user_id));
+            mock.expect_create_token()
+                .withf(move |event| {
+                    event.user_id == user_id
+                        && event
+                            .refresh_token
+                            .starts_with(&format!("{}.", event.session_id))
+                        && event.user_agent.as_deref() == Some("test-agent")
+                        && event.ip_address.as_deref() == Some("203.0.113.1")
+                })
+                .returning(|event| {
+                    Ok(AuthTokens {
+                        user_id: event.user_id,
+                        access_token: AccessToken(event.access_token),
+                        refresh_token: RefreshToken(event.refresh_token),
+                    })
+                });
+            Arc::new(mock)
+        });
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    // 信頼するロードバランサーからの接続では、X-Forwarded-For の末尾から
+    // ロードバランサーのアドレスを読み飛ばした最初のアドレスを使う
+    let mut req = Request::post("/auth/login")
+        .header("Content-Type", "application/json")
+        .header("User-Agent", "test-agent")
+        .header("X-Forwarded-For", "198.51.100.1, 203.0.113.1, 10.0.1.5")
+        .body(Body::from(
+            r#"{"email":"test@example.com","password":"password"}"#,
+        ))?;
+    req.extensions_mut()
+        .insert(ConnectInfo(SocketAddr::from(([10, 0, 0, 2], 443))));
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::OK);
+
+    let result = deserialize_json!(resp, AccessTokenResponse);
+    assert_eq!(result.user_id, user_id);
+    assert!(!result.access_token.is_empty());
+    assert!(!result.refresh_token.is_empty());
+
+    Ok(())
+}
+
+#[rstest]
+#[case(None)]
+#[case(Some("198.51.100.1"))]
+#[case(Some("198.51.100.2, 198.51.100.3"))]
+#[tokio::test]
+async fn login_ignores_spoofed_forwarded_for(
+    mut fixture_registry: registry::MockAppRegistryExt,
+    #[case] forwarded_for: Option<&'static str>,
+) -> anyhow::Result<()> {
+    fixture_registry
+        .expect_trusted_proxies()
+        .returning(|| "10.0.0.0/8".parse().unwrap());
+    // 信頼するプロキシ以外からの接続では、X-Forwarded-For に何を指定しても
+    // ログイン失敗回数を数える IP アドレスは接続元のアドレスになる
+    fixture_registry.expect_auth_repository().returning(|| {
+        let mut mock = MockAuthRepository::new();
+        mock.expect_verify_user()
+            .withf(|event| event.ip_address.as_deref() == Some("203.0.113.1"))
+            .returning(|_| Err(AppError::UnauthenticatedError));
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let mut req = Request::post("/auth/login").header("Content-Type", "application/json");
+    if let Some(forwarded_for) = forwarded_for {
+        req = req.header("X-Forwarded-For", forwarded_for);
+    }
+    let mut req = req.body(Body::from(
+        r#"{"email":"test@example.com","password":"wrong"}"#,
+    ))?;
+    req.extensions_mut()
+        .insert(ConnectInfo(SocketAddr::from(([203, 0, 113, 1], 50000))));
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::FORBIDDEN);
+
+    Ok(())
+}
+
+#[rstest]
+#[case(true, axum::http::StatusCode::OK)]
+#[case(false, axum::http::StatusCode::UNAUTHORIZED)]
+#[tokio::test]
+async fn refresh_token(
+    mut fixture_registry: registry::MockAppRegistryExt,
+    #[case] valid: bool,
+    #[case] expected_status: axum::http::StatusCode,
+) -> anyhow::Result<()> {
+    let user_id = UserId::new();
+    fixture_registry
+        .expect_auth_repository()
+        .returning(move || {
+            let mut mock = MockAuthRepository::new();
+            mock.expect_refresh_token()
+                .withf(|token| token.0 == "family.secret")
+                .returning(move |_| {
+                    // 無効・使用済みのリフレッシュトークンの場合は認証エラーになる
+                    if !valid {
+                        return Err(AppError::UnauthorizedError);
+                    }
+                    Ok(AuthTokens {
+                        user_id,
+                        access_token: AccessToken("new-access-token".into()),
+                        refresh_token: RefreshToken("family.new-secret".into()),
+                    })
+                });
+            Arc::new(mock)
+        });
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::post("/auth/refresh")
+        .header("Content-Type", "application/json")
+        .body(Body::from(r#"{"refreshToken":"family.secret"}"#))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), expected_status);
+
+    if valid {
+        let result = deserialize_json!(resp, AccessTokenResponse);
+        assert_eq!(result.user_id, user_id);
+        assert_eq!(result.access_token, "new-access-token");
+        assert_eq!(result.refresh_token, "family.new-secret");
+    }
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn login_locked_429(
+    mut fixture_registry: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    fixture_registry
+        .expect_trusted_proxies()
+        .returning(TrustedProxies::default);
+    fixture_registry.expect_auth_repository().returning(|| {
+        let mut mock = MockAuthRepository::new();
+        mock.expect_verify_user()
+            .returning(|_| Err(AppError::TooManyLoginAttempts { retry_after: 120 }));
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::post("/auth/login")
+        .header("Content-Type", "application/json")
+        .body(Body::from(
+            r#"{"email":"test@example.com","password":"password"}"#,
+        ))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(resp.headers()[axum::http::header::RETRY_AFTER], "120");
+
+    let body = deserialize_json!(resp, serde_json::Value);
+    assert_eq!(body["code"], "TOO_MANY_LOGIN_ATTEMPTS");
+    assert_eq!(body["retryAfter"], 120);
+
+    Ok(())
+}
@@ -9,10 +9,22 @@ use crate::{
     deserialize_json,
     helper::{TestRequestExt, fixture, make_router, v1},
 };
-use api::model::book::PaginatedBookResponse;
+use api::model::book::{
+    BookImportResponse, BookOwnerTransfersResponse, BookResponse, CreateBookRequest,
+    PaginatedBookResponse,
+};
 use kernel::{
-    model::{book::Book, id::UserId, list::PaginatedList, user::BookOwner},
-    repository::book::MockBookRepository,
+    model::{
+        book::{
+            Book, BookArchive, BookAvailability, BookImportReport, BookMetadata, BookOwnerTransfer,
+            BookSortKey, ImportedBook,
+        },
+        id::{BookId, TagId, UserId},
+        list::{Cursor, PaginatedList},
+        tag::{Tag, TagFacet},
+        user::BookOwner,
+    },
+    repository::{book::MockBookRepository, book_metadata::MockBookMetadataProvider},
 };
 
 #[rstest]
@@ -31,24 +43,30 @@ async fn show_book_list_with_query_200(
     // モックの挙動を設定する
     fixture.expect_book_repository().returning(move || {
         let mut mock = MockBookRepository::new();
+        mock.expect_find_tag_facets().returning(|_| Ok(vec![]));
         mock.expect_find_all().returning(move |opt| {
             let items = vec![Book {
                 id: book_id,
                 title: "RustによるWebアプリケーション開発".to_string(),
-                isbn: "".to_string(),
+                isbn: "9784798186016".parse().unwrap(),
                 author: "Yuki Toyoda".to_string(),
                 description: "RustによるWebアプリケーション開発".to_string(),
                 owner: BookOwner {
                     id: UserId::new(),
                     name: "Yuki Toyoda".to_string(),
                 },
-                checkout: None,
+                copies: vec![],
+                tags: vec![],
+                archive: None,
+                version: 1,
+                cover: None,
             }];
             Ok(PaginatedList {
                 total: 1,
                 limit: opt.limit,
                 offset: opt.offset,
                 items,
+                next_cursor: None,
             })
         });
         Arc::new(mock)
@@ -58,7 +76,7 @@ async fn show_book_list_with_query_200(
     let app: axum::Router = make_router(fixture);
 
     // 4. リクエストを作成・送信し、レスポンスのステータスコードを検証する
-    let req = Request::get(&v1(path)).bearer().body(Body::empty())?;
+    let req = Request::get(v1(path)).bearer().body(Body::empty())?;
     let resp = app.oneshot(req).await?;
     assert_eq!(resp.status(), axum::http::StatusCode::OK);
 
@@ -69,3 +87,820 @@ async fn show_book_list_with_query_200(
 
     Ok(())
 }
+
+#[rstest]
+#[tokio::test]
+async fn show_book_list_with_search_query_200(
+    mut fixture: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let owner_id = UserId::new();
+
+    // クエリパラメータが検索条件として渡されることを検証する
+    fixture.expect_book_repository().returning(move || {
+        let mut mock = MockBookRepository::new();
+        mock.expect_find_tag_facets().returning(|_| Ok(vec![]));
+        mock.expect_find_all()
+            .withf(move |opt| {
+                opt.query.as_deref() == Some("Rust 入門")
+                    && opt.availability == Some(BookAvailability::Available)
+                    && opt.owner == Some(owner_id)
+                    && opt.author.as_deref() == Some("Klabnik")
+                    && opt.sort == BookSortKey::Relevance
+            })
+            .returning(|opt| {
+                Ok(PaginatedList {
+                    total: 0,
+                    limit: opt.limit,
+                    offset: opt.offset,
+                    items: vec![],
+                    next_cursor: None,
+                })
+            });
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let path = format!(
+        "/books?q=Rust%20%E5%85%A5%E9%96%80&availability=available&ownerId={owner_id}&author=Klabnik&sort=relevance"
+    );
+    let req = Request::get(v1(&path)).bearer().body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::OK);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn show_book_list_with_tags_200(
+    mut fixture: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let rust = Tag {
+        id: TagId::new(),
+        name: "Rust".to_string(),
+    };
+    let web = TagId::new();
+    let tag_ids = vec![rust.id, web];
+
+    // カンマ区切りのタグが絞り込み条件として渡され、タグごとの冊数が返ることを検証する
+    let facet_tag = rust.clone();
+    let expected = tag_ids.clone();
+    fixture.expect_book_repository().returning(move || {
+        let mut mock = MockBookRepository::new();
+        let facet_tag = facet_tag.clone();
+        let facet_expected = expected.clone();
+        mock.expect_find_tag_facets()
+            .withf(move |opt| opt.tags == facet_expected)
+            .returning(move |_| {
+                Ok(vec![TagFacet {
+                    tag: facet_tag.clone(),
+                    count: 3,
+                }])
+            });
+        let list_expected = expected.clone();
+        mock.expect_find_all()
+            .withf(move |opt| opt.tags == list_expected)
+            .returning(|opt| {
+                Ok(PaginatedList {
+                    total: 0,
+                    limit: opt.limit,
+                    offset: opt.offset,
+                    items: vec![],
+                    next_cursor: None,
+                })
+            });
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let path = format!("/books?tags={},%20{}", tag_ids[0], tag_ids[1]);
+    let req = Request::get(v1(&path)).bearer().body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::OK);
+
+    let result = deserialize_json!(resp, PaginatedBookResponse);
+    assert_eq!(result.tag_facets.len(), 1);
+    assert_eq!(result.tag_facets[0].tag.id, rust.id);
+    assert_eq!(result.tag_facets[0].tag.name, "Rust");
+    assert_eq!(result.tag_facets[0].count, 3);
+
+    Ok(())
+}
+
+#[rstest]
+#[case("/books?sort=unknown")]
+#[case("/books?availability=lost")]
+#[case("/books?q=")]
+#[case("/books?cursor=invalid")]
+#[case("/books?isbn=978-1593278282")]
+#[case("/books?tags=not-a-tag-id")]
+#[case("/books?limit=101")]
+#[case("/books?limit=9223372036854775807")]
+#[tokio::test]
+async fn show_book_list_with_invalid_query_400(
+    mut fixture: registry::MockAppRegistryExt,
+    #[case] path: &str,
+) -> anyhow::Result<()> {
+    fixture
+        .expect_book_repository()
+        .returning(|| Arc::new(MockBookRepository::new()));
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::get(v1(path)).bearer().body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::BAD_REQUEST);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn show_book_list_with_cursor_200(
+    mut fixture: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let cursor = Cursor {
+        created_at: chrono::DateTime::from_timestamp_millis(1_700_000_000_123).unwrap(),
+        id: Uuid::new_v4(),
+    };
+    let next_cursor = Cursor {
+        created_at: chrono::DateTime::from_timestamp_millis(1_600_000_000_456).unwrap(),
+        id: Uuid::new_v4(),
+    };
+
+    // リクエストのカーソルがデコードされて渡され、次のカーソルがエンコードされて返ることを検証する
+    fixture.expect_book_repository().returning(move || {
+        let mut mock = MockBookRepository::new();
+        mock.expect_find_tag_facets().returning(|_| Ok(vec![]));
+        mock.expect_find_all()
+            .withf(move |opt| opt.cursor == Some(cursor))
+            .returning(move |opt| {
+                Ok(PaginatedList {
+                    total: 0,
+                    limit: opt.limit,
+                    offset: opt.offset,
+                    items: vec![],
+                    next_cursor: Some(next_cursor),
+                })
+            });
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let path = format!("/books?cursor={}", String::from(cursor));
+    let req = Request::get(v1(&path)).bearer().body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::OK);
+
+    let result = deserialize_json!(resp, PaginatedBookResponse);
+    assert_eq!(result.next_cursor, Some(next_cursor));
+
+    Ok(())
+}
+
+#[rstest]
+#[case("978-1-59327-828-1")]
+#[case("1-59327-828-4")]
+#[case("159327828 4")]
+#[tokio::test]
+async fn register_book_normalizes_isbn_201(
+    mut fixture: registry::MockAppRegistryExt,
+    #[case] isbn: &str,
+) -> anyhow::Result<()> {
+    // ISBN-10 やハイフン付きの表記でも、ハイフンなしの ISBN-13 に正規化して登録する
+    fixture.expect_book_repository().returning(|| {
+        let mut mock = MockBookRepository::new();
+        mock.expect_create()
+            .withf(|event, _| event.isbn.as_str() == "9781593278281")
+            .returning(|_, _| Ok(()));
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let body = format!(
+        r#"{{"title":"The Rust Programming Language","author":"Steve Klabnik","isbn":"{isbn}","description":""}}"#
+    );
+    let req = Request::post(v1("/books"))
+        .bearer()
+        .header("Content-Type", "application/json")
+        .body(Body::from(body))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::CREATED);
+
+    Ok(())
+}
+
+#[rstest]
+#[case("あ".repeat(255), axum::http::StatusCode::CREATED)]
+#[case("あ".repeat(256), axum::http::StatusCode::BAD_REQUEST)]
+#[tokio::test]
+async fn register_book_title_length_in_chars(
+    mut fixture: registry::MockAppRegistryExt,
+    #[case] title: String,
+    #[case] expected_status: axum::http::StatusCode,
+) -> anyhow::Result<()> {
+    // 文字数はデータベースの列と同じく、バイト数ではなく文字数で数える
+    fixture.expect_book_repository().returning(|| {
+        let mut mock = MockBookRepository::new();
+        mock.expect_create().returning(|_, _| Ok(()));
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let body = serde_json::json!({
+        "title": title,
+        "author": "著者",
+        "isbn": "9781593278281",
+        "description": "",
+    });
+    let req = Request::post(v1("/books"))
+        .bearer()
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), expected_status);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn update_book_with_too_long_title_400(
+    fixture: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let app: axum::Router = make_router(fixture);
+
+    let body = serde_json::json!({
+        "title": "あ".repeat(256),
+        "author": "著者",
+        "isbn": "9781593278281",
+        "description": "",
+    });
+    let req = Request::put(v1(&format!("/books/{}", BookId::new())))
+        .bearer()
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::BAD_REQUEST);
+
+    Ok(())
+}
+
+#[rstest]
+#[case("978-1593278282")]
+#[case("1-59327-828-5")]
+#[case("not an isbn")]
+#[case("")]
+#[tokio::test]
+async fn register_book_with_invalid_isbn_422(
+    mut fixture: registry::MockAppRegistryExt,
+    #[case] isbn: &str,
+) -> anyhow::Result<()> {
+    fixture
+        .expect_book_repository()
+        .returning(|| Arc::new(MockBookRepository::new()));
+
+    let app: axum::Router = make_router(fixture);
+
+    let body = format!(
+        r#"{{"title":"The Rust Programming Language","author":"Steve Klabnik","isbn":"{isbn}","description":""}}"#
+    );
+    let req = Request::post(v1("/books"))
+        .bearer()
+        .header("Content-Type", "application/json")
+        .body(Body::from(body))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::UNPROCESSABLE_ENTITY);
+
+    Ok(())
+}
+
+#[rstest]
+#[case(
+    "csv",
+    "title,author,isbn,description\n\
+     Book 2,Author,978-1593278281,\n\
+     Book 3,Author,978-1593278282,\n\
+     ,Author,9784798186016,\n"
+)]
+#[case(
+    "jsonl",
+    r#"
+{"title":"Book 2","author":"Author","isbn":"978-1593278281","description":""}
+{"title":"Book 3","author":"Author","isbn":"978-1593278282","description":""}
+{"title":"","author":"Author","isbn":"9784798186016","description":""}"#
+)]
+#[tokio::test]
+async fn import_books_reports_invalid_rows_200(
+    mut fixture: registry::MockAppRegistryExt,
+    #[case] format: &str,
+    #[case] body: &'static str,
+) -> anyhow::Result<()> {
+    // 検証に失敗した行は登録せず、行番号付きのエラーとして返す
+    fixture.expect_book_repository().returning(|| {
+        let mut mock = MockBookRepository::new();
+        mock.expect_import()
+            .withf(|event| {
+                event.dry_run
+                    && event.rows.len() == 1
+                    && event.rows[0].book.isbn.as_str() == "9781593278281"
+            })
+            .returning(|event| {
+                Ok(BookImportReport {
+                    imported: event
+                        .rows
+                        .iter()
+                        .map(|row| ImportedBook {
+                            line: row.line,
+                            book_id: None,
+                        })
+                        .collect(),
+                    ..Default::default()
+                })
+            });
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::post(v1(&format!("/books/import?format={format}&dryRun=true")))
+        .bearer()
+        .body(Body::from(body))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::OK);
+
+    let result = deserialize_json!(resp, BookImportResponse);
+    assert!(result.dry_run);
+    assert_eq!(result.imported.len(), 1);
+    assert_eq!(
+        result.errors.iter().map(|e| e.line).collect::<Vec<_>>(),
+        vec![3, 4]
+    );
+
+    Ok(())
+}
+
+#[rstest]
+#[case("csv", "text/csv; charset=utf-8", "\"Rust, Web\",")]
+#[case("jsonl", "application/jsonl", r#""title":"Rust, Web""#)]
+#[case("bibtex", "application/x-bibtex; charset=utf-8", "title = {Rust, Web}")]
+#[tokio::test]
+async fn export_books_200(
+    mut fixture: registry::MockAppRegistryExt,
+    #[case] format: &str,
+    #[case] content_type: &str,
+    #[case] expected: &str,
+) -> anyhow::Result<()> {
+    fixture.expect_book_repository().returning(|| {
+        let mut mock = MockBookRepository::new();
+        mock.expect_export_all().returning(|| {
+            let book = Book {
+                id: Uuid::new_v4(),
+                title: "Rust, Web".to_string(),
+                isbn: "9784798186016".parse().unwrap(),
+                author: "Yuki Toyoda".to_string(),
+                description: "".to_string(),
+                owner: BookOwner {
+                    id: UserId::new(),
+                    name: "Yuki Toyoda".to_string(),
+                },
+                copies: vec![],
+                tags: vec![],
+                archive: None,
+                version: 1,
+                cover: None,
+            };
+            Box::pin(tokio_stream::iter(vec![Ok(book)]))
+        });
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::get(v1(&format!("/books/export?format={format}")))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::OK);
+    assert_eq!(resp.headers()["content-type"], content_type);
+
+    let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await?;
+    let body = String::from_utf8(bytes.to_vec())?;
+    assert!(body.contains(expected));
+    if format == "csv" {
+        assert!(body.starts_with("id,title,author,isbn,"));
+        assert_eq!(body.lines().count(), 2);
+    }
+
+    Ok(())
+}
+
+#[rstest]
+#[case("9781593278281", axum::http::StatusCode::OK)]
+#[case("1-59327-828-4", axum::http::StatusCode::OK)]
+#[case("9784798186016", axum::http::StatusCode::NOT_FOUND)]
+#[case("not-an-isbn", axum::http::StatusCode::BAD_REQUEST)]
+#[tokio::test]
+async fn lookup_book_by_isbn(
+    mut fixture: registry::MockAppRegistryExt,
+    #[case] isbn: &str,
+    #[case] expected_status: axum::http::StatusCode,
+) -> anyhow::Result<()> {
+    // ISBN-13 に正規化した ISBN で書誌情報を検索する
+    fixture.expect_book_metadata_provider().returning(|| {
+        let mut mock = MockBookMetadataProvider::new();
+        mock.expect_find_by_isbn().returning(|isbn| {
+            Ok((isbn.as_str() == "9781593278281").then(|| BookMetadata {
+                isbn: isbn.clone(),
+                title: "The Rust Programming Language".to_string(),
+                author: "Steve Klabnik".to_string(),
+                description: "The official book".to_string(),
+            }))
+        });
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::get(v1(&format!("/books/lookup?isbn={isbn}")))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), expected_status);
+
+    if expected_status == axum::http::StatusCode::OK {
+        let result = deserialize_json!(resp, CreateBookRequest);
+        assert_eq!(result.isbn.as_str(), "9781593278281");
+        assert_eq!(result.title, "The Rust Programming Language");
+        assert!(result.barcode.is_none());
+    }
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn show_book_list_include_archived_by_non_admin_403(
+    mut fixture: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    fixture
+        .expect_book_repository()
+        .returning(|| Arc::new(MockBookRepository::new()));
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::get(v1("/books?includeArchived=true"))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::FORBIDDEN);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn delete_book_with_reason_204(
+    mut fixture: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let book_id = BookId::new();
+
+    // 削除の理由がアーカイブの理由として渡されることを検証する
+    fixture.expect_book_repository().returning(move || {
+        let mut mock = MockBookRepository::new();
+        mock.expect_delete()
+            .withf(move |event| {
+                event.book_id == book_id && event.reason.as_deref() == Some("破損のため廃棄")
+            })
+            .returning(|_| Ok(()));
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let path = format!(
+        "/books/{book_id}?reason=%E7%A0%B4%E6%90%8D%E3%81%AE%E3%81%9F%E3%82%81%E5%BB%83%E6%A3%84"
+    );
+    let req = Request::delete(v1(&path)).bearer().body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::NO_CONTENT);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn show_archived_book_by_non_owner_404(
+    mut fixture: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let book_id = Uuid::new_v4();
+
+    // アーカイブ済みの蔵書は、所有者と管理者以外には存在しないものとして扱う
+    fixture.expect_book_repository().returning(move || {
+        let mut mock = MockBookRepository::new();
+        mock.expect_find_by_id().returning(move |_| {
+            Ok(Some(Book {
+                id: book_id,
+                title: "RustによるWebアプリケーション開発".to_string(),
+                isbn: "9784798186016".parse().unwrap(),
+                author: "Yuki Toyoda".to_string(),
+                description: "".to_string(),
+                owner: BookOwner {
+                    id: UserId::new(),
+                    name: "Yuki Toyoda".to_string(),
+                },
+                copies: vec![],
+                tags: vec![],
+                archive: Some(BookArchive {
+                    archived_at: chrono::Utc::now(),
+                    reason: None,
+                }),
+                version: 1,
+                cover: None,
+            }))
+        });
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::get(v1(&format!("/books/{book_id}")))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn show_book_with_etag_200(mut fixture: registry::MockAppRegistryExt) -> anyhow::Result<()> {
+    let book_id = Uuid::new_v4();
+
+    fixture.expect_book_repository().returning(move || {
+        let mut mock = MockBookRepository::new();
+        mock.expect_find_by_id().returning(move |_| {
+            Ok(Some(Book {
+                id: book_id,
+                title: "RustによるWebアプリケーション開発".to_string(),
+                isbn: "9784798186016".parse().unwrap(),
+                author: "Yuki Toyoda".to_string(),
+                description: "".to_string(),
+                owner: BookOwner {
+                    id: UserId::new(),
+                    name: "Yuki Toyoda".to_string(),
+                },
+                copies: vec![],
+                tags: vec![],
+                archive: None,
+                version: 3,
+                cover: None,
+            }))
+        });
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::get(v1(&format!("/books/{book_id}")))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::OK);
+    assert_eq!(resp.headers()[axum::http::header::ETAG], "\"3\"");
+
+    let result = deserialize_json!(resp, BookResponse);
+    assert_eq!(result.version, 3);
+
+    Ok(())
+}
+
+#[rstest]
+#[case(None, Some(None), axum::http::StatusCode::NO_CONTENT)]
+#[case(Some("*"), Some(None), axum::http::StatusCode::NO_CONTENT)]
+#[case(Some("\"3\""), Some(Some(3)), axum::http::StatusCode::NO_CONTENT)]
+#[case(Some("W/\"3\""), None, axum::http::StatusCode::PRECONDITION_FAILED)]
+#[case(
+    Some("\"3\", \"4\""),
+    None,
+    axum::http::StatusCode::PRECONDITION_FAILED
+)]
+#[tokio::test]
+async fn update_book_with_if_match(
+    mut fixture: registry::MockAppRegistryExt,
+    #[case] if_match: Option<&'static str>,
+    #[case] expected_version: Option<Option<i64>>,
+    #[case] expected_status: axum::http::StatusCode,
+) -> anyhow::Result<()> {
+    let book_id = BookId::new();
+
+    // If-Match ヘッダのバージョンがリポジトリに渡されることを検証する。
+    // ヘッダの値が不正な場合はリポジトリを呼ばずに 412 を返す
+    fixture.expect_book_repository().returning(move || {
+        let mut mock = MockBookRepository::new();
+        if let Some(expected_version) = expected_version {
+            mock.expect_update()
+                .withf(move |event| {
+                    event.book_id == book_id && event.expected_version == expected_version
+                })
+                .returning(|_| Ok(()));
+        }
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let mut req = Request::put(v1(&format!("/books/{book_id}")))
+        .bearer()
+        .header("Content-Type", "application/json");
+    if let Some(if_match) = if_match {
+        req = req.header("If-Match", if_match);
+    }
+    let req = req.body(Body::from(
+        serde_json::json!({
+            "title": "RustによるWebアプリケーション開発",
+            "author": "Yuki Toyoda",
+            "isbn": "9784798186016",
+            "description": "",
+        })
+        .to_string(),
+    ))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), expected_status);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn patch_book_only_given_fields_204(
+    mut fixture: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let book_id = BookId::new();
+
+    // 省略した項目は None、null を指定したタグは空として渡されることを検証する
+    fixture.expect_book_repository().returning(move || {
+        let mut mock = MockBookRepository::new();
+        mock.expect_patch()
+            .withf(move |event| {
+                event.book_id == book_id
+                    && event.title.is_none()
+                    && event.author.is_none()
+                    && event.isbn.is_none()
+                    && event.description.as_deref() == Some("誤字を修正")
+                    && event.tags.as_deref() == Some(&[])
+            })
+            .returning(|_| Ok(()));
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::patch(v1(&format!("/books/{book_id}")))
+        .bearer()
+        .header("Content-Type", "application/merge-patch+json")
+        .body(Body::from(
+            serde_json::json!({ "description": "誤字を修正", "tags": null }).to_string(),
+        ))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::NO_CONTENT);
+
+    Ok(())
+}
+
+#[rstest]
+#[case(serde_json::json!({ "title": null }), axum::http::StatusCode::UNPROCESSABLE_ENTITY)]
+#[case(serde_json::json!({ "title": "" }), axum::http::StatusCode::BAD_REQUEST)]
+#[case(serde_json::json!({ "title": "あ".repeat(256) }), axum::http::StatusCode::BAD_REQUEST)]
+#[case(serde_json::json!({ "description": "あ".repeat(1025) }), axum::http::StatusCode::BAD_REQUEST)]
+#[case(serde_json::json!({ "isbn": "9784798186017" }), axum::http::StatusCode::UNPROCESSABLE_ENTITY)]
+#[tokio::test]
+async fn patch_book_with_invalid_body(
+    fixture: registry::MockAppRegistryExt,
+    #[case] body: serde_json::Value,
+    #[case] expected_status: axum::http::StatusCode,
+) -> anyhow::Result<()> {
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::patch(v1(&format!("/books/{}", BookId::new())))
+        .bearer()
+        .header("Content-Type", "application/merge-patch+json")
+        .body(Body::from(body.to_string()))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), expected_status);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn transfer_book_owner_204(mut fixture: registry::MockAppRegistryExt) -> anyhow::Result<()> {
+    let book_id = BookId::new();
+    let new_owner = UserId::new();
+
+    // リクエストの新しい所有者がリポジトリに渡されることを検証する
+    fixture.expect_book_repository().returning(move || {
+        let mut mock = MockBookRepository::new();
+        mock.expect_transfer_owner()
+            .withf(move |event| {
+                event.book_id == book_id && event.new_owner == new_owner && !event.is_admin
+            })
+            .returning(|_| Ok(()));
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::put(v1(&format!("/books/{book_id}/owner")))
+        .bearer()
+        .header("Content-Type", "application/json")
+        .body(Body::from(
+            serde_json::json!({ "ownerId": new_owner }).to_string(),
+        ))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::NO_CONTENT);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn show_book_owner_history_200(
+    mut fixture: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let book_id = BookId::new();
+    let to = UserId::new();
+
+    fixture.expect_book_repository().returning(move || {
+        let mut mock = MockBookRepository::new();
+        mock.expect_find_owner_transfers()
+            .withf(move |id| *id == book_id)
+            .returning(move |_| {
+                Ok(vec![BookOwnerTransfer {
+                    from: BookOwner::anonymized(),
+                    to: BookOwner {
+                        id: to,
+                        name: "Yuki Toyoda".to_string(),
+                    },
+                    transferred_by: BookOwner {
+                        id: to,
+                        name: "Yuki Toyoda".to_string(),
+                    },
+                    transferred_at: chrono::Utc::now(),
+                }])
+            });
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::get(v1(&format!("/books/{book_id}/owner-history")))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::OK);
+
+    let result = deserialize_json!(resp, BookOwnerTransfersResponse);
+    assert_eq!(result.items.len(), 1);
+    assert_eq!(result.items[0].from.name, "削除されたユーザー");
+    assert_eq!(result.items[0].to.id, to);
+
+    Ok(())
+}
+
+#[rstest]
+#[case("LIB-0001", axum::http::StatusCode::CREATED)]
+#[case("BC0000001", axum::http::StatusCode::CREATED)]
+#[case("BC00000001", axum::http::StatusCode::BAD_REQUEST)]
+#[case("", axum::http::StatusCode::BAD_REQUEST)]
+#[tokio::test]
+async fn create_book_copy_with_barcode(
+    mut fixture: registry::MockAppRegistryExt,
+    #[case] barcode: &str,
+    #[case] expected_status: axum::http::StatusCode,
+) -> anyhow::Result<()> {
+    // 採番するバーコードと同じ形式のバーコードは、採番したものと重複しないよう受け付けない
+    fixture.expect_book_repository().returning(|| {
+        let mut mock = MockBookRepository::new();
+        mock.expect_create_copy().returning(|_| Ok(()));
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let body = serde_json::json!({ "barcode": barcode });
+    let req = Request::post(v1(&format!("/books/{}/copies", BookId::new())))
+        .bearer()
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), expected_status);
+
+    Ok(())
+}
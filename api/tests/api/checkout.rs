@@ -0,0 +1,117 @@
+use std::sync::Arc;
+
+use axum::{body::Body, http::Request};
+use rstest::rstest;
+use tower::ServiceExt;
+
+use crate::{
+    deserialize_json,
+    helper::{TestRequestExt, fixture, fixture_admin, make_router, v1},
+};
+use kernel::{
+    model::id::{BookId, UserId},
+    repository::checkout::MockCheckoutRepository,
+};
+use shared::error::AppError;
+
+#[rstest]
+#[tokio::test]
+async fn checkout_book_over_limit_409(
+    mut fixture: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    fixture.expect_checkout_repository().returning(|| {
+        let mut mock = MockCheckoutRepository::new();
+        mock.expect_create()
+            .returning(|_| Err(AppError::CheckoutLimitExceeded { limit: 5 }));
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let path = format!("/books/{}/checkouts", BookId::new());
+    let req = Request::post(v1(&path)).bearer().body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::CONFLICT);
+
+    // フロントエンドが理由を表示できるよう、エラーの種類と上限値が返る
+    let result = deserialize_json!(resp, serde_json::Value);
+    assert_eq!(result["code"], "CHECKOUT_LIMIT_EXCEEDED");
+    assert_eq!(result["limit"], 5);
+    assert!(result["message"].is_string());
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn checkout_book_on_behalf_by_admin_201(
+    mut fixture_admin: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let borrower = UserId::new();
+
+    // 借りるユーザーは指定したユーザー、操作したユーザーはログイン中の管理者になる
+    fixture_admin
+        .expect_checkout_repository()
+        .returning(move || {
+            let mut mock = MockCheckoutRepository::new();
+            mock.expect_create()
+                .withf(move |event| {
+                    event.checked_out_by == borrower && event.operated_by != borrower
+                })
+                .returning(|_| Ok(()));
+            Arc::new(mock)
+        });
+
+    let app: axum::Router = make_router(fixture_admin);
+
+    let path = format!("/books/{}/checkouts", BookId::new());
+    let req = Request::post(v1(&path))
+        .bearer()
+        .header("Content-Type", "application/json")
+        .body(Body::from(format!(r#"{{"userId":"{}"}}"#, borrower)))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::CREATED);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn checkout_book_on_behalf_by_user_403(
+    fixture: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let app: axum::Router = make_router(fixture);
+
+    let path = format!("/books/{}/checkouts", BookId::new());
+    let req = Request::post(v1(&path))
+        .bearer()
+        .header("Content-Type", "application/json")
+        .body(Body::from(format!(r#"{{"userId":"{}"}}"#, UserId::new())))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::FORBIDDEN);
+
+    Ok(())
+}
+
+#[rstest]
+#[case("/books/checkouts?limit=-1")]
+#[case("/books/checkouts?limit=101")]
+#[case("/books/checkouts?limit=9223372036854775807")]
+#[tokio::test]
+async fn show_checked_out_list_with_invalid_limit_400(
+    mut fixture: registry::MockAppRegistryExt,
+    #[case] path: &str,
+) -> anyhow::Result<()> {
+    // 上限を超える件数はリポジトリに渡る前に弾かれる
+    fixture
+        .expect_checkout_repository()
+        .returning(|| Arc::new(MockCheckoutRepository::new()));
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::get(v1(path)).bearer().body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::BAD_REQUEST);
+
+    Ok(())
+}
@@ -0,0 +1,368 @@
+use std::{
+    io::Cursor,
+    sync::{Arc, Mutex},
+};
+
+use axum::{body::Body, http::Request};
+use rstest::rstest;
+use tower::ServiceExt;
+
+use crate::{
+    deserialize_json,
+    helper::{TestRequestExt, fixture, fixture_admin, make_router, v1},
+};
+use api::model::cover::BookCoverResponse;
+use kernel::{
+    model::{
+        book::{Book, BookArchive, BookCover},
+        id::{BookId, CoverId, UserId},
+        user::BookOwner,
+    },
+    repository::{blob_storage::MockBlobStorage, book::MockBookRepository},
+};
+use shared::error::AppError;
+
+fn png(width: u32, height: u32) -> Vec<u8> {
+    let mut data = Vec::new();
+    image::RgbImage::new(width, height)
+        .write_to(&mut Cursor::new(&mut data), image::ImageFormat::Png)
+        .unwrap();
+    data
+}
+
+fn book(book_id: BookId, cover: Option<BookCover>) -> Book {
+    Book {
+        id: book_id.raw(),
+        title: "RustによるWebアプリケーション開発".to_string(),
+        isbn: "9784798186016".parse().unwrap(),
+        author: "Yuki Toyoda".to_string(),
+        description: "".to_string(),
+        owner: BookOwner {
+            id: UserId::new(),
+            name: "Yuki Toyoda".to_string(),
+        },
+        copies: vec![],
+        tags: vec![],
+        archive: None,
+        version: 1,
+        cover,
+    }
+}
+
+#[rstest]
+#[tokio::test]
+async fn upload_book_cover_200(
+    mut fixture_admin: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let book_id = BookId::new();
+    let previous = BookCover {
+        id: CoverId::new(),
+        content_type: "image/jpeg".into(),
+    };
+
+    // 元の画像とサムネイルを保存してから書影を登録し、置き換えた書影の画像を削除することを検証する
+    let saved = Arc::new(Mutex::new(Vec::new()));
+    let saved_in_mock = saved.clone();
+    let deleted = Arc::new(Mutex::new(Vec::new()));
+    let deleted_in_mock = deleted.clone();
+    fixture_admin.expect_blob_storage().returning(move || {
+        let mut mock = MockBlobStorage::new();
+        let saved = saved_in_mock.clone();
+        mock.expect_put().returning(move |key, data| {
+            saved.lock().unwrap().push((key.to_string(), data));
+            Ok(())
+        });
+        let deleted = deleted_in_mock.clone();
+        mock.expect_delete().returning(move |key| {
+            deleted.lock().unwrap().push(key.to_string());
+            Ok(())
+        });
+        Arc::new(mock)
+    });
+    let previous_in_mock = previous.clone();
+    fixture_admin.expect_book_repository().returning(move || {
+        let mut mock = MockBookRepository::new();
+        let previous = previous_in_mock.clone();
+        mock.expect_find_by_id()
+            .returning(|id| Ok(Some(book(id, None))));
+        mock.expect_update_cover()
+            .withf(move |event| event.book_id == book_id && event.cover.content_type == "image/png")
+            .returning(move |_| Ok(Some(previous.clone())));
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture_admin);
+
+    let req = Request::put(v1(&format!("/books/{book_id}/cover")))
+        .bearer()
+        .header("Content-Type", "image/png")
+        .body(Body::from(png(800, 1200)))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::OK);
+
+    let result = deserialize_json!(resp, BookCoverResponse);
+    assert!(
+        result
+            .cover_url
+            .starts_with(&format!("/api/v1/books/{}/cover?v=", book_id.raw()))
+    );
+
+    let saved = saved.lock().unwrap();
+    assert_eq!(saved.len(), 2);
+    let (_, thumbnail) = saved
+        .iter()
+        .find(|(key, _)| key.ends_with("_thumbnail"))
+        .unwrap();
+    let thumbnail = image::load_from_memory(thumbnail)?;
+    assert_eq!((thumbnail.width(), thumbnail.height()), (171, 256));
+
+    let mut deleted = deleted.lock().unwrap().clone();
+    deleted.sort();
+    assert_eq!(deleted, vec![previous.key(), previous.thumbnail_key()]);
+
+    Ok(())
+}
+
+#[rstest]
+#[case(
+    "text/plain",
+    png(10, 10),
+    axum::http::StatusCode::UNSUPPORTED_MEDIA_TYPE
+)]
+#[case(
+    "image/jpeg",
+    png(10, 10),
+    axum::http::StatusCode::UNPROCESSABLE_ENTITY
+)]
+#[case("image/png", b"not an image".to_vec(), axum::http::StatusCode::UNPROCESSABLE_ENTITY)]
+#[case(
+    "image/png",
+    png(5000, 10),
+    axum::http::StatusCode::UNPROCESSABLE_ENTITY
+)]
+// リクエストボディの上限を超える画像は、ハンドラを実行する前に 413 を返す
+#[case("image/png", vec![0; 6 * 1024 * 1024], axum::http::StatusCode::PAYLOAD_TOO_LARGE)]
+#[tokio::test]
+async fn upload_book_cover_with_invalid_image(
+    mut fixture_admin: registry::MockAppRegistryExt,
+    #[case] content_type: &str,
+    #[case] body: Vec<u8>,
+    #[case] expected_status: axum::http::StatusCode,
+) -> anyhow::Result<()> {
+    // 画像を検証できない場合は、ファイルを保存せずにエラーを返す
+    fixture_admin.expect_book_repository().returning(|| {
+        let mut mock = MockBookRepository::new();
+        mock.expect_find_by_id()
+            .returning(|id| Ok(Some(book(id, None))));
+        Arc::new(mock)
+    });
+    fixture_admin
+        .expect_blob_storage()
+        .returning(|| Arc::new(MockBlobStorage::new()));
+
+    let app: axum::Router = make_router(fixture_admin);
+
+    let req = Request::put(v1(&format!("/books/{}/cover", BookId::new())))
+        .bearer()
+        .header("Content-Type", content_type)
+        .body(Body::from(body))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), expected_status);
+
+    Ok(())
+}
+
+#[rstest]
+#[case(false, false)]
+#[case(true, false)]
+#[case(true, true)]
+#[tokio::test]
+async fn upload_book_cover_without_permission_404(
+    mut fixture: registry::MockAppRegistryExt,
+    #[case] exists: bool,
+    #[case] archived: bool,
+) -> anyhow::Result<()> {
+    // 書影を登録できない蔵書の場合は、画像を読み込んだり保存したりせずに 404 を返す
+    fixture.expect_book_repository().returning(move || {
+        let mut mock = MockBookRepository::new();
+        mock.expect_find_by_id().returning(move |id| {
+            let mut book = book(id, None);
+            if archived {
+                book.archive = Some(BookArchive {
+                    archived_at: chrono::Utc::now(),
+                    reason: None,
+                });
+            }
+            Ok(exists.then_some(book))
+        });
+        mock.expect_update_cover().never();
+        Arc::new(mock)
+    });
+    let mut storage = MockBlobStorage::new();
+    storage.expect_put().never();
+    let storage = Arc::new(storage);
+    fixture
+        .expect_blob_storage()
+        .returning(move || storage.clone());
+
+    let app: axum::Router = make_router(fixture);
+
+    // 画像として読み込めない内容でも、蔵書を確認した時点で 404 を返す
+    let req = Request::put(v1(&format!("/books/{}/cover", BookId::new())))
+        .bearer()
+        .header("Content-Type", "image/png")
+        .body(Body::from(b"not an image".to_vec()))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn upload_book_cover_failed_to_update_404(
+    mut fixture_admin: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    // 書影を登録できなかった場合は、保存した画像を削除する
+    let mut storage = MockBlobStorage::new();
+    storage.expect_put().times(2).returning(|_, _| Ok(()));
+    storage.expect_delete().times(2).returning(|_| Ok(()));
+    let storage = Arc::new(storage);
+    fixture_admin
+        .expect_blob_storage()
+        .returning(move || storage.clone());
+    fixture_admin.expect_book_repository().returning(|| {
+        let mut mock = MockBookRepository::new();
+        mock.expect_find_by_id()
+            .returning(|id| Ok(Some(book(id, None))));
+        mock.expect_update_cover()
+            .returning(|_| Err(AppError::EntityNotFound("Specified book not found".into())));
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture_admin);
+
+    let req = Request::put(v1(&format!("/books/{}/cover", BookId::new())))
+        .bearer()
+        .header("Content-Type", "image/png")
+        .body(Body::from(png(10, 10)))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[rstest]
+#[case(true, "public, max-age=31536000, immutable")]
+#[case(false, "public, max-age=300")]
+#[tokio::test]
+async fn show_book_cover_200(
+    mut fixture: registry::MockAppRegistryExt,
+    #[case] versioned: bool,
+    #[case] expected_cache_control: &str,
+) -> anyhow::Result<()> {
+    let book_id = BookId::new();
+    let cover = BookCover {
+        id: CoverId::new(),
+        content_type: "image/webp".into(),
+    };
+
+    let cover_in_mock = cover.clone();
+    fixture.expect_book_repository().returning(move || {
+        let mut mock = MockBookRepository::new();
+        let cover = cover_in_mock.clone();
+        mock.expect_find_by_id()
+            .returning(move |id| Ok(Some(book(id, Some(cover.clone())))));
+        Arc::new(mock)
+    });
+    let key = cover.key();
+    fixture.expect_blob_storage().returning(move || {
+        let mut mock = MockBlobStorage::new();
+        let key = key.clone();
+        mock.expect_get()
+            .withf(move |k| k == key)
+            .returning(|_| Ok(Some(b"cover".to_vec())));
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    // 書影は認証なしで取得できる
+    // 書影の ID を含む URL だけを長期間キャッシュさせる
+    let path = if versioned {
+        format!("/books/{book_id}/cover?v={}", cover.id)
+    } else {
+        format!("/books/{book_id}/cover")
+    };
+    let req = Request::get(v1(&path)).body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::OK);
+    assert_eq!(
+        resp.headers()[axum::http::header::CONTENT_TYPE],
+        "image/webp"
+    );
+    assert_eq!(
+        resp.headers()[axum::http::header::CACHE_CONTROL],
+        expected_cache_control
+    );
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn show_book_cover_without_cover_404(
+    mut fixture: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    fixture.expect_book_repository().returning(|| {
+        let mut mock = MockBookRepository::new();
+        mock.expect_find_by_id()
+            .returning(|id| Ok(Some(book(id, None))));
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::get(v1(&format!("/books/{}/cover/thumbnail", BookId::new())))
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[rstest]
+#[case("/cover")]
+#[case("/cover/thumbnail")]
+#[tokio::test]
+async fn show_book_cover_with_stale_version_404(
+    mut fixture: registry::MockAppRegistryExt,
+    #[case] suffix: &str,
+) -> anyhow::Result<()> {
+    // 置き換える前の書影の URL では、現在の書影を返さない
+    fixture.expect_book_repository().returning(|| {
+        let mut mock = MockBookRepository::new();
+        mock.expect_find_by_id().returning(|id| {
+            Ok(Some(book(
+                id,
+                Some(BookCover {
+                    id: CoverId::new(),
+                    content_type: "image/png".into(),
+                }),
+            )))
+        });
+        Arc::new(mock)
+    });
+    fixture
+        .expect_blob_storage()
+        .returning(|| Arc::new(MockBlobStorage::new()));
+
+    let app: axum::Router = make_router(fixture);
+
+    let path = format!("/books/{}{suffix}?v={}", BookId::new(), CoverId::new());
+    let req = Request::get(v1(&path)).body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::NOT_FOUND);
+
+    Ok(())
+}
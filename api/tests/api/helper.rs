@@ -3,7 +3,12 @@ use std::sync::Arc;
 use api::route::{auth, v1};
 use axum::{Router, http::request::Builder};
 use kernel::{
-    model::{auth::AccessToken, id::UserId, role::Role, user::User},
+    model::{
+        auth::{AccessToken, AuthTokens, RefreshToken},
+        id::UserId,
+        role::Role,
+        user::User,
+    },
     repository::{auth::MockAuthRepository, user::MockUserRepository},
 };
 use registry::MockAppRegistryExt;
@@ -34,31 +39,50 @@ pub fn fixture_auth(mut fixture_registry: MockAppRegistryExt) -> MockAppRegistry
             .returning(|_| Ok(Some(UserId::new())));
         mock_auth_repository
             .expect_verify_user()
-            .returning(|_, _| Ok(UserId::new()));
+            .returning(|_| Ok(UserId::new()));
         mock_auth_repository
             .expect_create_token()
-            .returning(|_| Ok(AccessToken("dummy".into())));
+            .returning(|event| {
+                Ok(AuthTokens {
+                    user_id: event.user_id,
+                    access_token: AccessToken(event.access_token),
+                    refresh_token: RefreshToken(event.refresh_token),
+                })
+            });
         Arc::new(mock_auth_repository)
     });
     fixture_registry
 }
 
+fn mock_user_repository(role: Role) -> MockUserRepository {
+    let mut mock_user_repository = MockUserRepository::new();
+    mock_user_repository
+        .expect_find_current_user()
+        .returning(move |id| {
+            Ok(Some(User {
+                id,
+                name: "dummy-user".to_string(),
+                email: "dummy@example.com".to_string(),
+                role: role.clone(),
+            }))
+        });
+    mock_user_repository
+}
+
 #[fixture]
 pub fn fixture(mut fixture_auth: MockAppRegistryExt) -> MockAppRegistryExt {
-    fixture_auth.expect_user_repository().returning(|| {
-        let mut mock_user_repository = MockUserRepository::new();
-        mock_user_repository
-            .expect_find_current_user()
-            .returning(|id| {
-                Ok(Some(User {
-                    id,
-                    name: "dummy-user".to_string(),
-                    email: "dummy@example.com".to_string(),
-                    role: Role::User,
-                }))
-            });
-        Arc::new(mock_user_repository)
-    });
+    fixture_auth
+        .expect_user_repository()
+        .returning(|| Arc::new(mock_user_repository(Role::User)));
+    fixture_auth
+}
+
+// 管理者としてログインしている状態のフィクスチャ
+#[fixture]
+pub fn fixture_admin(mut fixture_auth: MockAppRegistryExt) -> MockAppRegistryExt {
+    fixture_auth
+        .expect_user_repository()
+        .returning(|| Arc::new(mock_user_repository(Role::Admin)));
     fixture_auth
 }
 
@@ -1,2 +1,8 @@
+mod auth;
 mod book;
+mod checkout;
+mod cover;
 mod helper;
+mod session;
+mod tag;
+mod user;
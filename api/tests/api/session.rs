@@ -0,0 +1,242 @@
+use std::sync::Arc;
+
+use axum::{body::Body, http::Request};
+use chrono::{Duration, Utc};
+use kernel::{
+    model::{
+        auth::Session,
+        id::{SessionId, UserId},
+        role::Role,
+        user::User,
+    },
+    repository::{auth::MockAuthRepository, user::MockUserRepository},
+};
+use rstest::rstest;
+use shared::error::AppError;
+use tower::ServiceExt;
+
+use crate::{
+    deserialize_json,
+    helper::{TestRequestExt, fixture_registry, make_router, v1},
+};
+use api::model::session::SessionsResponse;
+
+// 指定したユーザー ID・ロールでログインしている状態にする
+// セッションの操作は setup で設定する
+fn login(
+    registry: &mut registry::MockAppRegistryExt,
+    user_id: UserId,
+    role: Role,
+    setup: impl Fn(&mut MockAuthRepository) + Send + Sync + 'static,
+) {
+    registry.expect_user_repository().returning(move || {
+        let mut mock = MockUserRepository::new();
+        let role = role.clone();
+        mock.expect_find_current_user().returning(move |id| {
+            Ok(Some(User {
+                id,
+                name: "dummy-user".to_string(),
+                email: "dummy@example.com".to_string(),
+                role: role.clone(),
+            }))
+        });
+        Arc::new(mock)
+    });
+    registry.expect_auth_repository().returning(move || {
+        let mut mock = MockAuthRepository::new();
+        mock.expect_fetch_user_id_from_token()
+            .returning(move |_| Ok(Some(user_id)));
+        setup(&mut mock);
+        Arc::new(mock)
+    });
+}
+
+fn session(id: SessionId, minutes_ago: i64) -> Session {
+    let now = Utc::now();
+    Session {
+        id,
+        user_agent: Some("test-agent".into()),
+        ip_address: Some("203.0.113.1".into()),
+        created_at: now - Duration::days(1),
+        last_used_at: now - Duration::minutes(minutes_ago),
+    }
+}
+
+#[rstest]
+#[tokio::test]
+async fn get_my_sessions_200(
+    mut fixture_registry: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let user_id = UserId::new();
+    let current = SessionId::new();
+    let other = SessionId::new();
+
+    login(&mut fixture_registry, user_id, Role::User, move |mock| {
+        mock.expect_find_session_id()
+            .returning(move |_| Ok(Some(current)));
+        mock.expect_find_sessions()
+            .withf(move |id| *id == user_id)
+            .returning(move |_| Ok(vec![session(current, 0), session(other, 10)]));
+    });
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::get(v1("/users/me/sessions"))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::OK);
+
+    // リクエストに使ったセッションには current が付く
+    let result = deserialize_json!(resp, SessionsResponse);
+    assert_eq!(result.items.len(), 2);
+    assert_eq!(result.items[0].id, current);
+    assert!(result.items[0].current);
+    assert_eq!(result.items[1].id, other);
+    assert!(!result.items[1].current);
+    assert_eq!(result.items[1].user_agent.as_deref(), Some("test-agent"));
+    assert_eq!(result.items[1].ip_address.as_deref(), Some("203.0.113.1"));
+
+    Ok(())
+}
+
+#[rstest]
+#[case(true, axum::http::StatusCode::NO_CONTENT)]
+#[case(false, axum::http::StatusCode::NOT_FOUND)]
+#[tokio::test]
+async fn delete_my_session(
+    mut fixture_registry: registry::MockAppRegistryExt,
+    #[case] found: bool,
+    #[case] expected_status: axum::http::StatusCode,
+) -> anyhow::Result<()> {
+    let user_id = UserId::new();
+    let session_id = SessionId::new();
+
+    // 他のユーザーのセッションは見つからない扱いになる
+    login(&mut fixture_registry, user_id, Role::User, move |mock| {
+        mock.expect_delete_session()
+            .withf(move |uid, sid| *uid == user_id && *sid == session_id)
+            .returning(move |_, _| {
+                if found {
+                    Ok(())
+                } else {
+                    Err(AppError::EntityNotFound(
+                        "Specified session not found".into(),
+                    ))
+                }
+            });
+    });
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::delete(v1(&format!("/users/me/sessions/{session_id}")))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), expected_status);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn delete_my_sessions_204(
+    mut fixture_registry: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let user_id = UserId::new();
+
+    login(&mut fixture_registry, user_id, Role::User, move |mock| {
+        mock.expect_delete_sessions()
+            .withf(move |id| *id == user_id)
+            .returning(|_| Ok(()));
+    });
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::delete(v1("/users/me/sessions"))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::NO_CONTENT);
+
+    Ok(())
+}
+
+#[rstest]
+#[case(Role::Admin, axum::http::StatusCode::OK)]
+#[case(Role::User, axum::http::StatusCode::FORBIDDEN)]
+#[tokio::test]
+async fn get_user_sessions(
+    mut fixture_registry: registry::MockAppRegistryExt,
+    #[case] role: Role,
+    #[case] expected_status: axum::http::StatusCode,
+) -> anyhow::Result<()> {
+    let target = UserId::new();
+    let session_id = SessionId::new();
+
+    login(&mut fixture_registry, UserId::new(), role, move |mock| {
+        mock.expect_find_session_id()
+            .returning(|_| Ok(Some(SessionId::new())));
+        mock.expect_find_sessions()
+            .withf(move |id| *id == target)
+            .returning(move |_| Ok(vec![session(session_id, 0)]));
+    });
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::get(v1(&format!("/users/{target}/sessions")))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), expected_status);
+
+    if expected_status == axum::http::StatusCode::OK {
+        let result = deserialize_json!(resp, SessionsResponse);
+        assert_eq!(result.items.len(), 1);
+        assert_eq!(result.items[0].id, session_id);
+        assert!(!result.items[0].current);
+    }
+
+    Ok(())
+}
+
+#[rstest]
+#[case(Role::Admin, axum::http::StatusCode::NO_CONTENT)]
+#[case(Role::User, axum::http::StatusCode::FORBIDDEN)]
+#[tokio::test]
+async fn delete_user_sessions(
+    mut fixture_registry: registry::MockAppRegistryExt,
+    #[case] role: Role,
+    #[case] expected_status: axum::http::StatusCode,
+) -> anyhow::Result<()> {
+    let target = UserId::new();
+
+    // 管理者はユーザーをすべての端末からログアウトさせられる
+    login(&mut fixture_registry, UserId::new(), role, move |mock| {
+        mock.expect_delete_sessions()
+            .withf(move |id| *id == target)
+            .returning(|_| Ok(()));
+        mock.expect_delete_session()
+            .withf(move |id, _| *id == target)
+            .returning(|_, _| Ok(()));
+    });
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::delete(v1(&format!("/users/{target}/sessions")))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.clone().oneshot(req).await?;
+    assert_eq!(resp.status(), expected_status);
+
+    let req = Request::delete(v1(&format!(
+        "/users/{target}/sessions/{}",
+        SessionId::new()
+    )))
+    .bearer()
+    .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), expected_status);
+
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use std::sync::Arc;
+
+use axum::{body::Body, http::Request};
+use rstest::rstest;
+use tower::ServiceExt;
+
+use crate::{
+    deserialize_json,
+    helper::{TestRequestExt, fixture, fixture_admin, make_router, v1},
+};
+use api::model::tag::{TagResponse, TagsResponse};
+use kernel::{
+    model::{id::TagId, tag::Tag},
+    repository::tag::MockTagRepository,
+};
+
+#[rstest]
+#[tokio::test]
+async fn list_tags_200(mut fixture: registry::MockAppRegistryExt) -> anyhow::Result<()> {
+    fixture.expect_tag_repository().returning(|| {
+        let mut mock = MockTagRepository::new();
+        mock.expect_find_all().returning(|| {
+            Ok(vec![Tag {
+                id: TagId::new(),
+                name: "Rust".to_string(),
+            }])
+        });
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::get(v1("/tags")).bearer().body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::OK);
+
+    let result = deserialize_json!(resp, TagsResponse);
+    assert_eq!(result.items.len(), 1);
+    assert_eq!(result.items[0].name, "Rust");
+
+    Ok(())
+}
+
+#[rstest]
+#[case("Rust".to_string())]
+// タグ名の長さはバイト数ではなく文字数で数える
+#[case("あ".repeat(64))]
+#[tokio::test]
+async fn register_tag_201(
+    mut fixture_admin: registry::MockAppRegistryExt,
+    #[case] name: String,
+) -> anyhow::Result<()> {
+    let expected = name.clone();
+    fixture_admin.expect_tag_repository().returning(move || {
+        let mut mock = MockTagRepository::new();
+        let expected = expected.clone();
+        mock.expect_create()
+            .withf(move |event| event.name == expected)
+            .returning(|event| {
+                Ok(Tag {
+                    id: TagId::new(),
+                    name: event.name,
+                })
+            });
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture_admin);
+
+    let req = Request::post(v1("/tags"))
+        .bearer()
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::json!({ "name": name }).to_string()))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::CREATED);
+
+    let result = deserialize_json!(resp, TagResponse);
+    assert_eq!(result.name, name);
+
+    Ok(())
+}
+
+#[rstest]
+#[case(Request::post(v1("/tags")))]
+#[case(Request::put(v1(&format!("/tags/{}", TagId::new()))))]
+#[tokio::test]
+async fn modify_tag_by_non_admin_403(
+    mut fixture: registry::MockAppRegistryExt,
+    #[case] builder: axum::http::request::Builder,
+) -> anyhow::Result<()> {
+    // 管理者でない場合はリポジトリを呼び出さずに拒否する
+    fixture
+        .expect_tag_repository()
+        .returning(|| Arc::new(MockTagRepository::new()));
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = builder
+        .bearer()
+        .header("Content-Type", "application/json")
+        .body(Body::from(r#"{"name":"Rust"}"#))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::FORBIDDEN);
+
+    Ok(())
+}
@@ -0,0 +1,314 @@
+use std::sync::{Arc, Mutex};
+
+use axum::{body::Body, http::Request};
+use rstest::rstest;
+use tower::ServiceExt;
+
+use crate::{
+    deserialize_json,
+    helper::{
+        TestRequestExt, fixture, fixture_admin, fixture_auth, fixture_registry, make_router, v1,
+    },
+};
+use api::model::user::UserResponse;
+use kernel::{
+    model::{
+        id::{SessionId, UserId},
+        role::Role,
+        user::{User, event::DeleteUserBooks},
+    },
+    repository::{auth::MockAuthRepository, user::MockUserRepository},
+};
+
+// 終了させたセッション（ユーザー ID と、終了させずに残したセッション）の記録
+type RevokedSessions = Arc<Mutex<Vec<(UserId, Option<SessionId>)>>>;
+
+// 終了させたセッションを記録する認証リポジトリのモックを用意する
+// ログイン中のユーザーのセッションは current になる
+fn mock_auth_repository(
+    current: SessionId,
+    revoked: RevokedSessions,
+) -> impl Fn() -> Arc<dyn kernel::repository::auth::AuthRepository> {
+    move || {
+        let mut mock = MockAuthRepository::new();
+        mock.expect_fetch_user_id_from_token()
+            .returning(|_| Ok(Some(UserId::new())));
+        mock.expect_find_session_id()
+            .returning(move |_| Ok(Some(current)));
+        let revoked_all = revoked.clone();
+        mock.expect_delete_sessions().returning(move |user_id| {
+            revoked_all.lock().unwrap().push((user_id, None));
+            Ok(())
+        });
+        let revoked_others = revoked.clone();
+        mock.expect_delete_other_sessions()
+            .returning(move |user_id, keep| {
+                revoked_others.lock().unwrap().push((user_id, Some(keep)));
+                Ok(())
+            });
+        Arc::new(mock)
+    }
+}
+
+#[rstest]
+#[tokio::test]
+async fn delete_user_with_transfer_200(
+    mut fixture_registry: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let user_id = UserId::new();
+    let successor = UserId::new();
+    let revoked = Arc::new(Mutex::new(Vec::new()));
+    fixture_registry
+        .expect_auth_repository()
+        .returning(mock_auth_repository(SessionId::new(), revoked.clone()));
+
+    // クエリパラメータが蔵書の引き継ぎ先と返却の指定として渡されることを検証する
+    // 管理者としてログインしている状態にする
+    fixture_registry
+        .expect_user_repository()
+        .returning(move || {
+            let mut mock = MockUserRepository::new();
+            mock.expect_find_current_user().returning(|id| {
+                Ok(Some(User {
+                    id,
+                    name: "admin".into(),
+                    email: "admin@example.com".into(),
+                    role: Role::Admin,
+                }))
+            });
+            mock.expect_delete()
+                .withf(move |event| {
+                    event.user_id == user_id
+                        && event.books == Some(DeleteUserBooks::TransferTo(successor))
+                        && event.force_return
+                })
+                .returning(|_| Ok(()));
+            Arc::new(mock)
+        });
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let path = format!("/users/{user_id}?transferBooksTo={successor}&forceReturn=true");
+    let req = Request::delete(v1(&path)).bearer().body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::OK);
+
+    // 削除したユーザーのセッションはすべて終了する
+    assert_eq!(*revoked.lock().unwrap(), vec![(user_id, None)]);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn delete_user_with_conflicting_options_422(
+    fixture_admin: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let app: axum::Router = make_router(fixture_admin);
+
+    let path = format!(
+        "/users/{}?transferBooksTo={}&archiveBooks=true",
+        UserId::new(),
+        UserId::new()
+    );
+    let req = Request::delete(v1(&path)).bearer().body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::UNPROCESSABLE_ENTITY);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn patch_current_user_200(
+    mut fixture_auth: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    // 省略した項目は変更しないことと、更新後のユーザー情報を返すことを検証する
+    fixture_auth.expect_user_repository().returning(|| {
+        let mut mock = MockUserRepository::new();
+        mock.expect_find_current_user().returning(|id| {
+            Ok(Some(User {
+                id,
+                name: "新しい名前".into(),
+                email: "user@example.com".into(),
+                role: Role::User,
+            }))
+        });
+        mock.expect_update_profile()
+            .withf(|event| event.name.as_deref() == Some("新しい名前") && event.email.is_none())
+            .returning(|_| Ok(()));
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture_auth);
+
+    let req = Request::patch(v1("/users/me"))
+        .bearer()
+        .header("Content-Type", "application/merge-patch+json")
+        .body(Body::from(r#"{"name":"新しい名前"}"#))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::OK);
+
+    let result = deserialize_json!(resp, UserResponse);
+    assert_eq!(result.name, "新しい名前");
+
+    Ok(())
+}
+
+#[rstest]
+#[case(r#"{"email":null}"#, axum::http::StatusCode::UNPROCESSABLE_ENTITY)]
+#[case(r#"{"email":"not-an-email"}"#, axum::http::StatusCode::BAD_REQUEST)]
+#[tokio::test]
+async fn patch_current_user_with_invalid_body(
+    fixture: registry::MockAppRegistryExt,
+    #[case] body: &'static str,
+    #[case] expected_status: axum::http::StatusCode,
+) -> anyhow::Result<()> {
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::patch(v1("/users/me"))
+        .bearer()
+        .header("Content-Type", "application/merge-patch+json")
+        .body(Body::from(body))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), expected_status);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn change_role_revokes_sessions(
+    mut fixture_registry: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let user_id = UserId::new();
+    let revoked = Arc::new(Mutex::new(Vec::new()));
+    fixture_registry
+        .expect_auth_repository()
+        .returning(mock_auth_repository(SessionId::new(), revoked.clone()));
+    fixture_registry
+        .expect_user_repository()
+        .returning(move || {
+            let mut mock = MockUserRepository::new();
+            mock.expect_find_current_user().returning(|id| {
+                Ok(Some(User {
+                    id,
+                    name: "admin".into(),
+                    email: "admin@example.com".into(),
+                    role: Role::Admin,
+                }))
+            });
+            mock.expect_update_role()
+                .withf(move |event| event.user_id == user_id && event.role == Role::User)
+                .returning(|_| Ok(()));
+            Arc::new(mock)
+        });
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::put(v1(&format!("/users/{user_id}/role")))
+        .bearer()
+        .header("Content-Type", "application/json")
+        .body(Body::from(r#"{"role":"User"}"#))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::OK);
+
+    // 降格したユーザーのセッションはすべて終了する
+    assert_eq!(*revoked.lock().unwrap(), vec![(user_id, None)]);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn change_password_revokes_other_sessions(
+    mut fixture_registry: registry::MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let user_id = UserId::new();
+    let current = SessionId::new();
+    let revoked = Arc::new(Mutex::new(Vec::new()));
+    fixture_registry
+        .expect_auth_repository()
+        .returning(mock_auth_repository(current, revoked.clone()));
+    fixture_registry
+        .expect_user_repository()
+        .returning(move || {
+            let mut mock = MockUserRepository::new();
+            mock.expect_find_current_user().returning(move |_| {
+                Ok(Some(User {
+                    id: user_id,
+                    name: "dummy-user".into(),
+                    email: "dummy@example.com".into(),
+                    role: Role::User,
+                }))
+            });
+            mock.expect_update_password()
+                .withf(move |event| event.user_id == user_id)
+                .returning(|_| Ok(()));
+            Arc::new(mock)
+        });
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::put(v1("/users/me/password"))
+        .bearer()
+        .header("Content-Type", "application/json")
+        .body(Body::from(
+            r#"{"currentPassword":"password","newPassword":"new-password"}"#,
+        ))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), axum::http::StatusCode::OK);
+
+    // パスワードを変更したセッション以外は終了する
+    assert_eq!(*revoked.lock().unwrap(), vec![(user_id, Some(current))]);
+
+    Ok(())
+}
+
+#[rstest]
+#[case(Role::Admin, axum::http::StatusCode::OK)]
+#[case(Role::User, axum::http::StatusCode::FORBIDDEN)]
+#[tokio::test]
+async fn unlock_user(
+    mut fixture_registry: registry::MockAppRegistryExt,
+    #[case] role: Role,
+    #[case] expected_status: axum::http::StatusCode,
+) -> anyhow::Result<()> {
+    let user_id = UserId::new();
+    fixture_registry
+        .expect_auth_repository()
+        .returning(move || {
+            let mut mock = MockAuthRepository::new();
+            mock.expect_fetch_user_id_from_token()
+                .returning(|_| Ok(Some(UserId::new())));
+            mock.expect_unlock_account()
+                .withf(move |id| *id == user_id)
+                .returning(|_| Ok(()));
+            Arc::new(mock)
+        });
+    fixture_registry
+        .expect_user_repository()
+        .returning(move || {
+            let mut mock = MockUserRepository::new();
+            let role = role.clone();
+            mock.expect_find_current_user().returning(move |id| {
+                Ok(Some(User {
+                    id,
+                    name: "dummy-user".into(),
+                    email: "dummy@example.com".into(),
+                    role: role.clone(),
+                }))
+            });
+            Arc::new(mock)
+        });
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::delete(v1(&format!("/users/{user_id}/login-lock")))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), expected_status);
+
+    Ok(())
+}
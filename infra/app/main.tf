@@ -39,12 +39,22 @@ resource "aws_apprunner_service" "backend_book_manager" {
     image_repository {
       image_configuration {
         port = "8080"
+        # TRUSTED_PROXIES: App Runner のコンテナにはロードバランサーからプライベートアドレスで接続される
         runtime_environment_variables = {
-          AUTH_TOKEN_TTL = 86400
-          HOST           = "0.0.0.0"
-          PORT           = 8080
+          AUTH_TOKEN_SLIDING_EXPIRATION = true
+          AUTH_TOKEN_TTL                = 1800
+          CHECKOUT_LIMIT_ADMIN          = 20
+          CHECKOUT_LIMIT_USER           = 5
+          HOLD_PICKUP_DAYS              = 3
+          HOST                          = "0.0.0.0"
+          LOAN_PERIOD_DAYS              = 14
+          MAX_RENEWALS                  = 2
+          PORT                          = 8080
+          REFRESH_TOKEN_TTL             = 1209600
+          TRUSTED_PROXIES               = "10.0.0.0/8,172.16.0.0/12,192.168.0.0/16"
         }
         runtime_environment_secrets = {
+          AUTH_TOKEN_SECRET = "${var.book_app_secrets_manager_arn}:AUTH_TOKEN_SECRET::"
           DATABASE_HOST     = "${var.book_app_secrets_manager_arn}:DATABASE_HOST::"
           DATABASE_NAME     = "${var.book_app_secrets_manager_arn}:DATABASE_NAME::"
           DATABASE_PASSWORD = "${var.book_app_secrets_manager_arn}:DATABASE_PASSWORD::"
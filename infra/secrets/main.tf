@@ -1,5 +1,6 @@
 locals {
   book_app_secrets = {
+    AUTH_TOKEN_SECRET = "fill_your_auth_token_secret_of_at_least_32_bytes"
     DATABASE_HOST     = "fill_your_db_host"
     DATABASE_PORT     = 5432
     DATABASE_NAME     = "app"
@@ -1,19 +1,50 @@
 use uuid::Uuid;
 
-use crate::model::id::UserId;
+use crate::model::{
+    auth::RefreshToken,
+    id::{SessionId, UserId},
+};
 
 pub struct CreateToken {
     pub user_id: UserId,
+    pub session_id: SessionId,
     pub access_token: String,
+    pub refresh_token: String,
+    // ログインしたクライアントの情報（セッション一覧に表示する）
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
 }
 
 impl CreateToken {
-    pub fn new(user_id: UserId) -> Self {
+    pub fn new(user_id: UserId, user_agent: Option<String>, ip_address: Option<String>) -> Self {
+        Self::for_session(user_id, SessionId::new(), user_agent, ip_address)
+    }
+
+    // 既存のセッションのトークンを発行し直す（リフレッシュトークンのローテーション時に使う）
+    pub fn for_session(
+        user_id: UserId,
+        session_id: SessionId,
+        user_agent: Option<String>,
+        ip_address: Option<String>,
+    ) -> Self {
         let access_token = Uuid::new_v4().simple().to_string();
+        let refresh_token = RefreshToken::new(session_id).0;
 
         Self {
             user_id,
+            session_id,
             access_token,
+            refresh_token,
+            user_agent,
+            ip_address,
         }
     }
 }
+
+// ログイン時のメールアドレスとパスワードの照合
+pub struct VerifyUser {
+    pub email: String,
+    pub password: String,
+    // 失敗回数を IP アドレスごとにも数えるために使う
+    pub ip_address: Option<String>,
+}
@@ -1,3 +1,42 @@
+use std::str::FromStr;
+
+use sqlx::types::chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::model::id::{SessionId, UserId};
+
 pub mod event;
 
 pub struct AccessToken(pub String);
+
+// アクセストークンを再発行するためのトークン
+// ログインごとに作られるセッション（トークンの系列）の ID を含み、{セッション ID}.{ランダムな文字列} の形式で表す
+pub struct RefreshToken(pub String);
+
+impl RefreshToken {
+    pub fn new(session_id: SessionId) -> Self {
+        Self(format!("{}.{}", session_id, Uuid::new_v4().simple()))
+    }
+
+    pub fn session_id(&self) -> Option<SessionId> {
+        let (session_id, _) = self.0.split_once('.')?;
+        SessionId::from_str(session_id).ok()
+    }
+}
+
+pub struct AuthTokens {
+    pub user_id: UserId,
+    pub access_token: AccessToken,
+    pub refresh_token: RefreshToken,
+}
+
+// ログイン中のセッション
+// ログインするたびに作られ、リフレッシュトークンで再発行したトークンは同じセッションに属する
+#[derive(Debug)]
+pub struct Session {
+    pub id: SessionId,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+}
@@ -1,6 +1,60 @@
+use crate::model::{
+    book::BookCover,
+    id::{BookCopyId, BookId, TagId, UserId},
+    isbn::Isbn,
+};
+
 pub struct CreateBook {
     pub title: String,
     pub author: String,
-    pub isbn: String,
+    pub isbn: Isbn,
     pub description: String,
+    // 蔵書と同時に登録する最初のコピーのバーコード（None の場合は採番する）
+    pub barcode: Option<String>,
+    pub tags: Vec<TagId>,
+}
+
+pub struct CreateBookCopy {
+    pub book_id: BookId,
+    // None の場合は採番する
+    pub barcode: Option<String>,
+    // 登録したユーザーがコピーの所有者になる
+    pub requested_user: UserId,
+}
+
+pub struct DeleteBookCopy {
+    pub book_id: BookId,
+    pub copy_id: BookCopyId,
+    pub requested_user: UserId,
+    pub is_admin: bool,
+}
+
+// 蔵書の所有者を変更する（蔵書の所有者か管理者のみ、貸出中は変更できない）
+pub struct TransferBookOwner {
+    pub book_id: BookId,
+    pub new_owner: UserId,
+    pub requested_user: UserId,
+    pub is_admin: bool,
+}
+
+// 蔵書の書影を登録する（蔵書の所有者か管理者のみ、すでにある場合は置き換える）
+pub struct UpdateBookCover {
+    pub book_id: BookId,
+    pub cover: BookCover,
+    pub requested_user: UserId,
+    pub is_admin: bool,
+}
+
+pub struct ImportBooks {
+    pub rows: Vec<ImportBookRow>,
+    // 登録したユーザーがすべての蔵書の所有者になる
+    pub requested_user: UserId,
+    // true の場合は登録を確定せず、結果だけを返す
+    pub dry_run: bool,
+}
+
+pub struct ImportBookRow {
+    // 取り込んだデータ中の行番号（1 始まり）
+    pub line: usize,
+    pub book: CreateBook,
 }
@@ -1,5 +1,8 @@
 use crate::model::{
-    id::{BookId, CheckoutId, UserId},
+    id::{BookCopyId, BookId, CheckoutId, CoverId, TagId, UserId},
+    isbn::Isbn,
+    list::Cursor,
+    tag::Tag,
     user::{BookOwner, CheckoutUser},
 };
 use sqlx::types::chrono::{DateTime, Utc};
@@ -12,9 +15,77 @@ pub struct Book {
     pub id: Uuid,
     pub title: String,
     pub author: String,
-    pub isbn: String,
+    pub isbn: Isbn,
     pub description: String,
     pub owner: BookOwner,
+    // 蔵書の現物（コピー）の一覧
+    pub copies: Vec<BookCopy>,
+    pub tags: Vec<Tag>,
+    // アーカイブ済みの場合のみ Some
+    pub archive: Option<BookArchive>,
+    // 更新の競合を検出するためのバージョン（蔵書を更新するたびに増える）
+    pub version: i64,
+    // 書影が登録されている場合のみ Some
+    pub cover: Option<BookCover>,
+}
+
+impl Book {
+    pub fn total_copies(&self) -> usize {
+        self.copies.len()
+    }
+
+    // 貸出中でないコピーの冊数
+    pub fn available_copies(&self) -> usize {
+        self.copies.iter().filter(|c| c.checkout.is_none()).count()
+    }
+}
+
+// 蔵書のアーカイブ（論理削除）の情報
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BookArchive {
+    pub archived_at: DateTime<Utc>,
+    pub reason: Option<String>,
+}
+
+// 蔵書の書影
+// 書影を登録するたびに新しい ID を採番し、元の画像とサムネイルを ID ごとのキーで保存する
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BookCover {
+    pub id: CoverId,
+    // 元の画像の Content-Type（image/jpeg・image/png・image/webp のいずれか）
+    pub content_type: String,
+}
+
+impl BookCover {
+    // サムネイルは元の画像の形式によらず PNG で保存する
+    pub const THUMBNAIL_CONTENT_TYPE: &str = "image/png";
+
+    pub fn key(&self) -> String {
+        format!("covers/{}", self.id)
+    }
+
+    pub fn thumbnail_key(&self) -> String {
+        format!("covers/{}_thumbnail", self.id)
+    }
+}
+
+// 蔵書の所有者の変更履歴
+#[derive(Debug)]
+pub struct BookOwnerTransfer {
+    pub from: BookOwner,
+    pub to: BookOwner,
+    // 変更を実行したユーザー（所有者本人か管理者）
+    pub transferred_by: BookOwner,
+    pub transferred_at: DateTime<Utc>,
+}
+
+// 蔵書の現物
+// 同じ蔵書を複数冊所有している場合は、1 冊ごとにバーコードと所有者を持つ
+#[derive(Debug)]
+pub struct BookCopy {
+    pub id: BookCopyId,
+    pub barcode: String,
+    pub owner: BookOwner,
     pub checkout: Option<Checkout>,
 }
 
@@ -22,12 +93,51 @@ pub struct Book {
 pub struct BookListOptions {
     pub limit: i64,
     pub offset: i64,
+    // タイトル・著者名・説明・ISBN を対象とした検索語
+    pub query: Option<String>,
+    pub availability: Option<BookAvailability>,
+    pub owner: Option<UserId>,
+    // 著者名の部分一致
+    pub author: Option<String>,
+    // ISBN の完全一致（ISBN-10 で指定しても ISBN-13 で登録された蔵書にマッチする）
+    pub isbn: Option<Isbn>,
+    pub sort: BookSortKey,
+    // 指定した場合は offset の代わりにカーソルの位置の次の蔵書から取得する
+    // 登録日時による並び順（CreatedAtDesc / CreatedAtAsc）でのみ指定できる
+    pub cursor: Option<Cursor>,
+    // 指定したすべてのタグが付いている蔵書に絞り込む
+    pub tags: Vec<TagId>,
+    // true の場合はアーカイブ済みの蔵書も含める
+    pub include_archived: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BookAvailability {
+    // 貸出可能（貸出中でない）
+    Available,
+    // 貸出中
+    CheckedOut,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BookSortKey {
+    // 登録日時の新しい順
+    #[default]
+    CreatedAtDesc,
+    // 登録日時の古い順
+    CreatedAtAsc,
+    TitleAsc,
+    TitleDesc,
+    AuthorAsc,
+    AuthorDesc,
+    // 検索語との関連度の高い順（検索語がない場合は登録日時の新しい順）
+    Relevance,
 }
 
 pub struct CreateBook {
     pub title: String,
     pub author: String,
-    pub isbn: String,
+    pub isbn: Isbn,
     pub description: String,
 }
 
@@ -36,22 +146,95 @@ pub struct UpdateBook {
     pub book_id: BookId,
     pub title: String,
     pub author: String,
-    pub isbn: String,
+    pub isbn: Isbn,
     pub description: String,
+    // None の場合はタグを変更しない
+    pub tags: Option<Vec<TagId>>,
+    // 指定した場合は、蔵書のバージョンが一致するときのみ更新する
+    pub expected_version: Option<i64>,
+    pub requested_user: UserId,
+    pub is_admin: bool,
+}
+
+// 蔵書の一部の項目だけを更新する（None の項目は変更しない）
+#[derive(Debug)]
+pub struct PatchBook {
+    pub book_id: BookId,
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub isbn: Option<Isbn>,
+    pub description: Option<String>,
+    pub tags: Option<Vec<TagId>>,
+    // 指定した場合は、蔵書のバージョンが一致するときのみ更新する
+    pub expected_version: Option<i64>,
     pub requested_user: UserId,
     pub is_admin: bool,
 }
 
+// 蔵書をアーカイブする（貸出中のコピーがある場合はアーカイブできない）
 #[derive(Debug)]
 pub struct DeleteBook {
     pub book_id: BookId,
+    pub reason: Option<String>,
+    // 指定した場合は、蔵書のバージョンが一致するときのみアーカイブする
+    pub expected_version: Option<i64>,
     pub requested_user: UserId,
     pub is_admin: bool,
 }
 
+// アーカイブした蔵書を元に戻す
+#[derive(Debug)]
+pub struct RestoreBook {
+    pub book_id: BookId,
+    pub requested_user: UserId,
+    pub is_admin: bool,
+}
+
+// ISBN から取得した書誌情報
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BookMetadata {
+    pub isbn: Isbn,
+    pub title: String,
+    pub author: String,
+    pub description: String,
+}
+
+// 蔵書の一括登録の結果
+#[derive(Debug, Default)]
+pub struct BookImportReport {
+    pub imported: Vec<ImportedBook>,
+    // ISBN が登録済みの蔵書や、前の行と重複しているため登録しなかった行
+    pub duplicates: Vec<BookImportDuplicate>,
+    pub errors: Vec<BookImportError>,
+}
+
+#[derive(Debug)]
+pub struct ImportedBook {
+    pub line: usize,
+    // ドライランの場合は登録を確定しないため None
+    pub book_id: Option<BookId>,
+}
+
+#[derive(Debug)]
+pub struct BookImportDuplicate {
+    pub line: usize,
+    pub isbn: Isbn,
+    // 同じ ISBN で登録済みの蔵書
+    pub existing_book_id: Option<BookId>,
+    // 同じ ISBN が前に現れた行
+    pub duplicate_of_line: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct BookImportError {
+    pub line: usize,
+    pub message: String,
+}
+
 #[derive(Debug)]
 pub struct Checkout {
     pub checkout_id: CheckoutId,
     pub checked_out_by: CheckoutUser,
     pub checked_out_at: DateTime<Utc>,
+    pub due_at: DateTime<Utc>,
 }
@@ -1,19 +1,34 @@
 use derive_new::new;
 use sqlx::types::chrono::{DateTime, Utc};
 
-use crate::model::id::{BookId, CheckoutId, UserId};
+use crate::model::id::{BookCopyId, BookId, CheckoutId, UserId};
 
-#[derive(new)]
+#[derive(new, Clone)]
 pub struct CreateCheckout {
     pub book_id: BookId,
+    // 貸し出すコピー（None の場合は貸出可能なコピーのいずれかを選ぶ）
+    pub copy_id: Option<BookCopyId>,
+    // 借りるユーザー
     pub checked_out_by: UserId,
+    // 貸出操作を行ったユーザー（管理者が代理で貸し出した場合は借りるユーザーと異なる）
+    pub operated_by: UserId,
     pub checked_out_at: DateTime<Utc>,
 }
 
-#[derive(new)]
+#[derive(new, Clone)]
+pub struct RenewCheckout {
+    pub checkout_id: CheckoutId,
+    pub book_id: BookId,
+    pub renewed_by: UserId,
+}
+
+#[derive(new, Clone)]
 pub struct UpdateReturned {
     pub checkout_id: CheckoutId,
     pub book_id: BookId,
+    // 返却操作を行ったユーザー
     pub returned_by: UserId,
+    // 管理者は借りたユーザー以外の貸出も返却できる
+    pub is_admin: bool,
     pub returned_at: DateTime<Utc>,
 }
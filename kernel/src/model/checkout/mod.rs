@@ -1,6 +1,6 @@
 use sqlx::types::chrono::{DateTime, Utc};
 
-use crate::model::id::{BookId, CheckoutId};
+use crate::model::id::{BookCopyId, BookId, CheckoutId};
 use crate::model::user::CheckoutUser;
 
 pub mod event;
@@ -10,14 +10,29 @@ pub struct Checkout {
     pub id: CheckoutId,
     pub checked_out_by: CheckoutUser,
     pub checked_out_at: DateTime<Utc>,
+    pub due_at: DateTime<Utc>,
     pub returned_at: Option<DateTime<Utc>>,
+    // 返却期限を延長した回数
+    pub renewal_count: i32,
+    // 未返却かつ返却期限を過ぎている場合は true
+    pub is_overdue: bool,
     pub book: CheckoutBook,
 }
 
 #[derive(Debug)]
 pub struct CheckoutBook {
     pub book_id: BookId,
+    // 貸し出したコピー
+    pub copy_id: BookCopyId,
     pub title: String,
     pub author: String,
     pub isbn: String,
 }
+
+// 延滞中の貸出
+#[derive(Debug)]
+pub struct OverdueCheckout {
+    pub checkout: Checkout,
+    // 返却期限から経過した日数
+    pub days_overdue: i64,
+}
@@ -53,4 +53,9 @@ macro_rules! define_id {
 
 define_id!(UserId);
 define_id!(BookId);
+define_id!(BookCopyId);
 define_id!(CheckoutId);
+define_id!(ReservationId);
+define_id!(TagId);
+define_id!(CoverId);
+define_id!(SessionId);
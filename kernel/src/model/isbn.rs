@@ -0,0 +1,196 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{
+    Decode, Encode, Postgres, Type,
+    encode::IsNull,
+    error::BoxDynError,
+    postgres::{PgArgumentBuffer, PgTypeInfo, PgValueRef},
+};
+use std::str::FromStr;
+
+// ISBN（国際標準図書番号）
+// ハイフン・空白区切りの有無を問わず ISBN-10 と ISBN-13 を受け付け、
+// チェックディジットを検証したうえで、ハイフンなしの ISBN-13 に正規化して保持する
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Isbn(String);
+
+impl Isbn {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    // ISBN-10 のチェックディジットを含む 10 桁を ISBN-13 に変換する
+    fn from_isbn10(digits: &[u32]) -> Option<Self> {
+        let sum: u32 = digits
+            .iter()
+            .enumerate()
+            .map(|(i, d)| (10 - i as u32) * d)
+            .sum();
+        if !sum.is_multiple_of(11) {
+            return None;
+        }
+        let mut isbn13 = vec![9, 7, 8];
+        isbn13.extend_from_slice(&digits[..9]);
+        isbn13.push(isbn13_check_digit(&isbn13));
+        Some(Self(isbn13.iter().map(|d| d.to_string()).collect()))
+    }
+
+    fn from_isbn13(digits: &[u32]) -> Option<Self> {
+        if !matches!(&digits[..3], [9, 7, 8] | [9, 7, 9]) {
+            return None;
+        }
+        if isbn13_check_digit(&digits[..12]) != digits[12] {
+            return None;
+        }
+        Some(Self(digits.iter().map(|d| d.to_string()).collect()))
+    }
+}
+
+// ISBN-13 の先頭 12 桁からチェックディジットを求める
+fn isbn13_check_digit(digits: &[u32]) -> u32 {
+    let sum: u32 = digits
+        .iter()
+        .enumerate()
+        .map(|(i, d)| if i % 2 == 0 { *d } else { d * 3 })
+        .sum();
+    (10 - sum % 10) % 10
+}
+
+impl FromStr for Isbn {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("ISBN（{s}）が不正です。");
+        let chars = s
+            .chars()
+            .filter(|c| !matches!(c, '-' | ' '))
+            .collect::<Vec<_>>();
+        let digits = chars
+            .iter()
+            .enumerate()
+            .map(|(i, c)| match c {
+                // ISBN-10 のチェックディジットは 10 を X で表す
+                'X' | 'x' if chars.len() == 10 && i == 9 => Some(10),
+                c => c.to_digit(10),
+            })
+            .collect::<Option<Vec<u32>>>()
+            .ok_or_else(invalid)?;
+        match digits.len() {
+            10 => Self::from_isbn10(&digits),
+            13 => Self::from_isbn13(&digits),
+            _ => None,
+        }
+        .ok_or_else(invalid)
+    }
+}
+
+impl TryFrom<String> for Isbn {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Isbn> for String {
+    fn from(value: Isbn) -> Self {
+        value.0
+    }
+}
+
+impl std::fmt::Display for Isbn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+// データベースには正規化した文字列として保存する
+// 読み込むときもチェックディジットを検証し、不正な値から Isbn を作らないようにする
+impl Type<Postgres> for Isbn {
+    fn type_info() -> PgTypeInfo {
+        <String as Type<Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        <String as Type<Postgres>>::compatible(ty)
+    }
+}
+
+impl Decode<'_, Postgres> for Isbn {
+    fn decode(value: PgValueRef<'_>) -> Result<Self, BoxDynError> {
+        let value = <String as Decode<Postgres>>::decode(value)?;
+        Ok(value.parse()?)
+    }
+}
+
+impl Encode<'_, Postgres> for Isbn {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+        <&str as Encode<Postgres>>::encode(self.as_str(), buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_isbn13() {
+        // チェックディジットが正しい ISBN-13 はそのまま受け付ける
+        assert_eq!(
+            "9781593278281".parse::<Isbn>().unwrap().as_str(),
+            "9781593278281"
+        );
+        assert_eq!(
+            "9791032305690".parse::<Isbn>().unwrap().as_str(),
+            "9791032305690"
+        );
+        // チェックディジットが誤っているものや、978 / 979 以外で始まるものは受け付けない
+        assert!("9781593278282".parse::<Isbn>().is_err());
+        assert!("9771593278285".parse::<Isbn>().is_err());
+    }
+
+    #[test]
+    fn test_parse_isbn10() {
+        // ISBN-10 は ISBN-13 に変換する
+        assert_eq!(
+            "1593278284".parse::<Isbn>().unwrap().as_str(),
+            "9781593278281"
+        );
+        // チェックディジットの X は 10 として扱い、小文字も受け付ける
+        assert_eq!(
+            "080442957X".parse::<Isbn>().unwrap().as_str(),
+            "9780804429573"
+        );
+        assert_eq!(
+            "080442957x".parse::<Isbn>().unwrap().as_str(),
+            "9780804429573"
+        );
+        // チェックディジットが誤っているものや、末尾以外の X は受け付けない
+        assert!("1593278285".parse::<Isbn>().is_err());
+        assert!("08044295X7".parse::<Isbn>().is_err());
+        assert!("978159327828X".parse::<Isbn>().is_err());
+    }
+
+    #[test]
+    fn test_parse_normalizes_separators() {
+        // ハイフンや空白の区切りは取り除き、どの表記からも同じ値になる
+        for s in [
+            "978-1-59327-828-1",
+            "978 1 59327 828 1",
+            "1-59327-828-4",
+            "1 59327 828 4",
+        ] {
+            assert_eq!(s.parse::<Isbn>().unwrap().as_str(), "9781593278281");
+        }
+        // 桁数が合わないものや数字以外を含むものは受け付けない
+        for s in [
+            "",
+            "---",
+            "978159327828",
+            "97815932782811",
+            "978-1-59327-828-a",
+        ] {
+            assert!(s.parse::<Isbn>().is_err(), "{s}");
+        }
+    }
+}
@@ -1,9 +1,16 @@
+use serde::{Deserialize, Serialize};
+use sqlx::types::chrono::{DateTime, Utc};
+use std::fmt::Write;
+use uuid::Uuid;
+
 #[derive(Debug)]
 pub struct PaginatedList<T> {
     pub items: Vec<T>,
     pub total: i64,
     pub limit: i64,
     pub offset: i64,
+    // 次のページを取得するためのカーソル（次のページがない場合は None）
+    pub next_cursor: Option<Cursor>,
 }
 
 impl<T> PaginatedList<T> {
@@ -11,3 +18,80 @@ impl<T> PaginatedList<T> {
         self.items
     }
 }
+
+// キーセットページネーションで、前のページの最後の要素の位置を表すカーソル
+// 外部には作成日時と ID をつなげたバイト列を 16 進数で表した不透明な文字列として渡す
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Cursor {
+    pub created_at: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+impl From<Cursor> for String {
+    fn from(value: Cursor) -> Self {
+        let mut bytes = value.created_at.timestamp_micros().to_be_bytes().to_vec();
+        bytes.extend_from_slice(value.id.as_bytes());
+        bytes.iter().fold(String::new(), |mut s, b| {
+            let _ = write!(s, "{b:02x}");
+            s
+        })
+    }
+}
+
+impl TryFrom<String> for Cursor {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let invalid = || format!("カーソル（{value}）が不正です。");
+        if value.len() != 48 || !value.is_ascii() {
+            return Err(invalid());
+        }
+        let bytes = (0..value.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&value[i..i + 2], 16))
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|_| invalid())?;
+        let (micros, id) = bytes.split_at(8);
+        let micros = i64::from_be_bytes(micros.try_into().map_err(|_| invalid())?);
+        let created_at = DateTime::from_timestamp_micros(micros).ok_or_else(invalid)?;
+        let id = Uuid::from_slice(id).map_err(|_| invalid())?;
+        Ok(Self { created_at, id })
+    }
+}
+
+#[derive(Debug)]
+pub struct CursorListOptions {
+    pub limit: i64,
+    // 指定した場合はカーソルの位置の次の要素から取得する
+    pub cursor: Option<Cursor>,
+}
+
+#[derive(Debug)]
+pub struct CursorPaginatedList<T> {
+    pub items: Vec<T>,
+    pub limit: i64,
+    pub next_cursor: Option<Cursor>,
+}
+
+impl<T> CursorPaginatedList<T> {
+    // 次のページの有無を判定するため limit + 1 件まで取得した要素から一覧を組み立てる
+    pub fn from_overfetched(
+        mut items: Vec<T>,
+        limit: i64,
+        cursor_of: impl Fn(&T) -> Cursor,
+    ) -> Self {
+        let limit_len = usize::try_from(limit).unwrap_or_default();
+        let next_cursor = if items.len() > limit_len {
+            items.truncate(limit_len);
+            items.last().map(cursor_of)
+        } else {
+            None
+        };
+        Self {
+            items,
+            limit,
+            next_cursor,
+        }
+    }
+}
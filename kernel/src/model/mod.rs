@@ -2,6 +2,9 @@ pub mod auth;
 pub mod book;
 pub mod checkout;
 pub mod id;
+pub mod isbn;
 pub mod list;
+pub mod reservation;
 pub mod role;
+pub mod tag;
 pub mod user;
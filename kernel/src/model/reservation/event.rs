@@ -0,0 +1,20 @@
+use derive_new::new;
+use sqlx::types::chrono::{DateTime, Utc};
+
+use crate::model::id::{BookId, ReservationId, UserId};
+
+#[derive(new, Clone)]
+pub struct CreateReservation {
+    pub book_id: BookId,
+    pub reserved_by: UserId,
+    pub reserved_at: DateTime<Utc>,
+}
+
+#[derive(new, Clone)]
+pub struct DeleteReservation {
+    pub reservation_id: ReservationId,
+    pub book_id: BookId,
+    pub requested_user: UserId,
+    pub is_admin: bool,
+    pub deleted_at: DateTime<Utc>,
+}
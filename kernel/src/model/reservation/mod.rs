@@ -0,0 +1,26 @@
+use sqlx::types::chrono::{DateTime, Utc};
+
+use crate::model::id::{BookId, ReservationId};
+use crate::model::user::ReservationUser;
+
+pub mod event;
+
+#[derive(Debug)]
+pub struct Reservation {
+    pub id: ReservationId,
+    pub reserved_by: ReservationUser,
+    pub reserved_at: DateTime<Utc>,
+    // 蔵書ごとの予約の順番（1 始まり）
+    pub position: i64,
+    // 取り置き中の場合は受け取り期限、順番待ちの場合は None
+    pub hold_expires_at: Option<DateTime<Utc>>,
+    pub book: ReservationBook,
+}
+
+#[derive(Debug)]
+pub struct ReservationBook {
+    pub book_id: BookId,
+    pub title: String,
+    pub author: String,
+    pub isbn: String,
+}
@@ -1,6 +1,6 @@
 use strum::{AsRefStr, EnumIter, EnumString};
 
-#[derive(Debug, EnumString, AsRefStr, EnumIter, Default, PartialEq, Eq)]
+#[derive(Debug, Clone, EnumString, AsRefStr, EnumIter, Default, PartialEq, Eq)]
 pub enum Role {
     Admin,
     #[default]
@@ -0,0 +1,17 @@
+use crate::model::id::TagId;
+
+#[derive(Debug)]
+pub struct CreateTag {
+    pub name: String,
+}
+
+#[derive(Debug)]
+pub struct UpdateTag {
+    pub tag_id: TagId,
+    pub name: String,
+}
+
+#[derive(Debug)]
+pub struct DeleteTag {
+    pub tag_id: TagId,
+}
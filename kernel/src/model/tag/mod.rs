@@ -0,0 +1,16 @@
+use crate::model::id::TagId;
+
+pub mod event;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tag {
+    pub id: TagId,
+    pub name: String,
+}
+
+// 蔵書一覧の検索条件に当てはまる蔵書のうち、タグが付いている蔵書の冊数
+#[derive(Debug)]
+pub struct TagFacet {
+    pub tag: Tag,
+    pub count: i64,
+}
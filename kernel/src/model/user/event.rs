@@ -7,6 +7,14 @@ pub struct CreateUser {
     pub password: String,
 }
 
+// ユーザーの名前とメールアドレスを更新する（None の項目は変更しない）
+#[derive(Debug)]
+pub struct UpdateUserProfile {
+    pub user_id: UserId,
+    pub name: Option<String>,
+    pub email: Option<String>,
+}
+
 #[derive(Debug)]
 pub struct UpdateUserRole {
     pub user_id: UserId,
@@ -20,7 +28,28 @@ pub struct UpdateUserPassword {
     pub new_password: String,
 }
 
+#[derive(Debug)]
+pub struct UpdateUserCheckoutLimit {
+    pub user_id: UserId,
+    // None の場合はロールごとの上限に戻す
+    pub checkout_limit: Option<i32>,
+}
+
 #[derive(Debug)]
 pub struct DeleteUser {
     pub user_id: UserId,
+    // ユーザーが所有する蔵書とコピーの扱い（所有している場合は指定が必要）
+    pub books: Option<DeleteUserBooks>,
+    // true の場合は、ユーザーが借りている蔵書を返却してから削除する
+    // false の場合は、借りている蔵書があると削除できない
+    pub force_return: bool,
+    pub requested_user: UserId,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeleteUserBooks {
+    // 指定したユーザーに所有者を変更する
+    TransferTo(UserId),
+    // 蔵書をアーカイブし、所有者を削除を実行したユーザーに変更する
+    Archive,
 }
@@ -16,8 +16,36 @@ pub struct BookOwner {
     pub name: String,
 }
 
+impl BookOwner {
+    // 削除されたユーザーを所有者の変更履歴に表示するときに使う
+    // 元のユーザーを特定できないよう、ID は nil UUID とする
+    pub fn anonymized() -> Self {
+        Self {
+            id: UserId::from(uuid::Uuid::nil()),
+            name: "削除されたユーザー".into(),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct CheckoutUser {
     pub id: UserId,
     pub name: String,
 }
+
+impl CheckoutUser {
+    // 削除されたユーザーの貸出履歴に表示する借り手
+    // 元のユーザーを特定できないよう、ID は nil UUID とする
+    pub fn anonymized() -> Self {
+        Self {
+            id: UserId::from(uuid::Uuid::nil()),
+            name: "削除されたユーザー".into(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ReservationUser {
+    pub id: UserId,
+    pub name: String,
+}
@@ -2,8 +2,11 @@ use async_trait::async_trait;
 use shared::error::AppResult;
 
 use crate::model::{
-    auth::{AccessToken, event::CreateToken},
-    id::UserId,
+    auth::{
+        AccessToken, AuthTokens, RefreshToken, Session,
+        event::{CreateToken, VerifyUser},
+    },
+    id::{SessionId, UserId},
 };
 
 #[mockall::automock]
@@ -13,7 +16,25 @@ pub trait AuthRepository: Send + Sync {
         &self,
         access_token: &AccessToken,
     ) -> AppResult<Option<UserId>>;
-    async fn verify_user(&self, email: &str, password: &str) -> AppResult<UserId>;
-    async fn create_token(&self, event: CreateToken) -> AppResult<AccessToken>;
+    // メールアドレスとパスワードを照合する
+    // 失敗が続いたアカウントや IP アドレスは一時的にロックし、TooManyLoginAttempts を返す
+    async fn verify_user(&self, event: VerifyUser) -> AppResult<UserId>;
+    // ログインの失敗によるアカウントのロックを解除する
+    async fn unlock_account(&self, user_id: UserId) -> AppResult<()>;
+    async fn create_token(&self, event: CreateToken) -> AppResult<AuthTokens>;
+    // リフレッシュトークンを使ってアクセストークンとリフレッシュトークンを再発行する
+    // 使用済みのリフレッシュトークンが再び使われた場合は、そのセッションのトークンをすべて無効にする
+    async fn refresh_token(&self, refresh_token: RefreshToken) -> AppResult<AuthTokens>;
+    // アクセストークンのセッションを終了し、同じセッションのリフレッシュトークンも無効にする
     async fn delete_token(&self, access_token: AccessToken) -> AppResult<()>;
+    // アクセストークンが属するセッションの ID を取得する
+    async fn find_session_id(&self, access_token: &AccessToken) -> AppResult<Option<SessionId>>;
+    // ユーザーのログイン中のセッションを、最後に使われた日時の新しい順に取得する
+    async fn find_sessions(&self, user_id: UserId) -> AppResult<Vec<Session>>;
+    // ユーザーのセッションを終了する。ユーザーのセッションでない場合は EntityNotFound を返す
+    async fn delete_session(&self, user_id: UserId, session_id: SessionId) -> AppResult<()>;
+    // ユーザーのすべてのセッションを終了する
+    async fn delete_sessions(&self, user_id: UserId) -> AppResult<()>;
+    // 指定したセッション以外の、ユーザーのすべてのセッションを終了する
+    async fn delete_other_sessions(&self, user_id: UserId, session_id: SessionId) -> AppResult<()>;
 }
@@ -0,0 +1,14 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+// 書影などのファイルをキーで保存・取得する
+// ローカルのファイルシステムやオブジェクトストレージなど、保存先ごとに実装を差し替えられる
+#[mockall::automock]
+#[async_trait]
+pub trait BlobStorage: Send + Sync {
+    // 同じキーのファイルがある場合は置き換える
+    async fn put(&self, key: &str, data: Vec<u8>) -> AppResult<()>;
+    async fn get(&self, key: &str) -> AppResult<Option<Vec<u8>>>;
+    // ファイルがない場合も成功とする
+    async fn delete(&self, key: &str) -> AppResult<()>;
+}
@@ -1,18 +1,55 @@
+use std::pin::Pin;
+
 use async_trait::async_trait;
 use shared::error::AppResult;
+use tokio_stream::Stream;
 
 use crate::model::{
-    book::{Book, BookListOptions, DeleteBook, UpdateBook, event::CreateBook},
+    book::{
+        Book, BookCover, BookImportReport, BookListOptions, BookOwnerTransfer, DeleteBook,
+        PatchBook, RestoreBook, UpdateBook,
+        event::{
+            CreateBook, CreateBookCopy, DeleteBookCopy, ImportBooks, TransferBookOwner,
+            UpdateBookCover,
+        },
+    },
     id::{BookId, UserId},
     list::PaginatedList,
+    tag::TagFacet,
 };
 
+// 蔵書を 1 件ずつ返すストリーム
+pub type BookStream = Pin<Box<dyn Stream<Item = AppResult<Book>> + Send>>;
+
 #[mockall::automock]
 #[async_trait]
 pub trait BookRepository: Send + Sync {
     async fn create(&self, event: CreateBook, requested_user: UserId) -> AppResult<()>;
+    // 蔵書を 1 つのトランザクションで一括登録する
+    // ISBN が重複する行とバーコードが使われている行は登録せず、結果に含めて返す
+    async fn import(&self, event: ImportBooks) -> AppResult<BookImportReport>;
     async fn find_all(&self, options: BookListOptions) -> AppResult<PaginatedList<Book>>;
+    // 検索条件に当てはまる蔵書に付いているタグごとの冊数（ページネーションの指定は無視する）
+    async fn find_tag_facets(&self, options: &BookListOptions) -> AppResult<Vec<TagFacet>>;
+    // アーカイブ済みの蔵書も返す
     async fn find_by_id(&self, book_id: BookId) -> AppResult<Option<Book>>;
+    // アーカイブ済みでないすべての蔵書を登録日時の古い順に返す
+    // 一度にすべてを読み込まず、少しずつ取得しながら返す
+    fn export_all(&self) -> BookStream;
     async fn update(&self, event: UpdateBook) -> AppResult<()>;
+    // 指定された項目だけを更新する
+    async fn patch(&self, event: PatchBook) -> AppResult<()>;
+    // 蔵書を物理削除せずにアーカイブする。貸出履歴は残し、予約は取り消す
     async fn delete(&self, event: DeleteBook) -> AppResult<()>;
+    async fn restore(&self, event: RestoreBook) -> AppResult<()>;
+    // 蔵書と、元の所有者が追加したコピーの所有者を変更し、変更履歴に記録する
+    async fn transfer_owner(&self, event: TransferBookOwner) -> AppResult<()>;
+    // 所有者の変更履歴を変更日時の古い順に返す
+    async fn find_owner_transfers(&self, book_id: BookId) -> AppResult<Vec<BookOwnerTransfer>>;
+    // 書影を登録し、置き換えた以前の書影を返す（以前の書影のファイルは呼び出し側で削除する）
+    async fn update_cover(&self, event: UpdateBookCover) -> AppResult<Option<BookCover>>;
+    // 蔵書のコピーを追加する
+    async fn create_copy(&self, event: CreateBookCopy) -> AppResult<()>;
+    // 蔵書のコピーを削除する（貸出中のコピーは削除できない）
+    async fn delete_copy(&self, event: DeleteBookCopy) -> AppResult<()>;
 }
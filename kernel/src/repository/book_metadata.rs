@@ -0,0 +1,12 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::{book::BookMetadata, isbn::Isbn};
+
+// ISBN から書誌情報を取得する
+// 外部の書誌情報サービスやローカルのダンプファイルなど、取得元ごとに実装を差し替えられる
+#[mockall::automock]
+#[async_trait]
+pub trait BookMetadataProvider: Send + Sync {
+    async fn find_by_isbn(&self, isbn: &Isbn) -> AppResult<Option<BookMetadata>>;
+}
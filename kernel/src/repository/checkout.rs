@@ -3,10 +3,11 @@ use shared::error::AppResult;
 
 use crate::model::{
     checkout::{
-        Checkout,
-        event::{CreateCheckout, UpdateReturned},
+        Checkout, OverdueCheckout,
+        event::{CreateCheckout, RenewCheckout, UpdateReturned},
     },
     id::{BookId, UserId},
+    list::{CursorListOptions, CursorPaginatedList},
 };
 
 #[mockall::automock]
@@ -14,12 +15,23 @@ use crate::model::{
 pub trait CheckoutRepository: Send + Sync {
     // 貸出登録
     async fn create(&self, event: CreateCheckout) -> AppResult<()>;
+    // 返却期限の延長
+    async fn renew(&self, event: RenewCheckout) -> AppResult<()>;
     // 返却
     async fn update_returned(&self, event: UpdateReturned) -> AppResult<()>;
-    // 未返却一覧
-    async fn find_unreturned_all(&self) -> AppResult<Vec<Checkout>>;
+    // 未返却一覧（貸出日時の古い順）
+    async fn find_unreturned_all(
+        &self,
+        options: CursorListOptions,
+    ) -> AppResult<CursorPaginatedList<Checkout>>;
+    // 返却期限を過ぎた未返却の貸出一覧
+    async fn find_overdue_all(&self) -> AppResult<Vec<OverdueCheckout>>;
     // ユーザーID に紐づく未返却の貸出情報を取得
     async fn find_unreturned_by_user_id(&self, user_id: UserId) -> AppResult<Vec<Checkout>>;
-    // 蔵書の貸出履歴
-    async fn find_history_by_book_id(&self, book_id: BookId) -> AppResult<Vec<Checkout>>;
+    // 蔵書の貸出履歴（貸出日時の新しい順）
+    async fn find_history_by_book_id(
+        &self,
+        book_id: BookId,
+        options: CursorListOptions,
+    ) -> AppResult<CursorPaginatedList<Checkout>>;
 }
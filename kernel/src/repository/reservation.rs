@@ -0,0 +1,23 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::{
+    id::{BookId, UserId},
+    reservation::{
+        Reservation,
+        event::{CreateReservation, DeleteReservation},
+    },
+};
+
+#[mockall::automock]
+#[async_trait]
+pub trait ReservationRepository: Send + Sync {
+    // 予約登録
+    async fn create(&self, event: CreateReservation) -> AppResult<()>;
+    // 予約取り消し
+    async fn delete(&self, event: DeleteReservation) -> AppResult<()>;
+    // 蔵書の予約待ち一覧
+    async fn find_by_book_id(&self, book_id: BookId) -> AppResult<Vec<Reservation>>;
+    // ユーザー ID に紐づく予約一覧
+    async fn find_by_user_id(&self, user_id: UserId) -> AppResult<Vec<Reservation>>;
+}
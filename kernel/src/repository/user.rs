@@ -5,7 +5,10 @@ use crate::model::{
     id::UserId,
     user::{
         User,
-        event::{CreateUser, DeleteUser, UpdateUserPassword, UpdateUserRole},
+        event::{
+            CreateUser, DeleteUser, UpdateUserCheckoutLimit, UpdateUserPassword, UpdateUserProfile,
+            UpdateUserRole,
+        },
     },
 };
 
@@ -15,7 +18,11 @@ pub trait UserRepository: Send + Sync {
     async fn find_current_user(&self, current_user_id: UserId) -> AppResult<Option<User>>;
     async fn find_all(&self) -> AppResult<Vec<User>>;
     async fn create(&self, event: CreateUser) -> AppResult<User>;
+    async fn update_profile(&self, event: UpdateUserProfile) -> AppResult<()>;
     async fn update_password(&self, event: UpdateUserPassword) -> AppResult<()>;
     async fn update_role(&self, event: UpdateUserRole) -> AppResult<()>;
+    async fn update_checkout_limit(&self, event: UpdateUserCheckoutLimit) -> AppResult<()>;
+    // ユーザーが所有する蔵書を引き継ぐかアーカイブしてから削除する
+    // 返却済みの貸出履歴は、借りたユーザーを匿名化して残す
     async fn delete(&self, event: DeleteUser) -> AppResult<()>;
 }
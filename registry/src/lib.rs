@@ -5,42 +5,72 @@ use adapter::{
     redis::RedisClient,
     repository::{
         auth::AuthRepositoryImpl, book::BookRepositoryImpl, checkout::CheckoutRepositoryImpl,
-        health::HealthCheckRepositoryImpl, user::UserRepositoryImpl,
+        health::HealthCheckRepositoryImpl, reservation::ReservationRepositoryImpl,
+        tag::TagRepositoryImpl, user::UserRepositoryImpl,
     },
 };
 use kernel::repository::{
-    auth::AuthRepository, book::BookRepository, checkout::CheckoutRepository,
-    health::HealthCheckRepository, user::UserRepository,
+    auth::AuthRepository, blob_storage::BlobStorage, book::BookRepository,
+    book_metadata::BookMetadataProvider, checkout::CheckoutRepository,
+    health::HealthCheckRepository, reservation::ReservationRepository, tag::TagRepository,
+    user::UserRepository,
 };
-use shared::config::AppConfig;
+use shared::config::{AppConfig, TrustedProxies};
 
 #[derive(Clone)]
 pub struct AppRegistryImpl {
     health_check_repository: Arc<dyn HealthCheckRepository>,
     book_repository: Arc<dyn BookRepository>,
+    tag_repository: Arc<dyn TagRepository>,
     auth_repository: Arc<dyn AuthRepository>,
     user_repository: Arc<dyn UserRepository>,
     checkout_repository: Arc<dyn CheckoutRepository>,
+    reservation_repository: Arc<dyn ReservationRepository>,
+    book_metadata_provider: Arc<dyn BookMetadataProvider>,
+    blob_storage: Arc<dyn BlobStorage>,
+    trusted_proxies: TrustedProxies,
 }
 
 impl AppRegistryImpl {
-    pub fn new(pool: ConnectionPool, redis: Arc<RedisClient>, app_config: AppConfig) -> Self {
+    pub fn new(
+        pool: ConnectionPool,
+        redis: Arc<RedisClient>,
+        book_metadata_provider: Arc<dyn BookMetadataProvider>,
+        blob_storage: Arc<dyn BlobStorage>,
+        app_config: AppConfig,
+    ) -> Self {
         let health_check_repository = Arc::new(HealthCheckRepositoryImpl::new(pool.clone()));
         let book_repository = Arc::new(BookRepositoryImpl::new(pool.clone()));
+        let tag_repository = Arc::new(TagRepositoryImpl::new(pool.clone()));
         let auth_repository = Arc::new(AuthRepositoryImpl::new(
             pool.clone(),
             redis,
-            app_config.auth.ttl,
+            app_config.auth,
+        ));
+        let user_repository = Arc::new(UserRepositoryImpl::new(
+            pool.clone(),
+            app_config.checkout.hold_pickup_days,
+        ));
+        let reservation_repository = Arc::new(ReservationRepositoryImpl::new(
+            pool.clone(),
+            app_config.checkout.hold_pickup_days,
+        ));
+        let checkout_repository = Arc::new(CheckoutRepositoryImpl::new(
+            pool.clone(),
+            app_config.checkout,
         ));
-        let user_repository = Arc::new(UserRepositoryImpl::new(pool.clone()));
-        let checkout_repository = Arc::new(CheckoutRepositoryImpl::new(pool.clone()));
 
         Self {
             health_check_repository,
             book_repository,
+            tag_repository,
             auth_repository,
             user_repository,
             checkout_repository,
+            reservation_repository,
+            book_metadata_provider,
+            blob_storage,
+            trusted_proxies: app_config.trusted_proxies,
         }
     }
 }
@@ -49,9 +79,14 @@ impl AppRegistryImpl {
 pub trait AppRegistryExt {
     fn health_check_repository(&self) -> Arc<dyn HealthCheckRepository>;
     fn book_repository(&self) -> Arc<dyn BookRepository>;
+    fn tag_repository(&self) -> Arc<dyn TagRepository>;
     fn auth_repository(&self) -> Arc<dyn AuthRepository>;
     fn user_repository(&self) -> Arc<dyn UserRepository>;
     fn checkout_repository(&self) -> Arc<dyn CheckoutRepository>;
+    fn reservation_repository(&self) -> Arc<dyn ReservationRepository>;
+    fn book_metadata_provider(&self) -> Arc<dyn BookMetadataProvider>;
+    fn blob_storage(&self) -> Arc<dyn BlobStorage>;
+    fn trusted_proxies(&self) -> TrustedProxies;
 }
 
 impl AppRegistryExt for AppRegistryImpl {
@@ -63,6 +98,10 @@ impl AppRegistryExt for AppRegistryImpl {
         self.book_repository.clone()
     }
 
+    fn tag_repository(&self) -> Arc<dyn TagRepository> {
+        self.tag_repository.clone()
+    }
+
     fn auth_repository(&self) -> Arc<dyn AuthRepository> {
         self.auth_repository.clone()
     }
@@ -74,6 +113,22 @@ impl AppRegistryExt for AppRegistryImpl {
     fn checkout_repository(&self) -> Arc<dyn CheckoutRepository> {
         self.checkout_repository.clone()
     }
+
+    fn reservation_repository(&self) -> Arc<dyn ReservationRepository> {
+        self.reservation_repository.clone()
+    }
+
+    fn book_metadata_provider(&self) -> Arc<dyn BookMetadataProvider> {
+        self.book_metadata_provider.clone()
+    }
+
+    fn blob_storage(&self) -> Arc<dyn BlobStorage> {
+        self.blob_storage.clone()
+    }
+
+    fn trusted_proxies(&self) -> TrustedProxies {
+        self.trusted_proxies.clone()
+    }
 }
 
 // エンドポイントの実装で、これまで AppRegistry 型として受け取っていたところを
@@ -1,4 +1,7 @@
+use std::{net::IpAddr, str::FromStr};
+
 use anyhow::Result;
+use ipnet::IpNet;
 
 pub struct DatabaseConfig {
     pub host: String,
@@ -12,6 +15,10 @@ pub struct AppConfig {
     pub database: DatabaseConfig,
     pub redis: RedisConfig,
     pub auth: AuthConfig,
+    pub checkout: CheckoutConfig,
+    pub book_metadata: BookMetadataConfig,
+    pub blob_storage: BlobStorageConfig,
+    pub trusted_proxies: TrustedProxies,
 }
 
 impl AppConfig {
@@ -27,14 +34,66 @@ impl AppConfig {
             host: std::env::var("REDIS_HOST")?,
             port: std::env::var("REDIS_PORT")?.parse::<u16>()?,
         };
+        let token_secret = std::env::var("AUTH_TOKEN_SECRET")?;
+        anyhow::ensure!(
+            token_secret.len() >= MIN_TOKEN_SECRET_LENGTH,
+            "AUTH_TOKEN_SECRET は {MIN_TOKEN_SECRET_LENGTH} バイト以上にしてください。"
+        );
         let auth = AuthConfig {
             ttl: std::env::var("AUTH_TOKEN_TTL")?.parse::<u64>()?,
+            refresh_token_ttl: std::env::var("REFRESH_TOKEN_TTL")?.parse::<u64>()?,
+            token_secret,
+            sliding_expiration: std::env::var("AUTH_TOKEN_SLIDING_EXPIRATION")
+                .ok()
+                .map(|v| v.parse::<bool>())
+                .transpose()?
+                .unwrap_or(false),
+            login_throttle: LoginThrottleConfig {
+                max_failures_per_account: env_or("LOGIN_MAX_FAILURES_PER_ACCOUNT", 5)?,
+                max_failures_per_ip: env_or("LOGIN_MAX_FAILURES_PER_IP", 20)?,
+                lockout_seconds: env_or("LOGIN_LOCKOUT_SECONDS", 60)?,
+                max_lockout_seconds: env_or("LOGIN_MAX_LOCKOUT_SECONDS", 3600)?,
+                failure_window_seconds: env_or("LOGIN_FAILURE_WINDOW_SECONDS", 86400)?,
+            },
+        };
+        let checkout = CheckoutConfig {
+            loan_period_days: std::env::var("LOAN_PERIOD_DAYS")?.parse::<i64>()?,
+            max_renewals: std::env::var("MAX_RENEWALS")?.parse::<i32>()?,
+            hold_pickup_days: std::env::var("HOLD_PICKUP_DAYS")?.parse::<i64>()?,
+            checkout_limit_admin: std::env::var("CHECKOUT_LIMIT_ADMIN")?.parse::<i64>()?,
+            checkout_limit_user: std::env::var("CHECKOUT_LIMIT_USER")?.parse::<i64>()?,
+        };
+        let book_metadata = match std::env::var("BOOK_METADATA_SOURCE").ok().as_deref() {
+            None | Some("") => BookMetadataConfig::Disabled,
+            Some("http") => BookMetadataConfig::Http {
+                base_url: std::env::var("BOOK_METADATA_URL")
+                    .unwrap_or_else(|_| DEFAULT_BOOK_METADATA_URL.into()),
+            },
+            Some("file") => BookMetadataConfig::File {
+                path: std::env::var("BOOK_METADATA_FILE")?,
+            },
+            Some(source) => anyhow::bail!("BOOK_METADATA_SOURCE（{source}）が不正です。"),
         };
 
+        let blob_storage = BlobStorageConfig {
+            root_dir: std::env::var("BLOB_STORAGE_DIR")
+                .unwrap_or_else(|_| DEFAULT_BLOB_STORAGE_DIR.into()),
+        };
+
+        let trusted_proxies = std::env::var("TRUSTED_PROXIES")
+            .ok()
+            .map(|v| v.parse::<TrustedProxies>())
+            .transpose()?
+            .unwrap_or_default();
+
         Ok(Self {
             database,
             redis,
             auth,
+            checkout,
+            book_metadata,
+            blob_storage,
+            trusted_proxies,
         })
     }
 }
@@ -45,5 +104,99 @@ pub struct RedisConfig {
 }
 
 pub struct AuthConfig {
+    // アクセストークンの有効期間（秒）
     pub ttl: u64,
+    // リフレッシュトークンの有効期間（秒）
+    pub refresh_token_ttl: u64,
+    // アクセストークンが使われるたびに有効期間を延長するかどうか
+    pub sliding_expiration: bool,
+    // Redis に保存するトークンのハッシュ値（HMAC）の計算に使う秘密鍵
+    pub token_secret: String,
+    pub login_throttle: LoginThrottleConfig,
+}
+
+// トークンのハッシュ値の計算に使う秘密鍵の最小の長さ（HMAC-SHA256 の出力と同じ 32 バイト）
+// 空や短い秘密鍵では、Redis を読める人がハッシュ値からトークンを探せてしまうため、起動時に拒否する
+const MIN_TOKEN_SECRET_LENGTH: usize = 32;
+
+// ログインの失敗回数による制限
+#[derive(Clone, Copy)]
+pub struct LoginThrottleConfig {
+    // アカウント（メールアドレス）ごと・IP アドレスごとに、ロックするまでに許す失敗回数
+    pub max_failures_per_account: u64,
+    pub max_failures_per_ip: u64,
+    // 最初にロックする秒数。以降は失敗するたびに倍になり、max_lockout_seconds で頭打ちになる
+    pub lockout_seconds: u64,
+    pub max_lockout_seconds: u64,
+    // 失敗回数を数える期間（最後に失敗してからこの秒数が経つと回数をリセットする）
+    pub failure_window_seconds: u64,
+}
+
+// 環境変数が設定されていなければデフォルト値を使う
+fn env_or(key: &str, default: u64) -> Result<u64> {
+    match std::env::var(key) {
+        Ok(value) => Ok(value.parse()?),
+        Err(_) => Ok(default),
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct CheckoutConfig {
+    // 貸出日から返却期限までの日数
+    pub loan_period_days: i64,
+    // 1 回の貸出で延長できる回数の上限
+    pub max_renewals: i32,
+    // 予約者のために返却された蔵書を取り置いておく日数
+    pub hold_pickup_days: i64,
+    // ロールごとに同時に借りられる冊数の上限（ユーザーごとの上書きがない場合に使う）
+    pub checkout_limit_admin: i64,
+    pub checkout_limit_user: i64,
+}
+
+const DEFAULT_BOOK_METADATA_URL: &str = "https://openlibrary.org";
+
+// ISBN から書誌情報を取得する際の取得元
+pub enum BookMetadataConfig {
+    // 書誌情報を取得しない
+    Disabled,
+    // OpenLibrary 互換の書誌情報サービスから取得する
+    Http { base_url: String },
+    // ローカルの JSON / CSV のダンプファイルから取得する（拡張子で形式を判定する）
+    File { path: String },
+}
+
+const DEFAULT_BLOB_STORAGE_DIR: &str = "./storage";
+
+// 書影などのファイルの保存先
+pub struct BlobStorageConfig {
+    // ファイルを保存するローカルのディレクトリ
+    pub root_dir: String,
+}
+
+// X-Forwarded-For ヘッダを信頼するリバースプロキシ（ロードバランサー）のアドレス
+// TRUSTED_PROXIES にカンマ区切りで IP アドレスか CIDR を指定する。未指定の場合はどこからの接続も信頼しない
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies(Vec<IpNet>);
+
+impl TrustedProxies {
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        self.0.iter().any(|net| net.contains(ip))
+    }
+}
+
+impl FromStr for TrustedProxies {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(|v| {
+                v.parse::<IpNet>()
+                    .or_else(|_| v.parse::<IpAddr>().map(IpNet::from))
+                    .map_err(|_| anyhow::anyhow!("TRUSTED_PROXIES（{v}）が不正です。"))
+            })
+            .collect::<Result<_>>()
+            .map(Self)
+    }
 }
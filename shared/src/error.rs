@@ -1,4 +1,9 @@
-use axum::{http::StatusCode, response::IntoResponse};
+use axum::{
+    Json,
+    http::{StatusCode, header},
+    response::IntoResponse,
+};
+use serde::Serialize;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -29,6 +34,30 @@ pub enum AppError {
     ForbiddenOperation,
     #[error("{0}")]
     ConversionEntityError(String),
+    #[error("{0}")]
+    ExternalServiceError(String),
+    #[error("{0}")]
+    PreconditionFailed(String),
+    #[error("{0}")]
+    UnsupportedMediaType(String),
+    #[error("ファイルの読み書き中にエラーが発生しました。")]
+    BlobStorageError(#[source] std::io::Error),
+    #[error("同時に借りられる冊数の上限（{limit} 冊）に達しています。")]
+    CheckoutLimitExceeded { limit: i64 },
+    #[error("ログインの失敗が続いたため、{retry_after} 秒間ログインできません。")]
+    TooManyLoginAttempts { retry_after: u64 },
+}
+
+// 理由をフロントエンドで表示する必要のあるエラーで返すレスポンスボディ
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ErrorResponse {
+    code: &'static str,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    limit: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retry_after: Option<u64>,
 }
 
 impl IntoResponse for AppError {
@@ -36,16 +65,49 @@ impl IntoResponse for AppError {
         let status_code = match self {
             AppError::UnprocessableEntity(_) => StatusCode::UNPROCESSABLE_ENTITY,
             AppError::EntityNotFound(_) => StatusCode::NOT_FOUND,
+            AppError::PreconditionFailed(_) => StatusCode::PRECONDITION_FAILED,
+            AppError::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
             AppError::ValidationError(_) | AppError::ConvertToUuidError(_) => {
                 StatusCode::BAD_REQUEST
             }
             AppError::UnauthenticatedError | AppError::ForbiddenOperation => StatusCode::FORBIDDEN,
             AppError::UnauthorizedError => StatusCode::UNAUTHORIZED,
+            AppError::CheckoutLimitExceeded { limit } => {
+                let body = ErrorResponse {
+                    code: "CHECKOUT_LIMIT_EXCEEDED",
+                    message: self.to_string(),
+                    limit: Some(limit),
+                    retry_after: None,
+                };
+                return (StatusCode::CONFLICT, Json(body)).into_response();
+            }
+            AppError::TooManyLoginAttempts { retry_after } => {
+                let body = ErrorResponse {
+                    code: "TOO_MANY_LOGIN_ATTEMPTS",
+                    message: self.to_string(),
+                    limit: None,
+                    retry_after: Some(retry_after),
+                };
+                return (
+                    StatusCode::TOO_MANY_REQUESTS,
+                    [(header::RETRY_AFTER, retry_after.to_string())],
+                    Json(body),
+                )
+                    .into_response();
+            }
+            e @ AppError::ExternalServiceError(_) => {
+                tracing::error!(
+                error.message = %e,
+                "Failed to call external service"
+                );
+                StatusCode::BAD_GATEWAY
+            }
             e @ (AppError::TransactionError(_)
             | AppError::SpecificOperationError(_)
             | AppError::NoRowsAffectedError(_)
             | AppError::KeyValueStoreError(_)
             | AppError::BcryptError(_)
+            | AppError::BlobStorageError(_)
             | AppError::ConversionEntityError(_)) => {
                 tracing::error!(
                 error.cause_chain = ?e,
@@ -1,10 +1,17 @@
-use adapter::{database::connect_database_with, redis::RedisClient};
+use adapter::{
+    database::connect_database_with,
+    redis::RedisClient,
+    repository::{blob_storage::build_blob_storage, book_metadata::build_book_metadata_provider},
+};
 use anyhow::{Context, Result};
 use api::route::{
     auth::{self},
     v1,
 };
-use axum::{Router, http::Method};
+use axum::{
+    Router,
+    http::{Method, header},
+};
 use registry::AppRegistryImpl;
 use shared::{
     config::AppConfig,
@@ -63,7 +70,15 @@ async fn bootstrap() -> Result<()> {
     let app_config = AppConfig::new()?;
     let pool = connect_database_with(&app_config.database);
     let kv = Arc::new(RedisClient::new(&app_config.redis)?);
-    let registry = Arc::new(AppRegistryImpl::new(pool, kv, app_config));
+    let book_metadata = build_book_metadata_provider(&app_config.book_metadata)?;
+    let blob_storage = build_blob_storage(&app_config.blob_storage)?;
+    let registry = Arc::new(AppRegistryImpl::new(
+        pool,
+        kv,
+        book_metadata,
+        blob_storage,
+        app_config,
+    ));
 
     let app = Router::new()
         .merge(v1::routes())
@@ -86,21 +101,32 @@ async fn bootstrap() -> Result<()> {
     let listener = TcpListener::bind(&addr).await?;
 
     tracing::info!("Listening on {}", addr);
-    axum::serve(listener, app)
-        .await
-        .context("Unexpected error happened in server")
-        .inspect_err(|e| {
-            tracing::error!(
-                error.cause_chain = ?e,
-                error.message = %e,
-                "Unexpected error"
-            )
-        })
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .context("Unexpected error happened in server")
+    .inspect_err(|e| {
+        tracing::error!(
+            error.cause_chain = ?e,
+            error.message = %e,
+            "Unexpected error"
+        )
+    })
 }
 
 fn cors() -> CorsLayer {
     CorsLayer::new()
         .allow_headers(cors::Any)
-        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
+        .allow_methods([
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::PATCH,
+            Method::DELETE,
+        ])
+        // 蔵書の更新時に If-Match ヘッダに指定できるよう、ETag ヘッダを公開する
+        .expose_headers([header::ETAG])
         .allow_origin(cors::Any)
 }